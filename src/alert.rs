@@ -0,0 +1,419 @@
+//! Alert hooks: notify operators of events which require their attention
+//! (e.g. attempted double signs) by running a command or POSTing to a webhook
+
+mod command;
+mod webhook;
+
+use self::webhook::Url;
+use crate::{
+    chain,
+    config::{alert::AlertConfig, ValidatorConfig},
+    error::{Error, ErrorKind::ConfigError},
+    prelude::*,
+};
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    sync::Mutex,
+    thread,
+    time::{Duration, Instant},
+};
+use tendermint::consensus;
+
+/// Default timeout for delivering an alert
+pub const DEFAULT_TIMEOUT_SECS: u64 = 5;
+
+/// Default minimum interval between alerts for the same event
+pub const DEFAULT_RATE_LIMIT_SECS: u64 = 60;
+
+/// Default time a validator must be disconnected before alerting
+pub const DEFAULT_DISCONNECT_SECS: u64 = 60;
+
+/// Configured alert hooks
+static HOOKS: Lazy<Mutex<Vec<Hook>>> = Lazy::new(Default::default);
+
+/// Kinds of events which trigger alerts
+#[derive(Copy, Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum EventKind {
+    /// Refused to sign because it would be a double sign
+    #[serde(rename = "double_sign")]
+    DoubleSign,
+
+    /// Refused to sign because the height, round, or step regressed
+    #[serde(rename = "regression")]
+    Regression,
+
+    /// Refused to sign above the configured `max_height`
+    #[serde(rename = "exceed_max_height")]
+    ExceedMaxHeight,
+
+    /// Signing provider failed to produce a signature
+    #[serde(rename = "provider_failure")]
+    ProviderFailure,
+
+    /// Validator has been disconnected for longer than `disconnect_secs`
+    #[serde(rename = "disconnected")]
+    Disconnected,
+}
+
+/// Event sent to alert hooks (serialized as JSON)
+#[derive(Clone, Debug, Serialize)]
+pub struct Event {
+    /// Kind of event
+    pub kind: EventKind,
+
+    /// When the event occurred
+    pub timestamp: DateTime<Utc>,
+
+    /// Chain ID of the validator
+    pub chain_id: chain::Id,
+
+    /// Address of the validator
+    pub addr: String,
+
+    /// Description of the event
+    pub message: String,
+
+    /// Height, round, and step of the request which triggered the event
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub consensus_state: Option<consensus::State>,
+
+    /// How long the validator has been disconnected (in seconds)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disconnected_secs: Option<u64>,
+}
+
+impl Event {
+    /// Create a new event for the given validator
+    pub fn new(kind: EventKind, config: &ValidatorConfig, message: impl ToString) -> Self {
+        Self {
+            kind,
+            timestamp: Utc::now(),
+            chain_id: config.chain_id,
+            addr: config.addr.to_string(),
+            message: message.to_string(),
+            consensus_state: None,
+            disconnected_secs: None,
+        }
+    }
+
+    /// Set the consensus state of the request which triggered this event
+    pub fn with_consensus_state(mut self, consensus_state: consensus::State) -> Self {
+        self.consensus_state = Some(consensus_state);
+        self
+    }
+
+    /// Set how long the validator has been disconnected
+    pub fn with_disconnected_secs(mut self, disconnected_secs: u64) -> Self {
+        self.disconnected_secs = Some(disconnected_secs);
+        self
+    }
+}
+
+/// Where alerts are delivered
+#[derive(Clone, Debug)]
+enum Target {
+    /// Command to run with the event on stdin
+    Command(Vec<OsString>),
+
+    /// Webhook to POST the event to
+    Webhook(Url),
+}
+
+impl Target {
+    /// Deliver an event to this target
+    fn deliver(&self, event: &Event, timeout: Duration) -> Result<(), Error> {
+        match self {
+            Target::Command(cmd) => command::run(cmd, event, timeout),
+            Target::Webhook(url) => webhook::post(url, event, timeout),
+        }
+    }
+}
+
+/// A configured alert hook
+#[derive(Debug)]
+struct Hook {
+    /// Where to deliver alerts
+    target: Target,
+
+    /// Events this hook is interested in (`None` for all)
+    events: Option<Vec<EventKind>>,
+
+    /// Timeout for delivering an alert
+    timeout: Duration,
+
+    /// Minimum interval between alerts for the same event
+    rate_limit: Duration,
+
+    /// Time a validator must be disconnected before alerting (in seconds)
+    disconnect_secs: u64,
+
+    /// When alerts were last sent, keyed by event kind and `chain_id@addr`
+    last_sent: HashMap<(EventKind, String), Instant>,
+}
+
+impl Hook {
+    /// Create a hook from its configuration
+    fn new(config: &AlertConfig) -> Result<Self, Error> {
+        let target = match (&config.cmd, &config.url) {
+            (Some(cmd), None) => {
+                if cmd.is_empty() {
+                    fail!(ConfigError, "alert `cmd` must not be empty");
+                }
+
+                Target::Command(cmd.clone())
+            }
+            (None, Some(url)) => Target::Webhook(url.parse()?),
+            _ => fail!(ConfigError, "alerts need exactly one of `cmd` or `url`"),
+        };
+
+        Ok(Self {
+            target,
+            events: config.events.clone(),
+            timeout: Duration::from_secs(config.timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS)),
+            rate_limit: Duration::from_secs(
+                config.rate_limit_secs.unwrap_or(DEFAULT_RATE_LIMIT_SECS),
+            ),
+            disconnect_secs: config.disconnect_secs.unwrap_or(DEFAULT_DISCONNECT_SECS),
+            last_sent: HashMap::new(),
+        })
+    }
+
+    /// Should the given event be sent to this hook? Records the event as
+    /// sent if so.
+    fn should_send(&mut self, event: &Event, now: Instant) -> bool {
+        if let Some(events) = &self.events {
+            if !events.contains(&event.kind) {
+                return false;
+            }
+        }
+
+        if event.kind == EventKind::Disconnected
+            && event.disconnected_secs.unwrap_or(0) < self.disconnect_secs
+        {
+            return false;
+        }
+
+        let key = (event.kind, format!("{}@{}", &event.chain_id, &event.addr));
+
+        if let Some(last_sent) = self.last_sent.get(&key) {
+            if now.duration_since(*last_sent) < self.rate_limit {
+                debug!("[{}] rate limiting {:?} alert", &key.1, event.kind);
+                return false;
+            }
+        }
+
+        self.last_sent.insert(key, now);
+        true
+    }
+}
+
+/// Configure alert hooks, replacing any existing ones
+pub fn configure(configs: &[AlertConfig]) -> Result<(), Error> {
+    let hooks = configs
+        .iter()
+        .map(Hook::new)
+        .collect::<Result<Vec<_>, _>>()?;
+    *HOOKS.lock().unwrap() = hooks;
+    Ok(())
+}
+
+/// Send an event to all interested alert hooks.
+///
+/// Alerts are delivered in the background so as not to block signing.
+pub fn notify(event: Event) {
+    let now = Instant::now();
+
+    // TODO(tarcieri): better handle `PoisonError` here?
+    for hook in HOOKS.lock().unwrap().iter_mut() {
+        if !hook.should_send(&event, now) {
+            continue;
+        }
+
+        let target = hook.target.clone();
+        let timeout = hook.timeout;
+        let event = event.clone();
+
+        let result = thread::Builder::new()
+            .name("alert".to_owned())
+            .spawn(move || {
+                if let Err(e) = target.deliver(&event, timeout) {
+                    error!(
+                        chain_id = %event.chain_id,
+                        addr = %event.addr,
+                        error_kind = ?e.kind(),
+                        "[{}@{}] couldn't deliver {:?} alert: {}",
+                        &event.chain_id,
+                        &event.addr,
+                        event.kind,
+                        e
+                    );
+                }
+            });
+
+        if let Err(e) = result {
+            error!("couldn't spawn alert thread: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn validator_config() -> ValidatorConfig {
+        ValidatorConfig {
+            addr: "unix:///tmp/validator.sock".parse().unwrap(),
+            chain_id: "test-chain".parse().unwrap(),
+            reconnect: true,
+            timeout: None,
+            secret_key: None,
+            auto_generate_identity: false,
+            max_height: None,
+            peer_credentials: None,
+            secret_connection: None,
+        }
+    }
+
+    fn command_hook(config: AlertConfig) -> Hook {
+        Hook::new(&AlertConfig {
+            cmd: Some(vec!["true".into()]),
+            ..config
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_hook_config() {
+        assert!(Hook::new(&AlertConfig::default()).is_err());
+
+        assert!(Hook::new(&AlertConfig {
+            cmd: Some(vec![]),
+            ..AlertConfig::default()
+        })
+        .is_err());
+
+        assert!(Hook::new(&AlertConfig {
+            cmd: Some(vec!["true".into()]),
+            url: Some("http://127.0.0.1:9000/".to_owned()),
+            ..AlertConfig::default()
+        })
+        .is_err());
+
+        assert!(Hook::new(&AlertConfig {
+            url: Some("https://127.0.0.1:9000/".to_owned()),
+            ..AlertConfig::default()
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn test_event_filter() {
+        let config = validator_config();
+        let mut hook = command_hook(AlertConfig {
+            events: Some(vec![EventKind::DoubleSign]),
+            ..AlertConfig::default()
+        });
+
+        let now = Instant::now();
+        assert!(!hook.should_send(&Event::new(EventKind::Regression, &config, "test"), now));
+        assert!(hook.should_send(&Event::new(EventKind::DoubleSign, &config, "test"), now));
+    }
+
+    #[test]
+    fn test_rate_limit() {
+        let config = validator_config();
+        let mut hook = command_hook(AlertConfig {
+            rate_limit_secs: Some(60),
+            ..AlertConfig::default()
+        });
+
+        let event = Event::new(EventKind::DoubleSign, &config, "test");
+        let now = Instant::now();
+
+        assert!(hook.should_send(&event, now));
+        assert!(!hook.should_send(&event, now + Duration::from_secs(59)));
+        assert!(hook.should_send(&event, now + Duration::from_secs(61)));
+
+        // Rate limits are tracked separately per event kind
+        let other_event = Event::new(EventKind::Regression, &config, "test");
+        assert!(hook.should_send(&other_event, now + Duration::from_secs(61)));
+    }
+
+    #[test]
+    fn test_disconnect_threshold() {
+        let config = validator_config();
+        let mut hook = command_hook(AlertConfig {
+            disconnect_secs: Some(30),
+            ..AlertConfig::default()
+        });
+
+        let now = Instant::now();
+        let event = Event::new(EventKind::Disconnected, &config, "test");
+        assert!(!hook.should_send(&event.clone().with_disconnected_secs(29), now));
+        assert!(hook.should_send(&event.with_disconnected_secs(30), now));
+    }
+
+    #[test]
+    fn test_command_target() {
+        let dir = tempfile::tempdir().unwrap();
+        let output_path = dir.path().join("event.json");
+        let cmd = vec![
+            "sh".into(),
+            "-c".into(),
+            format!("cat > {}", output_path.display()).into(),
+        ];
+
+        let event = Event::new(EventKind::DoubleSign, &validator_config(), "double sign");
+        Target::Command(cmd)
+            .deliver(&event, Duration::from_secs(5))
+            .unwrap();
+
+        let output: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&output_path).unwrap()).unwrap();
+
+        assert_eq!(output["kind"], "double_sign");
+        assert_eq!(output["chain_id"], "test-chain");
+        assert_eq!(output["message"], "double sign");
+    }
+
+    #[test]
+    fn test_command_timeout() {
+        let cmd = vec!["sleep".into(), "10".into()];
+        let event = Event::new(EventKind::DoubleSign, &validator_config(), "double sign");
+        assert!(Target::Command(cmd)
+            .deliver(&event, Duration::from_millis(100))
+            .is_err());
+    }
+
+    #[test]
+    fn test_webhook_target() {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/alerts", server.server_addr());
+
+        let handle = thread::spawn(move || {
+            let mut request = server.recv().unwrap();
+            let mut body = String::new();
+            request.as_reader().read_to_string(&mut body).unwrap();
+            let path = request.url().to_owned();
+            request.respond(tiny_http::Response::empty(204)).unwrap();
+            (path, body)
+        });
+
+        let event = Event::new(EventKind::ExceedMaxHeight, &validator_config(), "too high");
+        Target::Webhook(url.parse().unwrap())
+            .deliver(&event, Duration::from_secs(5))
+            .unwrap();
+
+        let (path, body) = handle.join().unwrap();
+        let output: serde_json::Value = serde_json::from_str(&body).unwrap();
+
+        assert_eq!(path, "/alerts");
+        assert_eq!(output["kind"], "exceed_max_height");
+        assert_eq!(output["addr"], "unix:///tmp/validator.sock");
+    }
+}
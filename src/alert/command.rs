@@ -0,0 +1,47 @@
+//! Command alert hooks: run a command with a JSON event on stdin
+
+use super::Event;
+use crate::{
+    error::{Error, ErrorKind::AlertError},
+    prelude::*,
+};
+use std::{
+    ffi::OsString,
+    io::Write,
+    process::{Command, Stdio},
+    time::Duration,
+};
+use wait_timeout::ChildExt;
+
+/// Run the given command, writing the event to its stdin
+pub fn run(cmd: &[OsString], event: &Event, timeout: Duration) -> Result<(), Error> {
+    let event_json = serde_json::to_vec(event)?;
+
+    let mut child = Command::new(&cmd[0])
+        .args(&cmd[1..])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        // Commands which exit without reading the event are reported below
+        // via their exit status
+        let _ = stdin.write_all(&event_json);
+    }
+
+    match child.wait_timeout(timeout)? {
+        Some(status) => {
+            if status.success() {
+                Ok(())
+            } else {
+                fail!(AlertError, "command returned status {:?}", status.code())
+            }
+        }
+        None => {
+            // timeout
+            child.kill()?;
+            child.wait()?;
+            fail!(AlertError, "command timed out after {:?}", timeout)
+        }
+    }
+}
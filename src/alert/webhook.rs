@@ -0,0 +1,154 @@
+//! Webhook alert hooks: POST a JSON event to a (local) HTTP endpoint
+
+use super::Event;
+use crate::{
+    error::{
+        Error,
+        ErrorKind::{AlertError, ConfigError},
+    },
+    prelude::*,
+};
+use std::{
+    fmt::{self, Display},
+    io::{Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    str::FromStr,
+    time::Duration,
+};
+
+/// Webhook URL (`http://` only)
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Url {
+    /// Hostname or IP address
+    host: String,
+
+    /// TCP port
+    port: u16,
+
+    /// Request path
+    path: String,
+}
+
+impl FromStr for Url {
+    type Err = Error;
+
+    fn from_str(url: &str) -> Result<Self, Error> {
+        if !url.starts_with("http://") {
+            fail!(
+                ConfigError,
+                "unsupported webhook URL (only `http://` is supported): {}",
+                url
+            );
+        }
+
+        let rest = &url["http://".len()..];
+        let (authority, path) = match rest.find('/') {
+            Some(pos) => (&rest[..pos], &rest[pos..]),
+            None => (rest, "/"),
+        };
+
+        let (host, port) = match authority.rfind(':') {
+            Some(pos) => {
+                let port = authority[pos + 1..].parse().map_err(|_| {
+                    format_err!(ConfigError, "invalid port in webhook URL: {}", url)
+                })?;
+                (&authority[..pos], port)
+            }
+            None => (authority, 80),
+        };
+
+        if host.is_empty() {
+            fail!(ConfigError, "missing host in webhook URL: {}", url);
+        }
+
+        Ok(Self {
+            host: host.to_owned(),
+            port,
+            path: path.to_owned(),
+        })
+    }
+}
+
+impl Display for Url {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "http://{}:{}{}", self.host, self.port, self.path)
+    }
+}
+
+/// POST the event to the given webhook URL
+pub fn post(url: &Url, event: &Event, timeout: Duration) -> Result<(), Error> {
+    let body = serde_json::to_vec(event)?;
+    let mut socket = connect(url, timeout)?;
+    socket.set_read_timeout(Some(timeout))?;
+    socket.set_write_timeout(Some(timeout))?;
+
+    let request = format!(
+        "POST {} HTTP/1.1\r\n\
+         Host: {}:{}\r\n\
+         Content-Type: application/json\r\n\
+         Content-Length: {}\r\n\
+         Connection: close\r\n\r\n",
+        url.path,
+        url.host,
+        url.port,
+        body.len()
+    );
+
+    socket.write_all(request.as_bytes())?;
+    socket.write_all(&body)?;
+
+    let mut response = Vec::new();
+    socket.read_to_end(&mut response)?;
+
+    // Status line, e.g. `HTTP/1.1 200 OK`
+    let response = String::from_utf8_lossy(&response);
+    let status = response
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|code| code.parse::<u16>().ok())
+        .ok_or_else(|| format_err!(AlertError, "{}: malformed HTTP response", url))?;
+
+    if !(200..300).contains(&status) {
+        fail!(AlertError, "{}: webhook returned HTTP {}", url, status);
+    }
+
+    Ok(())
+}
+
+/// Connect to the webhook's host, trying each of its addresses in turn
+fn connect(url: &Url, timeout: Duration) -> Result<TcpStream, Error> {
+    let mut last_error = None;
+
+    for addr in (url.host.as_str(), url.port).to_socket_addrs()? {
+        match TcpStream::connect_timeout(&addr, timeout) {
+            Ok(socket) => return Ok(socket),
+            Err(e) => last_error = Some(e),
+        }
+    }
+
+    Err(match last_error {
+        Some(e) => AlertError.context(e).into(),
+        None => format_err!(AlertError, "{}: couldn't resolve host", url).into(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Url;
+
+    #[test]
+    fn parse_url() {
+        let url: Url = "http://127.0.0.1:9000/alerts".parse().unwrap();
+        assert_eq!(url.host, "127.0.0.1");
+        assert_eq!(url.port, 9000);
+        assert_eq!(url.path, "/alerts");
+
+        let url: Url = "http://localhost".parse().unwrap();
+        assert_eq!(url.port, 80);
+        assert_eq!(url.path, "/");
+
+        assert!("https://localhost/".parse::<Url>().is_err());
+        assert!("http://localhost:port/".parse::<Url>().is_err());
+    }
+}
@@ -1,10 +1,16 @@
 //! Abscissa `Application` for the KMS
 
-use crate::{commands::KmsCommand, config::KmsConfig};
+use crate::{
+    commands::KmsCommand,
+    config::KmsConfig,
+    logging::{JsonTracing, LogFormat},
+};
 use abscissa_core::{
     application::{self, AppCell},
-    trace, Application, FrameworkError, StandardPaths,
+    terminal::component::Terminal,
+    trace, Application, Component, FrameworkError, FrameworkErrorKind, StandardPaths,
 };
+use std::{error::Error as _, path::Path};
 
 /// Application state
 pub static APPLICATION: AppCell<KmsApplication> = AppCell::new();
@@ -78,10 +84,31 @@ impl Application for KmsApplication {
     /// beyond the default ones provided by the framework, this is the place
     /// to do so.
     fn register_components(&mut self, command: &Self::Cmd) -> Result<(), FrameworkError> {
-        let components = self.framework_components(command)?;
+        let components: Vec<Box<dyn Component<Self>>> = match LogFormat::for_command(command) {
+            LogFormat::Text => self.framework_components(command)?,
+            LogFormat::Json => vec![
+                Box::new(Terminal::new(self.term_colors(command))),
+                Box::new(JsonTracing::new(log_filter(command))?),
+            ],
+        };
+
         self.state.components.register(components)
     }
 
+    /// Load configuration from the given path, interpolating environment
+    /// variables and files.
+    fn load_config(&mut self, path: &Path) -> Result<Self::Cfg, FrameworkError> {
+        KmsConfig::load(path).map_err(|e| {
+            // Avoid repeating the error kind (i.e. "config error") in messages
+            let msg = e
+                .source()
+                .map(ToString::to_string)
+                .unwrap_or_else(|| e.to_string());
+
+            FrameworkErrorKind::ConfigError.context(msg).into()
+        })
+    }
+
     /// Post-configuration lifecycle callback.
     ///
     /// Called regardless of whether config is loaded to indicate this is the
@@ -95,10 +122,15 @@ impl Application for KmsApplication {
 
     /// Get tracing configuration from command-line options
     fn tracing_config(&self, command: &KmsCommand) -> trace::Config {
-        if command.verbose() {
-            trace::Config::verbose()
-        } else {
-            trace::Config::default()
-        }
+        trace::Config::from(log_filter(command).to_owned())
+    }
+}
+
+/// Get the tracing filter for the given command
+fn log_filter(command: &KmsCommand) -> &'static str {
+    if command.verbose() {
+        "debug"
+    } else {
+        "info"
     }
 }
@@ -15,7 +15,7 @@ use crate::{
     keyring::{self, KeyRing},
     prelude::*,
 };
-use std::{path::PathBuf, sync::Mutex};
+use std::sync::Mutex;
 pub use tendermint::chain::Id;
 
 /// Information about a particular Tendermint blockchain network
@@ -28,16 +28,16 @@ pub struct Chain {
 
     /// State from the last block signed for this chain
     pub state: Mutex<State>,
+
+    /// Lock on the state file, held for as long as we're signing for this chain
+    _state_lock: state::Lock,
 }
 
 impl Chain {
     /// Attempt to create a `Chain` state from the given configuration
     pub fn from_config(config: &ChainConfig) -> Result<Chain, Error> {
-        let state_file = match config.state_file {
-            Some(ref path) => path.to_owned(),
-            None => PathBuf::from(&format!("{}_priv_validator_state.json", config.id)),
-        };
-
+        let state_file = config.state_file_path();
+        let state_lock = state::Lock::acquire(&state_file)?;
         let mut state = State::load_state(state_file)?;
 
         if let Some(ref hook) = config.state_hook {
@@ -58,6 +58,7 @@ impl Chain {
             id: config.id,
             keyring: KeyRing::new(config.key_format.clone()),
             state: Mutex::new(state),
+            _state_lock: state_lock,
         })
     }
 }
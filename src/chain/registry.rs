@@ -52,6 +52,11 @@ impl Registry {
     pub fn get_chain(&self, chain_id: &Id) -> Option<&Chain> {
         self.0.get(chain_id)
     }
+
+    /// Iterate over all registered chains
+    pub fn chains(&self) -> impl Iterator<Item = &Chain> {
+        self.0.values()
+    }
 }
 
 /// Global registry of blockchain networks known to the KMS
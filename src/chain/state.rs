@@ -5,8 +5,14 @@
 
 mod error;
 pub mod hook;
+mod lock;
+mod priv_validator;
 
-pub use self::error::{StateError, StateErrorKind};
+pub use self::{
+    error::{StateError, StateErrorKind},
+    lock::Lock,
+    priv_validator::PrivValidatorState,
+};
 use crate::{
     error::{Error, ErrorKind::*},
     prelude::*,
@@ -127,6 +133,23 @@ impl State {
         Ok(())
     }
 
+    /// Overwrite the current consensus state, even if it moves backwards.
+    ///
+    /// This bypasses double signing protection, and is only intended for
+    /// manually repairing the state (i.e. `tmkms state set --force`).
+    pub fn overwrite_consensus_state(&mut self, new_state: consensus::State) -> Result<(), Error> {
+        self.consensus_state = new_state;
+        self.sync_to_disk().map_err(|e| {
+            format_err!(
+                IoError,
+                "error writing state to {}: {}",
+                self.state_file_path.display(),
+                e
+            )
+        })?;
+        Ok(())
+    }
+
     /// Update the internal state from the output from a hook command
     pub fn update_from_hook_output(&mut self, output: hook::Output) -> Result<(), StateError> {
         let hook_height = output.latest_block_height.value();
@@ -0,0 +1,80 @@
+//! Exclusive locks on chain state files, which prevent the state from being
+//! changed (e.g. by `tmkms state set`) while a KMS process is signing with it
+
+use crate::{
+    error::{
+        Error,
+        ErrorKind::{IoError, LockError},
+    },
+    prelude::*,
+};
+use nix::{
+    errno::Errno,
+    fcntl::{flock, FlockArg},
+};
+use std::{
+    fs::{File, OpenOptions},
+    os::unix::io::AsRawFd,
+    path::{Path, PathBuf},
+};
+
+/// Exclusive lock on a chain state file, released when dropped
+#[derive(Debug)]
+pub struct Lock {
+    /// Open lock file (holding the lock)
+    _file: File,
+
+    /// Path to the lock file
+    path: PathBuf,
+}
+
+impl Lock {
+    /// Acquire the lock for the given state file, failing immediately if
+    /// another process holds it
+    pub fn acquire(state_file: &Path) -> Result<Self, Error> {
+        let path = PathBuf::from(format!("{}.lock", state_file.display()));
+
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(false)
+            .open(&path)
+            .map_err(|e| format_err!(IoError, "couldn't open {}: {}", path.display(), e))?;
+
+        flock(file.as_raw_fd(), FlockArg::LockExclusiveNonblock).map_err(|e| match e {
+            nix::Error::Sys(Errno::EAGAIN) => format_err!(
+                LockError,
+                "{} is in use by another process (is the KMS running?)",
+                state_file.display()
+            ),
+            other => format_err!(IoError, "couldn't lock {}: {}", path.display(), other),
+        })?;
+
+        Ok(Self { _file: file, path })
+    }
+
+    /// Path to the lock file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exclusive_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        let state_file = dir.path().join("priv_validator_state.json");
+
+        let lock = Lock::acquire(&state_file).unwrap();
+        assert!(lock.path().exists());
+
+        let err = Lock::acquire(&state_file).unwrap_err();
+        assert_eq!(*err.kind(), LockError);
+
+        drop(lock);
+        Lock::acquire(&state_file).unwrap();
+    }
+}
@@ -0,0 +1,108 @@
+//! Tendermint's `priv_validator_state.json` format, used when importing and
+//! exporting chain state to/from Tendermint's built-in file signer
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use tendermint::{block, consensus};
+
+/// Last sign state in Tendermint's `priv_validator_state.json` format.
+///
+/// Note that Tendermint numbers its steps from 1 (propose = 1, prevote = 2,
+/// precommit = 3), whereas the KMS numbers them from 0.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PrivValidatorState {
+    /// Last signed height
+    pub height: block::Height,
+
+    /// Last signed round
+    #[serde(serialize_with = "serialize_round", deserialize_with = "parse_round")]
+    pub round: i64,
+
+    /// Last signed step
+    pub step: i8,
+
+    /// Last signature (base64)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+
+    /// Last signed bytes (hex)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signbytes: Option<String>,
+}
+
+impl From<&consensus::State> for PrivValidatorState {
+    fn from(state: &consensus::State) -> Self {
+        // A height of 0 means we've never signed anything
+        let step = if state.height.value() == 0 {
+            0
+        } else {
+            state.step + 1
+        };
+
+        Self {
+            height: state.height,
+            round: state.round,
+            step,
+            signature: None,
+            signbytes: None,
+        }
+    }
+}
+
+impl From<&PrivValidatorState> for consensus::State {
+    fn from(state: &PrivValidatorState) -> Self {
+        consensus::State {
+            height: state.height,
+            round: state.round,
+            step: if state.step > 0 { state.step - 1 } else { 0 },
+            block_id: None,
+        }
+    }
+}
+
+/// Serialize the round as a string (ala Amino JSON)
+fn serialize_round<S: Serializer>(round: &i64, serializer: S) -> Result<S::Ok, S::Error> {
+    round.to_string().serialize(serializer)
+}
+
+/// Parse the round from either a string or an integer
+fn parse_round<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i64, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Round {
+        String(String),
+        Integer(i64),
+    }
+
+    match Round::deserialize(deserializer)? {
+        Round::String(s) => s.parse().map_err(de::Error::custom),
+        Round::Integer(n) => Ok(n),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE_STATE: &str = r#"{
+      "height": "1234",
+      "round": "1",
+      "step": 3,
+      "signature": "ZXhhbXBsZQ==",
+      "signbytes": "6578616D706C65"
+    }"#;
+
+    #[test]
+    fn parse_priv_validator_state() {
+        let state: PrivValidatorState = serde_json::from_str(EXAMPLE_STATE).unwrap();
+        let consensus_state = consensus::State::from(&state);
+
+        assert_eq!(consensus_state.height.value(), 1234);
+        assert_eq!(consensus_state.round, 1);
+        assert_eq!(consensus_state.step, 2);
+
+        let exported = PrivValidatorState::from(&consensus_state);
+        assert_eq!(exported.height, state.height);
+        assert_eq!(exported.round, state.round);
+        assert_eq!(exported.step, state.step);
+    }
+}
@@ -6,11 +6,13 @@
 //! as a "Key Management System".
 
 use crate::{
+    alert::{self, EventKind},
     chain,
     config::ValidatorConfig,
     error::{Error, ErrorKind},
     prelude::*,
     session::Session,
+    status,
 };
 use std::{panic, process::exit, thread, time::Duration};
 
@@ -38,6 +40,7 @@ impl Client {
     /// Spawn a new client, returning a handle so it can be joined
     pub fn spawn(config: ValidatorConfig) -> Self {
         register_chain(&config.chain_id);
+        status::register_session(&config);
 
         let name = format!("{}@{}", &config.chain_id, &config.addr);
 
@@ -68,10 +71,39 @@ fn main_loop(config: ValidatorConfig) -> Result<(), Error> {
     while let Err(e) = run_client(config.clone()) {
         // `PoisonError` is unrecoverable
         if *e.kind() == ErrorKind::PoisonError {
-            error!("[{}@{}] FATAL -- {}", &config.chain_id, &config.addr, e);
+            error!(
+                chain_id = %config.chain_id,
+                addr = %config.addr,
+                error_kind = ?e.kind(),
+                "[{}@{}] FATAL -- {}",
+                &config.chain_id,
+                &config.addr,
+                e
+            );
             return Err(e);
         } else {
-            error!("[{}@{}] {}", &config.chain_id, &config.addr, e);
+            error!(
+                chain_id = %config.chain_id,
+                addr = %config.addr,
+                error_kind = ?e.kind(),
+                "[{}@{}] {}",
+                &config.chain_id,
+                &config.addr,
+                e
+            );
+        }
+
+        if let Some(disconnected_for) = status::session_disconnected_for(&config) {
+            let disconnected_secs = disconnected_for.as_secs();
+
+            alert::notify(
+                alert::Event::new(
+                    EventKind::Disconnected,
+                    &config,
+                    format!("disconnected for {} seconds: {}", disconnected_secs, e),
+                )
+                .with_disconnected_secs(disconnected_secs),
+            );
         }
 
         if config.reconnect {
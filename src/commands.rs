@@ -1,10 +1,14 @@
 //! Subcommands of the `tmkms` command-line application
 
+mod config;
+mod identity;
+mod init;
 #[cfg(feature = "ledgertm")]
 mod ledger;
 #[cfg(feature = "softsign")]
 mod softsign;
 mod start;
+mod state;
 mod version;
 #[cfg(feature = "yubihsm")]
 mod yubihsm;
@@ -16,7 +20,10 @@ pub use self::softsign::SoftsignCommand;
 #[cfg(feature = "yubihsm")]
 pub use self::yubihsm::YubihsmCommand;
 
-pub use self::{start::StartCommand, version::VersionCommand};
+pub use self::{
+    config::ConfigCommand, identity::IdentityCommand, init::InitCommand, start::StartCommand,
+    state::StateCommand, version::VersionCommand,
+};
 use crate::config::{KmsConfig, CONFIG_ENV_VAR, CONFIG_FILE_NAME};
 use abscissa_core::{Command, Configurable, Help, Options, Runnable};
 use std::{env, path::PathBuf};
@@ -24,14 +31,30 @@ use std::{env, path::PathBuf};
 /// Subcommands of the KMS command-line application
 #[derive(Command, Debug, Options, Runnable)]
 pub enum KmsCommand {
+    /// `config` subcommand
+    #[options(help = "inspect the KMS configuration")]
+    Config(ConfigCommand),
+
     /// `help` subcommand
     #[options(help = "show help for a command")]
     Help(Help<Self>),
 
+    /// `identity` subcommand
+    #[options(help = "manage the KMS's SecretConnection identity keys")]
+    Identity(IdentityCommand),
+
+    /// `init` subcommand
+    #[options(help = "generate a new KMS configuration")]
+    Init(InitCommand),
+
     /// `start` subcommand
     #[options(help = "start the KMS application")]
     Start(StartCommand),
 
+    /// `state` subcommand
+    #[options(help = "inspect and repair chain state (double signing protection)")]
+    State(StateCommand),
+
     /// `version` subcommand
     #[options(help = "display version information")]
     Version(VersionCommand),
@@ -69,7 +92,12 @@ impl Configurable<KmsConfig> for KmsCommand {
     /// or the default
     fn config_path(&self) -> Option<PathBuf> {
         let config = match self {
+            KmsCommand::Config(config) if config.uses_config() => config.config_path(),
+            KmsCommand::Identity(identity) if identity.uses_config() => identity.config_path(),
             KmsCommand::Start(start) => start.config.as_ref(),
+            #[cfg(feature = "softsign")]
+            KmsCommand::Softsign(softsign) if softsign.uses_config() => softsign.config_path(),
+            KmsCommand::State(state) if state.uses_config() => state.config_path(),
             #[cfg(feature = "yubihsm")]
             KmsCommand::Yubihsm(yubihsm) => yubihsm.config_path(),
             #[cfg(feature = "ledgertm")]
@@ -77,11 +105,15 @@ impl Configurable<KmsConfig> for KmsCommand {
             _ => return None,
         };
 
-        let path = config
-            .cloned()
-            .or_else(|| env::var(CONFIG_ENV_VAR).ok().map(PathBuf::from))
-            .unwrap_or_else(|| PathBuf::from(CONFIG_FILE_NAME));
-
-        Some(path)
+        Some(resolve_config_path(config))
     }
 }
+
+/// Get the path to the configuration file: either the given path, the path
+/// in the `TMKMS_CONFIG_FILE` environment variable, or the default
+pub(crate) fn resolve_config_path(config: Option<&PathBuf>) -> PathBuf {
+    config
+        .cloned()
+        .or_else(|| env::var(CONFIG_ENV_VAR).ok().map(PathBuf::from))
+        .unwrap_or_else(|| PathBuf::from(CONFIG_FILE_NAME))
+}
@@ -0,0 +1,39 @@
+//! `tmkms config` CLI (sub)commands: inspect the KMS configuration
+
+mod check;
+mod show;
+
+pub use self::{check::CheckCommand, show::ShowCommand};
+use abscissa_core::{Command, Help, Options, Runnable};
+use std::path::PathBuf;
+
+/// The `config` subcommand
+#[derive(Command, Debug, Options, Runnable)]
+pub enum ConfigCommand {
+    /// Check the configuration for problems
+    #[options(help = "check the configuration for problems")]
+    Check(CheckCommand),
+
+    /// Show help for the `config` subcommand
+    #[options(help = "show help for the 'config' subcommand")]
+    Help(Help<Self>),
+
+    /// Show the effective configuration
+    #[options(help = "show the configuration merged with included files (secrets redacted)")]
+    Show(ShowCommand),
+}
+
+impl ConfigCommand {
+    /// Does this command need the configuration file?
+    pub(super) fn uses_config(&self) -> bool {
+        !matches!(self, ConfigCommand::Help(_))
+    }
+
+    pub(super) fn config_path(&self) -> Option<&PathBuf> {
+        match self {
+            ConfigCommand::Check(check) => check.config.as_ref(),
+            ConfigCommand::Show(show) => show.config.as_ref(),
+            ConfigCommand::Help(_) => None,
+        }
+    }
+}
@@ -0,0 +1,340 @@
+//! `tmkms config check`: check the configuration for problems
+
+#[cfg(any(feature = "softsign", feature = "yubihsm"))]
+use crate::config::ProviderKeyConfig;
+use crate::{
+    application::app_config,
+    chain,
+    config::{KmsConfig, SecretKeyConfig},
+    prelude::*,
+};
+#[cfg(feature = "softsign")]
+use crate::{
+    config::{
+        password::Password,
+        provider::softsign::{KeyFormat, SoftPrivateKey},
+    },
+    error::Error,
+    keyring::ed25519::softsign::{self, encrypted::EncryptedKey},
+};
+#[cfg(feature = "softsign")]
+use abscissa_core::secret::Secret;
+use abscissa_core::{Command, Options, Runnable};
+use nix::unistd::{access, AccessFlags};
+use std::{
+    path::{Path, PathBuf},
+    process,
+};
+use tendermint::net;
+
+/// The `config check` subcommand
+#[derive(Command, Debug, Default, Options)]
+pub struct CheckCommand {
+    /// Path to configuration file
+    #[options(short = "c", long = "config", help = "path to tmkms.toml")]
+    pub config: Option<PathBuf>,
+}
+
+impl Runnable for CheckCommand {
+    /// Check the configuration, reporting all problems found
+    fn run(&self) {
+        let problems = check(&app_config());
+        let mut errors = 0;
+
+        for problem in &problems {
+            match problem {
+                Problem::Error(msg) => {
+                    status_err!("{}", msg);
+                    errors += 1;
+                }
+                Problem::Warning(msg) => status_warn!("{}", msg),
+            }
+        }
+
+        let warnings = problems.len() - errors;
+
+        if errors > 0 {
+            status_err!("found {} error(s) and {} warning(s)", errors, warnings);
+            process::exit(1);
+        }
+
+        status_ok!(
+            "Checked",
+            "configuration is valid ({} warning(s))",
+            warnings
+        );
+    }
+}
+
+/// Problems found in the configuration
+#[derive(Debug)]
+enum Problem {
+    /// Problems which will prevent the KMS from running correctly
+    Error(String),
+
+    /// Potential problems (e.g. insecure settings)
+    Warning(String),
+}
+
+/// Check the given configuration, returning all problems found
+fn check(config: &KmsConfig) -> Vec<Problem> {
+    let mut problems = vec![];
+    check_validators(config, &mut problems);
+    check_signing_keys(config, &mut problems);
+    check_state_files(config, &mut problems);
+    problems
+}
+
+/// Check validators refer to known chains and have usable identity keys
+fn check_validators(config: &KmsConfig, problems: &mut Vec<Problem>) {
+    for validator in &config.validator {
+        let name = format!("validator {} ({})", validator.addr, validator.chain_id);
+
+        if !is_configured_chain(config, &validator.chain_id) {
+            problems.push(Problem::Error(format!(
+                "{}: unknown chain {} (add it to tmkms.toml's [[chain]] section)",
+                name, validator.chain_id
+            )));
+        }
+
+        let needs_secret_key = match &validator.addr {
+            net::Address::Tcp { peer_id, .. } => {
+                if validator.peer_credentials.is_some() || validator.secret_connection.is_some() {
+                    problems.push(Problem::Error(format!(
+                        "{}: `peer_credentials` and `secret_connection` are only \
+                         supported on `unix://` sockets",
+                        name
+                    )));
+                }
+
+                if peer_id.is_none() {
+                    problems.push(Problem::Warning(format!(
+                        "{}: no peer ID (the validator's identity won't be verified; \
+                         use `tcp://<node ID>@host:port`)",
+                        name
+                    )));
+                }
+
+                true
+            }
+            net::Address::Unix { .. } => match &validator.secret_connection {
+                Some(secret_connection) => {
+                    if secret_connection.peer_id.is_none() {
+                        problems.push(Problem::Warning(format!(
+                            "{}: no `secret_connection.peer_id` (the validator's \
+                             identity won't be verified)",
+                            name
+                        )));
+                    }
+
+                    true
+                }
+                None => false,
+            },
+        };
+
+        match &validator.secret_key {
+            Some(secret_key) if needs_secret_key => check_secret_key(
+                secret_key,
+                validator.auto_generate_identity,
+                &name,
+                problems,
+            ),
+            Some(_) => (),
+            None if needs_secret_key => problems.push(Problem::Error(format!(
+                "{}: no `secret_key` configured",
+                name
+            ))),
+            None => (),
+        }
+    }
+}
+
+/// Check a validator's identity key can be loaded (or generated)
+fn check_secret_key(
+    secret_key: &SecretKeyConfig,
+    auto_generate: bool,
+    name: &str,
+    problems: &mut Vec<Problem>,
+) {
+    match secret_key {
+        SecretKeyConfig::Path(path) if auto_generate && !path.exists() => {
+            check_writable_dir(&parent_dir(path), name, problems)
+        }
+        // Checking keys stored in the YubiHSM requires connecting to it
+        #[cfg(feature = "yubihsm")]
+        SecretKeyConfig::Provider(ProviderKeyConfig::Yubihsm { .. }) => (),
+        #[cfg(feature = "softsign")]
+        SecretKeyConfig::Provider(ProviderKeyConfig::SoftSign {
+            key_format,
+            path,
+            password,
+        }) => {
+            if let Err(e) =
+                check_softsign_key(path, key_format.unwrap_or_default(), password.as_ref())
+            {
+                problems.push(Problem::Error(format!("{}: {}", name, e)));
+            }
+        }
+        _ => {
+            if let Err(e) = secret_key.load(false) {
+                problems.push(Problem::Error(format!("{}: {}", name, e)));
+            }
+        }
+    }
+}
+
+/// Check signing keys refer to known chains and every chain has a key
+fn check_signing_keys(config: &KmsConfig, problems: &mut Vec<Problem>) {
+    let signing_keys = signing_keys(config, problems);
+
+    for (name, chain_id) in &signing_keys {
+        if !is_configured_chain(config, chain_id) {
+            problems.push(Problem::Error(format!(
+                "{}: unknown chain {} (add it to tmkms.toml's [[chain]] section)",
+                name, chain_id
+            )));
+        }
+    }
+
+    for chain in &config.chain {
+        if !signing_keys
+            .iter()
+            .any(|(_, chain_id)| *chain_id == chain.id)
+        {
+            problems.push(Problem::Error(format!(
+                "chain {}: no signing keys (add it to a provider's `chain_ids`)",
+                chain.id
+            )));
+        }
+    }
+}
+
+/// Collect the chain IDs each signing key is authorized for, checking key
+/// and password files along the way
+#[allow(unused_variables, unused_mut)]
+fn signing_keys(config: &KmsConfig, problems: &mut Vec<Problem>) -> Vec<(String, chain::Id)> {
+    let mut signing_keys = vec![];
+
+    #[cfg(feature = "softsign")]
+    check_provider_count("softsign", config.providers.softsign.len(), problems);
+
+    #[cfg(feature = "softsign")]
+    for softsign in &config.providers.softsign {
+        let name = format!("softsign key {}", softsign.path.as_ref().display());
+
+        if let Err(e) = check_softsign_key(
+            &softsign.path,
+            softsign.key_format.unwrap_or_default(),
+            softsign.password.as_ref(),
+        ) {
+            problems.push(Problem::Error(format!("{}: {}", name, e)));
+        }
+
+        for chain_id in &softsign.chain_ids {
+            signing_keys.push((name.clone(), *chain_id));
+        }
+    }
+
+    #[cfg(feature = "yubihsm")]
+    check_provider_count("yubihsm", config.providers.yubihsm.len(), problems);
+
+    #[cfg(feature = "yubihsm")]
+    for yubihsm in &config.providers.yubihsm {
+        if let Err(e) = yubihsm.auth.check() {
+            problems.push(Problem::Error(e.to_string()));
+        }
+
+        for key in &yubihsm.keys {
+            for chain_id in &key.chain_ids {
+                signing_keys.push((format!("yubihsm key {}", key.key), *chain_id));
+            }
+        }
+    }
+
+    #[cfg(feature = "ledgertm")]
+    check_provider_count("ledgertm", config.providers.ledgertm.len(), problems);
+
+    #[cfg(feature = "ledgertm")]
+    for ledgertm in &config.providers.ledgertm {
+        for chain_id in &ledgertm.chain_ids {
+            signing_keys.push(("ledgertm key".to_owned(), *chain_id));
+        }
+    }
+
+    signing_keys
+}
+
+/// Check there's at most one provider section of the given type, as
+/// `tmkms start` rejects more than one
+#[allow(dead_code)]
+fn check_provider_count(provider: &str, count: usize, problems: &mut Vec<Problem>) {
+    if count > 1 {
+        problems.push(Problem::Error(format!(
+            "found {} [[providers.{}]] sections (at most one is supported; \
+             list every chain in its `chain_ids` instead)",
+            count, provider
+        )));
+    }
+}
+
+/// Check a softsign key can be loaded (without prompting for the passwords
+/// of encrypted keys)
+#[cfg(feature = "softsign")]
+fn check_softsign_key(
+    path: &SoftPrivateKey,
+    key_format: KeyFormat,
+    password: Option<&Secret<Password>>,
+) -> Result<(), Error> {
+    match (key_format, password) {
+        (KeyFormat::Encrypted, None) => EncryptedKey::read(path.as_ref()).map(|_| ()),
+        _ => softsign::load_seed(path, key_format, password).map(|_| ()),
+    }
+}
+
+/// Check chain state files (or their directories) are writable
+fn check_state_files(config: &KmsConfig, problems: &mut Vec<Problem>) {
+    for chain in &config.chain {
+        let name = format!("chain {}", chain.id);
+        let state_file = chain.state_file_path();
+
+        if state_file.exists() {
+            if let Err(e) = access(&state_file, AccessFlags::R_OK | AccessFlags::W_OK) {
+                problems.push(Problem::Error(format!(
+                    "{}: state file {} isn't writable: {}",
+                    name,
+                    state_file.display(),
+                    e
+                )));
+            }
+        } else {
+            check_writable_dir(&parent_dir(&state_file), &name, problems);
+        }
+    }
+}
+
+/// Check that files can be created in the given directory
+fn check_writable_dir(dir: &Path, name: &str, problems: &mut Vec<Problem>) {
+    if let Err(e) = access(dir, AccessFlags::W_OK | AccessFlags::X_OK) {
+        problems.push(Problem::Error(format!(
+            "{}: directory {} isn't writable: {}",
+            name,
+            dir.display(),
+            e
+        )));
+    }
+}
+
+/// Directory containing the given path (i.e. the current directory for
+/// relative paths without a parent)
+fn parent_dir(path: &Path) -> PathBuf {
+    match path.parent() {
+        Some(parent) if parent != Path::new("") => parent.to_owned(),
+        _ => PathBuf::from("."),
+    }
+}
+
+/// Is the given chain ID configured in a `[[chain]]` section?
+fn is_configured_chain(config: &KmsConfig, chain_id: &chain::Id) -> bool {
+    config.chain.iter().any(|chain| chain.id == *chain_id)
+}
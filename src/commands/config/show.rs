@@ -0,0 +1,90 @@
+//! `tmkms config show`: show the effective configuration
+
+use crate::{
+    commands::resolve_config_path,
+    config::{
+        include::{self, ConfigFile},
+        interpolate::{interpolate, FILE_PREFIX},
+    },
+    error::Error,
+    prelude::*,
+};
+use abscissa_core::{Command, Options, Runnable};
+use std::{
+    path::{Path, PathBuf},
+    process,
+};
+use toml::Value;
+
+/// Placeholder for redacted values
+const REDACTED: &str = "<redacted>";
+
+/// Keys whose values are always secret
+const SECRET_KEYS: &[&str] = &["password"];
+
+/// The `config show` subcommand
+#[derive(Command, Debug, Default, Options)]
+pub struct ShowCommand {
+    /// Path to configuration file
+    #[options(short = "c", long = "config", help = "path to tmkms.toml")]
+    pub config: Option<PathBuf>,
+}
+
+impl Runnable for ShowCommand {
+    /// Print the configuration merged with any included files, with secrets
+    /// (i.e. passwords and values read from files) redacted
+    fn run(&self) {
+        let config_path = resolve_config_path(self.config.as_ref());
+
+        let config = merged_config(&config_path).unwrap_or_else(|e| {
+            status_err!("{}", e);
+            process::exit(1);
+        });
+
+        let toml_string = toml::to_string_pretty(&config).unwrap_or_else(|e| {
+            status_err!("couldn't serialize config: {}", e);
+            process::exit(1);
+        });
+
+        print!("{}", toml_string);
+    }
+}
+
+/// Read the configuration along with any included files, merging them and
+/// interpolating everything except secrets
+fn merged_config(config_path: &Path) -> Result<Value, Error> {
+    let config_file = ConfigFile::read(config_path)?;
+    let mut config = config_file.value.clone();
+
+    for included_file in config_file.read_included()? {
+        include::merge(&mut config, included_file.value);
+    }
+
+    if let Some(table) = config.as_table_mut() {
+        table.remove("include");
+    }
+
+    redact(&mut config);
+    interpolate(&mut config)?;
+    Ok(config)
+}
+
+/// Redact secret values
+fn redact(value: &mut Value) {
+    match value {
+        Value::String(string) if string.starts_with(FILE_PREFIX) => {
+            *string = REDACTED.to_owned();
+        }
+        Value::Array(array) => array.iter_mut().for_each(redact),
+        Value::Table(table) => {
+            for (key, value) in table.iter_mut() {
+                if SECRET_KEYS.contains(&key.as_str()) {
+                    *value = Value::String(REDACTED.to_owned());
+                } else {
+                    redact(value);
+                }
+            }
+        }
+        _ => (),
+    }
+}
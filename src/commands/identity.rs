@@ -0,0 +1,41 @@
+//! `tmkms identity` CLI (sub)commands
+
+mod generate;
+mod show;
+
+pub use self::{generate::GenerateCommand, show::ShowCommand};
+use abscissa_core::{Command, Help, Options, Runnable};
+use std::path::PathBuf;
+
+/// The `identity` subcommand
+#[derive(Command, Debug, Options, Runnable)]
+pub enum IdentityCommand {
+    /// Generate a SecretConnection identity key
+    #[options(help = "generate a SecretConnection identity key")]
+    Generate(GenerateCommand),
+
+    /// Show help for the `identity` subcommand
+    #[options(help = "show help for the 'identity' subcommand")]
+    Help(Help<Self>),
+
+    /// Show the node ID of SecretConnection identity keys
+    #[options(help = "show the node ID of SecretConnection identity keys")]
+    Show(ShowCommand),
+}
+
+impl IdentityCommand {
+    /// Does this command need the configuration file?
+    pub(super) fn uses_config(&self) -> bool {
+        match self {
+            IdentityCommand::Show(show) => show.uses_config(),
+            _ => false,
+        }
+    }
+
+    pub(super) fn config_path(&self) -> Option<&PathBuf> {
+        match self {
+            IdentityCommand::Show(show) => show.config.as_ref(),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,48 @@
+//! `tmkms identity generate` subcommand
+
+use crate::{connection::secret_connection, keyring::SecretKeyEncoding, prelude::*};
+use abscissa_core::{Command, Options, Runnable};
+use signatory::{ed25519, encoding::Encode, public_key::PublicKeyed};
+use signatory_dalek::Ed25519Signer;
+use std::{path::PathBuf, process};
+
+/// `identity generate` command
+#[derive(Command, Debug, Default, Options)]
+pub struct GenerateCommand {
+    #[options(free, help = "path where the identity key should be created")]
+    output_paths: Vec<PathBuf>,
+}
+
+impl Runnable for GenerateCommand {
+    /// Generate an Ed25519 identity key for authenticating to validators
+    fn run(&self) {
+        if self.output_paths.len() != 1 {
+            eprintln!("Usage: tmkms identity generate [PATH]");
+            process::exit(1);
+        }
+
+        let output_path = &self.output_paths[0];
+
+        // Never clobber an existing identity, as doing so changes our node ID
+        if output_path.exists() {
+            status_err!("{} already exists!", output_path.display());
+            process::exit(1);
+        }
+
+        let seed = ed25519::Seed::generate();
+        seed.encode_to_file(output_path, &SecretKeyEncoding::default())
+            .unwrap_or_else(|e| {
+                status_err!("couldn't write to {}: {}", output_path.display(), e);
+                process::exit(1);
+            });
+
+        let public_key = Ed25519Signer::from(&seed).public_key().unwrap();
+
+        status_ok!(
+            "Generated",
+            "identity key at {} (node ID: {})",
+            output_path.display(),
+            secret_connection::PublicKey::from(public_key).peer_id()
+        );
+    }
+}
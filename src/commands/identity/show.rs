@@ -0,0 +1,67 @@
+//! `tmkms identity show` subcommand
+
+use crate::{
+    application::app_config, config::SecretKeyConfig, connection::secret_connection, prelude::*,
+};
+use abscissa_core::{Command, Options, Runnable};
+use std::{path::PathBuf, process};
+
+/// `identity show` command
+#[derive(Command, Debug, Default, Options)]
+pub struct ShowCommand {
+    /// Path to configuration file
+    #[options(short = "c", long = "config", help = "path to tmkms.toml")]
+    pub config: Option<PathBuf>,
+
+    #[options(free, help = "path to an identity key (default: keys in tmkms.toml)")]
+    key_paths: Vec<PathBuf>,
+}
+
+impl ShowCommand {
+    /// Are we showing the keys in the config file?
+    pub(super) fn uses_config(&self) -> bool {
+        self.key_paths.is_empty()
+    }
+}
+
+impl Runnable for ShowCommand {
+    /// Print the node IDs of SecretConnection identity keys
+    fn run(&self) {
+        match self.key_paths.len() {
+            0 => show_configured_keys(),
+            1 => {
+                let secret_key = SecretKeyConfig::Path(self.key_paths[0].clone());
+                println!("{}", node_id(&secret_key));
+            }
+            _ => {
+                eprintln!("Usage: tmkms identity show [-c tmkms.toml] [PATH]");
+                process::exit(1);
+            }
+        }
+    }
+}
+
+/// Show the node ID of each validator's identity key in the configuration
+fn show_configured_keys() {
+    let config = app_config();
+
+    for validator in &config.validator {
+        let node_id = match &validator.secret_key {
+            Some(secret_key) => node_id(secret_key).to_string(),
+            None => "(no secret_key configured)".to_owned(),
+        };
+
+        let addr = format!("- {}", &validator.addr);
+        status_attr_ok!(addr, node_id);
+    }
+}
+
+/// Load the given identity key (without generating it), returning its node ID
+fn node_id(secret_key: &SecretKeyConfig) -> tendermint::node::Id {
+    let (public_key, _) = secret_key.load(false).unwrap_or_else(|e| {
+        status_err!("{}", e);
+        process::exit(1);
+    });
+
+    secret_connection::PublicKey::from(public_key).peer_id()
+}
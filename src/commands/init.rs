@@ -0,0 +1,436 @@
+//! `tmkms init` subcommand: generate a new KMS configuration
+
+mod networks;
+
+use self::networks::{Network, NETWORKS};
+use crate::{
+    chain::{self, state::State},
+    config::{SecretKeyConfig, CONFIG_FILE_NAME},
+    connection::secret_connection,
+    keyring,
+    prelude::*,
+};
+use abscissa_core::{Command, Options, Runnable};
+use std::{
+    fmt::Write,
+    fs::{self, DirBuilder},
+    os::unix::fs::DirBuilderExt,
+    path::{Path, PathBuf},
+    process,
+};
+use tendermint::net;
+
+/// Default validator address
+const DEFAULT_VALIDATOR_ADDR: &str = "tcp://127.0.0.1:26658";
+
+/// Name of the SecretConnection identity key file
+const IDENTITY_KEY_FILE: &str = "kms-identity.key";
+
+/// Name of the softsign consensus key file (shared by all chains)
+const SOFTSIGN_KEY_FILE: &str = "consensus.key";
+
+/// `init` command
+#[derive(Command, Debug, Default, Options)]
+pub struct InitCommand {
+    /// Networks to configure
+    #[options(
+        short = "n",
+        long = "network",
+        help = "network preset or chain ID to configure (repeatable, default: cosmoshub)"
+    )]
+    networks: Vec<String>,
+
+    /// Signing provider to enable
+    #[options(
+        short = "p",
+        long = "provider",
+        help = "signing provider to enable (yubihsm, ledgertm, or softsign)"
+    )]
+    provider: Option<String>,
+
+    /// Validator address
+    #[options(
+        short = "a",
+        long = "addr",
+        help = "address of the validator to connect to (default: tcp://127.0.0.1:26658)"
+    )]
+    addr: Option<String>,
+
+    #[options(free, help = "directory to create the configuration in")]
+    output_paths: Vec<PathBuf>,
+}
+
+impl Runnable for InitCommand {
+    /// Generate a new KMS configuration
+    fn run(&self) {
+        if self.output_paths.len() != 1 {
+            eprintln!(
+                "Usage: tmkms init [-n NETWORK]... [-p PROVIDER] [-a ADDR] DIR\n\n\
+                 Network presets: {}",
+                NETWORKS
+                    .iter()
+                    .map(|network| network.name)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+            process::exit(1);
+        }
+
+        let chains = self.chains();
+        let provider = self.provider();
+        let addr = self.addr();
+
+        let output_dir = &self.output_paths[0];
+
+        if output_dir.join(CONFIG_FILE_NAME).exists() {
+            status_err!(
+                "{} already exists!",
+                output_dir.join(CONFIG_FILE_NAME).display()
+            );
+            process::exit(1);
+        }
+
+        let output_dir = create_dirs(output_dir);
+        let config_path = output_dir.join(CONFIG_FILE_NAME);
+        let identity_key_path = output_dir.join("secrets").join(IDENTITY_KEY_FILE);
+
+        // Generates the identity key if it doesn't already exist
+        let node_id = SecretKeyConfig::Path(identity_key_path.clone())
+            .load(true)
+            .map(|(public_key, _)| secret_connection::PublicKey::from(public_key).peer_id())
+            .unwrap_or_else(|e| {
+                status_err!("{}", e);
+                process::exit(1);
+            });
+
+        status_ok!(
+            "Generated",
+            "identity key at {} (node ID: {})",
+            identity_key_path.display(),
+            node_id
+        );
+
+        for (chain_id, _) in &chains {
+            let state_file_path = state_file_path(&output_dir, chain_id);
+
+            // Writes the initial state if the file doesn't already exist
+            State::load_state(&state_file_path).unwrap_or_else(|e| {
+                status_err!("couldn't create {}: {}", state_file_path.display(), e);
+                process::exit(1);
+            });
+        }
+
+        let config = render_config(&output_dir, &chains, &addr, provider);
+
+        fs::write(&config_path, config).unwrap_or_else(|e| {
+            status_err!("couldn't write {}: {}", config_path.display(), e);
+            process::exit(1);
+        });
+
+        status_ok!(
+            "Generated",
+            "KMS configuration at {}",
+            config_path.display()
+        );
+
+        for step in next_steps(&output_dir, provider) {
+            status_info!("Next", "{}", step);
+        }
+    }
+}
+
+impl InitCommand {
+    /// Get the chains to configure along with their key formats
+    fn chains(&self) -> Vec<(chain::Id, keyring::Format)> {
+        if self.networks.is_empty() {
+            let network = Network::find("cosmoshub").unwrap();
+            return vec![(network.chain_id(), network.key_format())];
+        }
+
+        self.networks
+            .iter()
+            .map(|name| match Network::find(name) {
+                Some(network) => (network.chain_id(), network.key_format()),
+                None => {
+                    let chain_id = name.parse().unwrap_or_else(|e| {
+                        status_err!("invalid chain ID '{}': {}", name, e);
+                        process::exit(1);
+                    });
+
+                    status_warn!(
+                        "no preset for '{}': using the hex key format (edit `key_format` \
+                         to use Bech32 prefixes)",
+                        name
+                    );
+
+                    (chain_id, keyring::Format::Hex)
+                }
+            })
+            .collect()
+    }
+
+    /// Get the signing provider to enable
+    fn provider(&self) -> Provider {
+        let compiled = Provider::compiled();
+
+        let provider = match &self.provider {
+            Some(name) => Provider::from_name(name).unwrap_or_else(|| {
+                status_err!("unknown signing provider: {}", name);
+                process::exit(1);
+            }),
+            None => compiled[0],
+        };
+
+        if !compiled.contains(&provider) {
+            status_err!(
+                "the `{}` provider is not enabled in this build of tmkms",
+                provider.name()
+            );
+            process::exit(1);
+        }
+
+        provider
+    }
+
+    /// Get the validator address
+    fn addr(&self) -> String {
+        let addr = self
+            .addr
+            .clone()
+            .unwrap_or_else(|| DEFAULT_VALIDATOR_ADDR.to_owned());
+
+        if let Err(e) = addr.parse::<net::Address>() {
+            status_err!("invalid validator address '{}': {}", addr, e);
+            process::exit(1);
+        }
+
+        addr
+    }
+}
+
+/// Signing providers
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Provider {
+    /// YubiHSM2
+    Yubihsm,
+
+    /// Ledger
+    Ledgertm,
+
+    /// Software signer
+    Softsign,
+}
+
+impl Provider {
+    /// Providers compiled into this build, most recommended first
+    fn compiled() -> Vec<Provider> {
+        vec![
+            #[cfg(feature = "yubihsm")]
+            Provider::Yubihsm,
+            #[cfg(feature = "ledgertm")]
+            Provider::Ledgertm,
+            #[cfg(feature = "softsign")]
+            Provider::Softsign,
+        ]
+    }
+
+    /// Parse a provider from its name
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "yubihsm" => Some(Provider::Yubihsm),
+            "ledgertm" | "ledger" => Some(Provider::Ledgertm),
+            "softsign" => Some(Provider::Softsign),
+            _ => None,
+        }
+    }
+
+    /// Name of this provider
+    fn name(self) -> &'static str {
+        match self {
+            Provider::Yubihsm => "yubihsm",
+            Provider::Ledgertm => "ledgertm",
+            Provider::Softsign => "softsign",
+        }
+    }
+}
+
+/// Create the output directory along with its `secrets` and `state`
+/// subdirectories, returning its canonical path
+fn create_dirs(output_dir: &Path) -> PathBuf {
+    let mut dir_builder = DirBuilder::new();
+    dir_builder.recursive(true);
+
+    for dir in &[output_dir.to_owned(), output_dir.join("state")] {
+        dir_builder.create(dir).unwrap_or_else(|e| {
+            status_err!("couldn't create {}: {}", dir.display(), e);
+            process::exit(1);
+        });
+    }
+
+    // Only the KMS user should be able to read secrets
+    let secrets_dir = output_dir.join("secrets");
+    dir_builder
+        .mode(0o700)
+        .create(&secrets_dir)
+        .unwrap_or_else(|e| {
+            status_err!("couldn't create {}: {}", secrets_dir.display(), e);
+            process::exit(1);
+        });
+
+    fs::canonicalize(output_dir).unwrap_or_else(|e| {
+        status_err!("couldn't canonicalize {}: {}", output_dir.display(), e);
+        process::exit(1);
+    })
+}
+
+/// Render the KMS configuration file
+fn render_config(
+    output_dir: &Path,
+    chains: &[(chain::Id, keyring::Format)],
+    addr: &str,
+    provider: Provider,
+) -> String {
+    let secrets_dir = output_dir.join("secrets");
+    let mut config = String::new();
+
+    writeln!(
+        config,
+        "# KMS configuration generated by `tmkms init`\n\
+         #\n\
+         # See `tmkms.toml.example` in the tmkms source code for all options\n"
+    )
+    .unwrap();
+
+    writeln!(config, "## Chain configuration\n").unwrap();
+
+    for (chain_id, key_format) in chains {
+        let key_format = match key_format {
+            keyring::Format::Bech32 {
+                account_key_prefix,
+                consensus_key_prefix,
+            } => format!(
+                "{{ type = \"bech32\", account_key_prefix = \"{}\", consensus_key_prefix = \"{}\" }}",
+                account_key_prefix, consensus_key_prefix
+            ),
+            keyring::Format::Hex => "{ type = \"hex\" }".to_owned(),
+        };
+
+        writeln!(
+            config,
+            "[[chain]]\n\
+             id = \"{}\"\n\
+             key_format = {}\n\
+             state_file = \"{}\"\n",
+            chain_id,
+            key_format,
+            state_file_path(output_dir, chain_id).display()
+        )
+        .unwrap();
+    }
+
+    writeln!(config, "## Validator configuration\n").unwrap();
+
+    for (chain_id, _) in chains {
+        writeln!(
+            config,
+            "[[validator]]\n\
+             chain_id = \"{}\"\n\
+             addr = \"{}\" # the validator's `priv_validator_laddr`\n\
+             secret_key = \"{}\"\n\
+             reconnect = true\n",
+            chain_id,
+            addr,
+            secrets_dir.join(IDENTITY_KEY_FILE).display()
+        )
+        .unwrap();
+    }
+
+    writeln!(config, "## Signing provider configuration").unwrap();
+
+    let chain_ids = chains
+        .iter()
+        .map(|(chain_id, _)| format!("\"{}\"", chain_id))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    for compiled_provider in Provider::compiled() {
+        let stanza = match compiled_provider {
+            Provider::Yubihsm => format!(
+                "[[providers.yubihsm]]\n\
+                 adapter = {{ type = \"usb\" }}\n\
+                 auth = {{ key = 1, password_file = \"{}\" }}\n\
+                 keys = [{{ chain_ids = [{}], key = 1 }}]\n",
+                secrets_dir.join("yubihsm-password").display(),
+                chain_ids
+            ),
+            Provider::Ledgertm => format!(
+                "[[providers.ledgertm]]\n\
+                 chain_ids = [{}]\n",
+                chain_ids
+            ),
+            Provider::Softsign => format!(
+                "[[providers.softsign]]\n\
+                 chain_ids = [{}]\n\
+                 key_format = \"base64\"\n\
+                 path = \"{}\"\n",
+                chain_ids,
+                secrets_dir.join(SOFTSIGN_KEY_FILE).display()
+            ),
+        };
+
+        writeln!(config).unwrap();
+
+        // Providers other than the selected one are included commented-out
+        for line in stanza.lines() {
+            if compiled_provider == provider || line.is_empty() {
+                writeln!(config, "{}", line).unwrap();
+            } else {
+                writeln!(config, "#{}", line).unwrap();
+            }
+        }
+    }
+
+    config
+}
+
+/// Path to the state file for the given chain
+fn state_file_path(output_dir: &Path, chain_id: &chain::Id) -> PathBuf {
+    output_dir
+        .join("state")
+        .join(format!("{}_priv_validator_state.json", chain_id))
+}
+
+/// Steps the operator needs to take to finish setting up the KMS
+fn next_steps(output_dir: &Path, provider: Provider) -> Vec<String> {
+    let config_path = output_dir.join(CONFIG_FILE_NAME);
+    let mut steps = vec![format!(
+        "edit the validator `addr` settings in {}",
+        config_path.display()
+    )];
+
+    match provider {
+        Provider::Yubihsm => {
+            steps.push(format!(
+                "write the YubiHSM2 auth key password to {}",
+                output_dir.join("secrets/yubihsm-password").display()
+            ));
+            steps.push(format!(
+                "provision the YubiHSM2 with `tmkms yubihsm setup -c {}`",
+                config_path.display()
+            ));
+        }
+        Provider::Ledgertm => {
+            steps.push("open the Tendermint validator app on your Ledger device".to_owned())
+        }
+        Provider::Softsign => steps.push(format!(
+            "generate or import a consensus key: `tmkms softsign keygen {}`",
+            output_dir.join("secrets").join(SOFTSIGN_KEY_FILE).display()
+        )),
+    }
+
+    steps.push(format!(
+        "start the KMS with `tmkms start -c {}`",
+        config_path.display()
+    ));
+    steps
+}
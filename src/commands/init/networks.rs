@@ -0,0 +1,76 @@
+//! Presets for well-known Tendermint networks
+
+use crate::{chain, keyring};
+
+/// A well-known Tendermint network
+#[derive(Copy, Clone, Debug)]
+pub struct Network {
+    /// Short name of the network
+    pub name: &'static str,
+
+    /// Chain ID of the network's current mainnet
+    pub chain_id: &'static str,
+
+    /// Bech32 prefix for account public keys
+    pub account_key_prefix: &'static str,
+
+    /// Bech32 prefix for consensus public keys
+    pub consensus_key_prefix: &'static str,
+}
+
+/// Presets for well-known networks
+pub const NETWORKS: &[Network] = &[
+    Network {
+        name: "cosmoshub",
+        chain_id: "cosmoshub-3",
+        account_key_prefix: "cosmospub",
+        consensus_key_prefix: "cosmosvalconspub",
+    },
+    Network {
+        name: "irishub",
+        chain_id: "irishub",
+        account_key_prefix: "iap",
+        consensus_key_prefix: "icp",
+    },
+    Network {
+        name: "columbus",
+        chain_id: "columbus-3",
+        account_key_prefix: "terrapub",
+        consensus_key_prefix: "terravalconspub",
+    },
+    Network {
+        name: "kava",
+        chain_id: "kava-3",
+        account_key_prefix: "kavapub",
+        consensus_key_prefix: "kavavalconspub",
+    },
+    Network {
+        name: "emoney",
+        chain_id: "emoney-1",
+        account_key_prefix: "emoneypub",
+        consensus_key_prefix: "emoneyvalconspub",
+    },
+];
+
+impl Network {
+    /// Find a preset by its name (e.g. `cosmoshub`) or chain ID
+    /// (e.g. `cosmoshub-3`)
+    pub fn find(name_or_chain_id: &str) -> Option<&'static Network> {
+        NETWORKS.iter().find(|network| {
+            network.name == name_or_chain_id || network.chain_id == name_or_chain_id
+        })
+    }
+
+    /// Chain ID of this network
+    pub fn chain_id(&self) -> chain::Id {
+        self.chain_id.parse().unwrap()
+    }
+
+    /// Key format for this network
+    pub fn key_format(&self) -> keyring::Format {
+        keyring::Format::Bech32 {
+            account_key_prefix: self.account_key_prefix.to_owned(),
+            consensus_key_prefix: self.consensus_key_prefix.to_owned(),
+        }
+    }
+}
@@ -1,75 +1,165 @@
 //! `tmkms ledger` CLI (sub)commands
 
-use crate::{chain, prelude::*};
-use abscissa_core::{Command, Options, Runnable};
-use std::{path::PathBuf, process};
-use tendermint::amino_types::{
-    vote::{SignVoteRequest, Vote},
-    SignableMsg, SignedMsgType,
+mod init;
+mod pubkey;
+mod status;
+
+pub use self::{init::InitCommand, pubkey::PubkeyCommand, status::StatusCommand};
+use crate::{
+    chain,
+    config::KmsConfig,
+    error::{Error, ErrorKind::*},
+    prelude::*,
 };
+use abscissa_core::{Command, Help, Options, Runnable};
+use std::{path::PathBuf, process};
 
 /// `ledger` subcommand
 #[derive(Command, Debug, Options, Runnable)]
 pub enum LedgerCommand {
+    /// Show help for the `ledger` subcommand
+    #[options(help = "show help for the 'ledger' subcommand")]
+    Help(Help<Self>),
+
     /// Initialize HRS values
-    #[options(help = "initialise the height/round/step")]
-    Initialise(InitCommand),
+    #[options(help = "initialise the device's height/round/step for a chain")]
+    Init(InitCommand),
+
+    /// Display the public key
+    #[options(help = "show the device's public key in a chain's format")]
+    Pubkey(PubkeyCommand),
+
+    /// Show the status of the device
+    #[options(help = "show the app version and signing state")]
+    Status(StatusCommand),
 }
 
 impl LedgerCommand {
     pub(super) fn config_path(&self) -> Option<&PathBuf> {
         match self {
-            LedgerCommand::Initialise(init) => init.config.as_ref(),
+            LedgerCommand::Init(init) => init.config.as_ref(),
+            LedgerCommand::Pubkey(pubkey) => pubkey.config.as_ref(),
+            LedgerCommand::Status(status) => status.config.as_ref(),
+            _ => None,
         }
     }
 }
 
-/// `ledger init` subcommand
-#[derive(Command, Debug, Options)]
-pub struct InitCommand {
-    /// config file path
-    #[options(short = "c", long = "config")]
-    pub config: Option<PathBuf>,
+/// Find the chain a `tmkms ledger` command operates on, exiting if there
+/// isn't exactly one the Ledger signs for
+fn ledger_chain_id(config: &KmsConfig, chain_ids: &[String]) -> chain::Id {
+    find_chain_id(config, chain_ids).unwrap_or_else(|e| {
+        status_err!("{}", e);
+        process::exit(1);
+    })
+}
 
-    /// block height
-    #[options(short = "h", long = "height")]
-    pub height: Option<i64>,
+/// Find the chain a `tmkms ledger` command operates on, either from the
+/// chain ID given on the command line or (if omitted) the only chain the
+/// Ledger is configured for, checking the Ledger signs for it
+fn find_chain_id(config: &KmsConfig, chain_ids: &[String]) -> Result<chain::Id, Error> {
+    let ledger_chain_ids = config
+        .providers
+        .ledgertm
+        .iter()
+        .flat_map(|ledgertm| ledgertm.chain_ids.iter())
+        .collect::<Vec<_>>();
 
-    /// block round
-    #[options(short = "r", long = "round")]
-    pub round: Option<i64>,
-}
+    let chain_id = match chain_ids {
+        [] if ledger_chain_ids.len() == 1 => *ledger_chain_ids[0],
+        [] => fail!(
+            ConfigError,
+            "no chain ID given, and the Ledger is configured for {} chains",
+            ledger_chain_ids.len()
+        ),
+        [chain_id] => chain_id
+            .parse()
+            .map_err(|e| format_err!(ConfigError, "invalid chain ID '{}': {}", chain_id, e))?,
+        _ => fail!(
+            ConfigError,
+            "expected at most 1 chain ID, got {}",
+            chain_ids.len()
+        ),
+    };
 
-impl Runnable for InitCommand {
-    fn run(&self) {
-        let config = app_config();
-
-        chain::load_config(&config).unwrap_or_else(|e| {
-            status_err!("error loading configuration: {}", e);
-            process::exit(1);
-        });
-
-        let chain_id = config.validator[0].chain_id;
-        let registry = chain::REGISTRY.get();
-        let chain = registry.get_chain(&chain_id).unwrap();
-
-        let mut vote = Vote::default();
-        vote.height = self.height.unwrap();
-        vote.round = self.round.unwrap();
-        vote.vote_type = SignedMsgType::Proposal.to_u32();
-        println!("{:?}", vote);
-        let sign_vote_req = SignVoteRequest { vote: Some(vote) };
-        let mut to_sign = vec![];
-        sign_vote_req
-            .sign_bytes(config.validator[0].chain_id, &mut to_sign)
-            .unwrap();
-
-        let _sig = chain.keyring.sign_ed25519(None, &to_sign).unwrap();
-
-        println!(
-            "Successfully called the init command with height {}, and round {}",
-            self.height.unwrap(),
-            self.round.unwrap()
+    if !config.chain.iter().any(|chain| chain.id == chain_id) {
+        fail!(
+            ConfigError,
+            "no [[chain]] section for {} in tmkms.toml",
+            chain_id
         );
     }
+
+    if !ledger_chain_ids.contains(&&chain_id) {
+        fail!(
+            ConfigError,
+            "chain {} isn't in `chain_ids` for [[providers.ledgertm]]",
+            chain_id
+        );
+    }
+
+    Ok(chain_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parse a config with the given chains, and a Ledger signing for `ledger_chain_ids`
+    fn config(chain_ids: &[&str], ledger_chain_ids: &[&str]) -> KmsConfig {
+        let mut toml_string = String::new();
+
+        for chain_id in chain_ids {
+            toml_string.push_str(&format!(
+                "[[chain]]\nid = \"{}\"\nkey_format = {{ type = \"hex\" }}\n\n",
+                chain_id
+            ));
+        }
+
+        toml_string.push_str(&format!(
+            "[[providers.ledgertm]]\nchain_ids = {:?}\n",
+            ledger_chain_ids
+        ));
+
+        toml::from_str(&toml_string).unwrap()
+    }
+
+    /// Find the chain ID given the command line arguments
+    fn find(config: &KmsConfig, args: &[&str]) -> Result<String, String> {
+        let args = args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+
+        find_chain_id(config, &args)
+            .map(|chain_id| chain_id.to_string())
+            .map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn defaults_to_only_ledger_chain() {
+        let config = config(&["chain-a", "chain-b"], &["chain-a"]);
+        assert_eq!(find(&config, &[]).unwrap(), "chain-a");
+        assert_eq!(find(&config, &["chain-a"]).unwrap(), "chain-a");
+    }
+
+    #[test]
+    fn requires_chain_id_for_multiple_chains() {
+        let config = config(&["chain-a", "chain-b"], &["chain-a", "chain-b"]);
+        assert!(find(&config, &[])
+            .unwrap_err()
+            .contains("configured for 2 chains"));
+        assert_eq!(find(&config, &["chain-b"]).unwrap(), "chain-b");
+        assert!(find(&config, &["chain-a", "chain-b"])
+            .unwrap_err()
+            .contains("at most 1 chain ID"));
+    }
+
+    #[test]
+    fn rejects_chains_the_ledger_does_not_sign_for() {
+        let config = config(&["chain-a", "chain-b"], &["chain-a", "chain-c"]);
+        assert!(find(&config, &["chain-b"])
+            .unwrap_err()
+            .contains("isn't in `chain_ids`"));
+        assert!(find(&config, &["chain-c"])
+            .unwrap_err()
+            .contains("no [[chain]] section"));
+    }
 }
@@ -0,0 +1,123 @@
+//! `tmkms ledger init` subcommand
+
+use crate::{keyring::ed25519::ledgertm, prelude::*};
+use abscissa_core::{Command, Options, Runnable};
+use signatory::signature::Signer;
+use std::{path::PathBuf, process};
+use tendermint::{
+    amino_types::{
+        proposal::{Proposal, SignProposalRequest},
+        SignableMsg, SignedMsgType,
+    },
+    Time,
+};
+
+/// `ledger init` subcommand
+#[derive(Command, Debug, Default, Options)]
+pub struct InitCommand {
+    /// config file path
+    #[options(short = "c", long = "config", help = "path to tmkms.toml")]
+    pub config: Option<PathBuf>,
+
+    /// block height
+    #[options(
+        short = "h",
+        long = "height",
+        help = "block height to start signing from"
+    )]
+    pub height: Option<i64>,
+
+    /// block round
+    #[options(
+        short = "r",
+        long = "round",
+        help = "round to start signing from (default 0)"
+    )]
+    pub round: Option<i64>,
+
+    /// chain ID
+    #[options(free, help = "chain ID (optional if the Ledger signs for only one)")]
+    pub chain_ids: Vec<String>,
+}
+
+impl Runnable for InitCommand {
+    /// Set the Ledger's height/round/step by signing a proposal at the given
+    /// height and round, which the app will not sign anything below afterwards
+    fn run(&self) {
+        let config = app_config();
+        let chain_id = super::ledger_chain_id(&config, &self.chain_ids);
+
+        let height = self.height.unwrap_or_else(|| {
+            status_err!("missing block height (use --height)");
+            process::exit(1);
+        });
+
+        if height < 1 {
+            status_err!("invalid block height {} (must be at least 1)", height);
+            process::exit(1);
+        }
+
+        let round = self.round.unwrap_or(0);
+
+        if round < 0 {
+            status_err!("invalid round {} (must not be negative)", round);
+            process::exit(1);
+        }
+
+        let request = SignProposalRequest {
+            proposal: Some(Proposal {
+                msg_type: SignedMsgType::Proposal.to_u32(),
+                height,
+                round,
+                pol_round: -1,
+                block_id: None,
+                timestamp: Some(Time::now().into()),
+                signature: vec![],
+            }),
+        };
+
+        request.validate().unwrap_or_else(|e| {
+            status_err!("invalid proposal: {}", e);
+            process::exit(1);
+        });
+
+        let mut to_sign = vec![];
+        request
+            .sign_bytes(chain_id, &mut to_sign)
+            .unwrap_or_else(|e| {
+                status_err!("couldn't encode proposal: {}", e);
+                process::exit(1);
+            });
+
+        let signer = ledgertm::connect().unwrap_or_else(|e| {
+            status_err!("{}", e);
+            process::exit(1);
+        });
+
+        status_info!(
+            "Signing",
+            "proposal for {} at height {}, round {} (confirm on the Ledger if prompted)",
+            chain_id,
+            height,
+            round
+        );
+
+        if let Err(e) = signer.try_sign(&to_sign) {
+            status_err!(
+                "Ledger refused to sign (is its height/round already past {}/{}?): {}",
+                height,
+                round,
+                e
+            );
+            process::exit(1);
+        }
+
+        status_ok!(
+            "Initialised",
+            "Ledger for {} at height {}, round {}",
+            chain_id,
+            height,
+            round
+        );
+    }
+}
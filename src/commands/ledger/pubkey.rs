@@ -0,0 +1,50 @@
+//! `tmkms ledger pubkey` subcommand
+
+use crate::{keyring::ed25519::ledgertm, prelude::*};
+use abscissa_core::{Command, Options, Runnable};
+use signatory::public_key::PublicKeyed;
+use std::{path::PathBuf, process};
+use tendermint::TendermintKey;
+
+/// `ledger pubkey` subcommand
+#[derive(Command, Debug, Default, Options)]
+pub struct PubkeyCommand {
+    /// config file path
+    #[options(short = "c", long = "config", help = "path to tmkms.toml")]
+    pub config: Option<PathBuf>,
+
+    /// chain ID
+    #[options(free, help = "chain ID (optional if the Ledger signs for only one)")]
+    pub chain_ids: Vec<String>,
+}
+
+impl Runnable for PubkeyCommand {
+    /// Print the Ledger's consensus key in the chain's configured format
+    fn run(&self) {
+        let config = app_config();
+        let chain_id = super::ledger_chain_id(&config, &self.chain_ids);
+
+        let chain_config = config
+            .chain
+            .iter()
+            .find(|chain| chain.id == chain_id)
+            .unwrap();
+
+        let signer = ledgertm::connect().unwrap_or_else(|e| {
+            status_err!("{}", e);
+            process::exit(1);
+        });
+
+        let public_key = signer.public_key().unwrap_or_else(|e| {
+            status_err!("couldn't get public key from Ledger: {}", e);
+            process::exit(1);
+        });
+
+        println!(
+            "{}",
+            chain_config
+                .key_format
+                .serialize(TendermintKey::ConsensusKey(public_key.into()))
+        );
+    }
+}
@@ -0,0 +1,100 @@
+//! `tmkms ledger status` subcommand
+
+use crate::{keyring::ed25519::ledgertm, prelude::*};
+use abscissa_core::{Command, Options, Runnable};
+use signatory::public_key::PublicKeyed;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process,
+};
+use subtle_encoding::hex;
+use tendermint::consensus;
+
+/// `ledger status` subcommand
+#[derive(Command, Debug, Default, Options)]
+pub struct StatusCommand {
+    /// config file path
+    #[options(short = "c", long = "config", help = "path to tmkms.toml")]
+    pub config: Option<PathBuf>,
+}
+
+impl Runnable for StatusCommand {
+    /// Show the Ledger app version and public key, along with the
+    /// height/round/step in the KMS state file for each of its chains
+    fn run(&self) {
+        let config = app_config();
+
+        let version = ledgertm::app_version().unwrap_or_else(|e| {
+            status_err!("{}", e);
+            process::exit(1);
+        });
+
+        let public_key = ledgertm::connect()
+            .ok()
+            .and_then(|signer| signer.public_key().ok())
+            .unwrap_or_else(|| {
+                status_err!("couldn't get public key from Ledger");
+                process::exit(1);
+            });
+
+        println!("Ledger Tendermint validator app:");
+        println!("  version:    {}", version);
+        println!(
+            "  public key: {}",
+            String::from_utf8(hex::encode_upper(public_key.as_bytes())).unwrap()
+        );
+
+        // The app can't report its own height/round/step watermark, so show
+        // the KMS's, which can differ from the device's (e.g. after `ledger init`)
+        println!("KMS state files (not read from the Ledger):");
+
+        for chain_id in config
+            .providers
+            .ledgertm
+            .iter()
+            .flat_map(|ledgertm| ledgertm.chain_ids.iter())
+        {
+            let chain_config = match config.chain.iter().find(|chain| chain.id == *chain_id) {
+                Some(chain_config) => chain_config,
+                None => {
+                    println!("  {}: (no [[chain]] section in tmkms.toml)", chain_id);
+                    continue;
+                }
+            };
+
+            let state_file = chain_config.state_file_path();
+
+            match read_consensus_state(&state_file) {
+                Ok(Some(state)) => println!(
+                    "  {}: height {}, round {}, step {} ({})",
+                    chain_id,
+                    state.height,
+                    state.round,
+                    state.step,
+                    state_file.display()
+                ),
+                Ok(None) => println!(
+                    "  {}: no state file yet ({})",
+                    chain_id,
+                    state_file.display()
+                ),
+                Err(e) => {
+                    status_err!("couldn't read {}: {}", state_file.display(), e);
+                    process::exit(1);
+                }
+            }
+        }
+    }
+}
+
+/// Read the consensus state for a chain without creating or locking the state file
+fn read_consensus_state(path: &Path) -> Result<Option<consensus::State>, io::Error> {
+    match fs::read_to_string(path) {
+        Ok(state_json) => serde_json::from_str(&state_json)
+            .map(Some)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
@@ -1,14 +1,38 @@
 //! `tmkms softsign` CLI (sub)commands
 
+mod export;
 mod import;
 mod keygen;
+mod recover;
 
-use self::{import::ImportCommand, keygen::KeygenCommand};
-use abscissa_core::{Command, Help, Options, Runnable};
+use self::{
+    export::ExportCommand, import::ImportCommand, keygen::KeygenCommand, recover::RecoverCommand,
+};
+use crate::{
+    config::password::Password,
+    keyring::{
+        ed25519::softsign::encrypted::{self, EncryptedKey},
+        SecretKeyEncoding,
+    },
+    prelude::*,
+};
+use abscissa_core::{secret::Secret, Command, Help, Options, Runnable};
+use hkd32::mnemonic;
+use signatory::{ed25519, encoding::Encode};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process,
+};
+use zeroize::{Zeroize, Zeroizing};
 
 /// The `softsign` subcommand
 #[derive(Command, Debug, Options, Runnable)]
 pub enum SoftsignCommand {
+    /// Export a key in Tendermint's `priv_validator_key.json` format
+    #[options(help = "export a key as Tendermint's priv_validator_key.json")]
+    Export(ExportCommand),
+
     /// Show help for the `softsign` subcommand
     #[options(help = "show help for the 'yubihsm' subcommand")]
     Help(Help<Self>),
@@ -20,4 +44,136 @@ pub enum SoftsignCommand {
     /// Import an existing key into the softsign Base64 format
     #[options(help = "convert existing private key to base64 format")]
     Import(ImportCommand),
+
+    /// Recover a software signing key from its mnemonic phrase
+    #[options(help = "recover a software signing key from its mnemonic phrase")]
+    Recover(RecoverCommand),
+}
+
+impl SoftsignCommand {
+    /// Does this command need the configuration file?
+    pub(super) fn uses_config(&self) -> bool {
+        match self {
+            SoftsignCommand::Export(export) => export.uses_config(),
+            _ => false,
+        }
+    }
+
+    pub(super) fn config_path(&self) -> Option<&PathBuf> {
+        match self {
+            SoftsignCommand::Export(export) => export.config.as_ref(),
+            _ => None,
+        }
+    }
+}
+
+/// Write a key to the given path (in the `encrypted` format if a password
+/// source is given, otherwise Base64), exiting on error
+fn write_key(seed: &ed25519::Seed, output_path: &Path, password: Option<Password>) {
+    match password {
+        Some(password) => EncryptedKey::encrypt(seed, &password, encrypted::DEFAULT_ITERATIONS)
+            .and_then(|encrypted_key| encrypted_key.write(output_path))
+            .unwrap_or_else(|e| {
+                status_err!("couldn't write to {}: {}", output_path.display(), e);
+                process::exit(1);
+            }),
+        None => seed
+            .encode_to_file(output_path, &SecretKeyEncoding::default())
+            .map(|_| ())
+            .unwrap_or_else(|e| {
+                status_err!("couldn't write to {}: {}", output_path.display(), e);
+                process::exit(1);
+            }),
+    }
+}
+
+/// Get the password to encrypt a key with if `--encrypt` was given, reading
+/// it from a file or environment variable, or otherwise prompting for it
+fn encryption_password(
+    encrypt: bool,
+    password_file: Option<&Path>,
+    password_env: Option<&str>,
+) -> Option<Password> {
+    if !encrypt {
+        if password_file.is_some() || password_env.is_some() {
+            status_err!("--password-file and --password-env require --encrypt");
+            process::exit(1);
+        }
+
+        return None;
+    }
+
+    let password =
+        read_password(password_file, password_env).unwrap_or_else(prompt_for_new_password);
+
+    if password.is_empty() {
+        status_err!("password is empty");
+        process::exit(1);
+    }
+
+    Some(Password::from(password))
+}
+
+/// Get the password to decrypt a key with from a file or environment variable
+/// (if given, otherwise it's prompted for when the key is loaded)
+fn decryption_password(
+    password_file: Option<&Path>,
+    password_env: Option<&str>,
+) -> Option<Secret<Password>> {
+    read_password(password_file, password_env).map(|password| Secret::new(password.into()))
+}
+
+/// Read a password from a file or environment variable, if one was given
+fn read_password(password_file: Option<&Path>, password_env: Option<&str>) -> Option<String> {
+    match (password_file, password_env) {
+        (Some(_), Some(_)) => {
+            status_err!("only one of --password-file or --password-env can be given");
+            process::exit(1);
+        }
+        (Some(path), None) => {
+            let contents = Zeroizing::new(fs::read_to_string(path).unwrap_or_else(|e| {
+                status_err!("couldn't read password from {}: {}", path.display(), e);
+                process::exit(1);
+            }));
+
+            Some(contents.trim_end().to_owned())
+        }
+        (None, Some(var)) => Some(env::var(var).unwrap_or_else(|e| {
+            status_err!("couldn't read password from ${}: {}", var, e);
+            process::exit(1);
+        })),
+        (None, None) => None,
+    }
+}
+
+/// Prompt for a new password (twice, to confirm it)
+fn prompt_for_new_password() -> String {
+    let read_password = |prompt| {
+        rpassword::read_password_from_tty(Some(prompt)).unwrap_or_else(|e| {
+            status_err!("couldn't read password: {}", e);
+            process::exit(1);
+        })
+    };
+
+    let password = read_password("Enter password to encrypt key: ");
+    let confirmation = Zeroizing::new(read_password("Confirm password: "));
+
+    if password != *confirmation {
+        status_err!("passwords don't match");
+        process::exit(1);
+    }
+
+    password
+}
+
+/// Display a mnemonic phrase as two groups of 12 words
+fn print_phrase(phrase: &mnemonic::Phrase) {
+    let words = phrase.phrase().split(' ').collect::<Vec<_>>();
+    let (first, second) = words.split_at(words.len() / 2);
+
+    for word_group in &[first, second] {
+        let mut word_group_joined = word_group.join(" ");
+        println!("    {}", word_group_joined);
+        word_group_joined.zeroize();
+    }
 }
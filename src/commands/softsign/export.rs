@@ -0,0 +1,204 @@
+//! `tmkms softsign export` command
+
+use crate::{
+    chain::{self, state::PrivValidatorState},
+    commands::state::load_state,
+    config::provider::softsign::{KeyFormat, SoftPrivateKey},
+    keyring::ed25519::softsign::load_seed,
+    prelude::*,
+};
+use abscissa_core::{Command, Options, Runnable};
+use serde::{
+    de::{
+        value::{self, StrDeserializer},
+        IntoDeserializer,
+    },
+    Deserialize,
+};
+use signatory::{ed25519, public_key::PublicKeyed};
+use signatory_dalek::Ed25519Signer;
+use std::{
+    fs,
+    io::Write,
+    os::unix::fs::OpenOptionsExt,
+    path::{Path, PathBuf},
+    process,
+};
+use subtle_encoding::base64;
+use tendermint::{
+    account, config::PrivValidatorKey, private_key::Ed25519Keypair, PrivateKey, PublicKey,
+};
+use zeroize::Zeroizing;
+
+/// `export` command: convert a softsign key into Tendermint's
+/// `priv_validator_key.json` format (optionally along with the matching
+/// `priv_validator_state.json`)
+#[derive(Command, Debug, Default, Options)]
+pub struct ExportCommand {
+    /// Path to configuration file
+    #[options(short = "c", long = "config", help = "path to tmkms.toml")]
+    pub config: Option<PathBuf>,
+
+    #[options(
+        short = "f",
+        help = "key format to export: 'raw', 'base64', 'json' or 'encrypted' (default 'base64')"
+    )]
+    format: Option<String>,
+
+    #[options(no_short, help = "read the key's password from a file")]
+    password_file: Option<PathBuf>,
+
+    #[options(
+        no_short,
+        help = "read the key's password from an environment variable"
+    )]
+    password_env: Option<String>,
+
+    #[options(
+        short = "s",
+        long = "state",
+        help = "also export this chain's state as priv_validator_state.json"
+    )]
+    state: Option<chain::Id>,
+
+    #[options(
+        no_short,
+        help = "where to write priv_validator_state.json (default: next to the key)"
+    )]
+    state_file: Option<PathBuf>,
+
+    #[options(free, help = "[INPUT] key and [OUTPUT] priv_validator_key.json paths")]
+    paths: Vec<PathBuf>,
+}
+
+impl ExportCommand {
+    /// Does this command need the configuration file?
+    pub(super) fn uses_config(&self) -> bool {
+        self.state.is_some()
+    }
+}
+
+impl Runnable for ExportCommand {
+    /// Export a softsign key as `priv_validator_key.json`
+    fn run(&self) {
+        if self.paths.len() != 2 {
+            status_err!("expected 2 arguments, got {}", self.paths.len());
+            eprintln!(
+                "\nUsage: tmkms softsign export [-f FORMAT] [-s CHAIN_ID] \
+                 [input.key] [priv_validator_key.json]"
+            );
+            process::exit(1);
+        }
+
+        if self.state_file.is_some() && self.state.is_none() {
+            status_err!("--state-file requires --state");
+            process::exit(1);
+        }
+
+        let input_path = &self.paths[0];
+        let output_path = &self.paths[1];
+
+        let format = self
+            .format
+            .as_ref()
+            .map(|f| {
+                f.parse::<KeyFormat>().unwrap_or_else(|e| {
+                    status_err!("{}", e);
+                    process::exit(1);
+                })
+            })
+            .unwrap_or(KeyFormat::Base64);
+
+        let password = super::decryption_password(
+            self.password_file.as_ref().map(AsRef::as_ref),
+            self.password_env.as_ref().map(AsRef::as_ref),
+        );
+
+        let seed = load_seed(
+            &SoftPrivateKey::from(input_path.clone()),
+            format,
+            password.as_ref(),
+        )
+        .unwrap_or_else(|e| {
+            status_err!("couldn't load {}: {}", input_path.display(), e);
+            process::exit(1);
+        });
+
+        // Load the chain state before writing anything
+        let state = self
+            .state
+            .as_ref()
+            .map(|chain_id| (chain_id, load_state(chain_id)));
+
+        let json = serde_json::to_string_pretty(&priv_validator_key(&seed)).unwrap();
+        write_new_file(output_path, &Zeroizing::new(json + "\n"), 0o600);
+
+        status_ok!(
+            "Exported",
+            "{} to {}",
+            input_path.display(),
+            output_path.display()
+        );
+
+        if let Some((chain_id, state)) = state {
+            let state_path = self.state_file.clone().unwrap_or_else(|| {
+                output_path
+                    .parent()
+                    .unwrap_or_else(|| Path::new(""))
+                    .join("priv_validator_state.json")
+            });
+
+            let consensus_state = state.consensus_state();
+
+            let json =
+                serde_json::to_string_pretty(&PrivValidatorState::from(consensus_state)).unwrap();
+
+            write_new_file(&state_path, &(json + "\n"), 0o644);
+
+            status_ok!(
+                "Exported",
+                "{} state ({}) to {}",
+                chain_id,
+                consensus_state,
+                state_path.display()
+            );
+        }
+    }
+}
+
+/// Build Tendermint's `priv_validator_key.json` for the given seed
+fn priv_validator_key(seed: &ed25519::Seed) -> PrivValidatorKey {
+    let public_key = Ed25519Signer::from(seed).public_key().unwrap();
+
+    // Tendermint stores Ed25519 keys as the seed followed by the public key
+    let mut keypair_bytes = Zeroizing::new(seed.as_secret_slice().to_vec());
+    keypair_bytes.extend_from_slice(public_key.as_bytes());
+
+    let keypair_base64 =
+        Zeroizing::new(String::from_utf8(base64::encode(&keypair_bytes[..])).unwrap());
+
+    let deserializer: StrDeserializer<'_, value::Error> =
+        keypair_base64.as_str().into_deserializer();
+    let keypair = Ed25519Keypair::deserialize(deserializer).unwrap();
+
+    PrivValidatorKey {
+        address: account::Id::from(public_key),
+        pub_key: PublicKey::from(public_key),
+        priv_key: PrivateKey::Ed25519(keypair),
+    }
+}
+
+/// Write a file which doesn't already exist with the given permissions,
+/// exiting on error
+fn write_new_file(path: &Path, contents: &str, mode: u32) {
+    fs::OpenOptions::new()
+        .create_new(true)
+        .write(true)
+        .mode(mode)
+        .open(path)
+        .and_then(|mut file| file.write_all(contents.as_bytes()))
+        .unwrap_or_else(|e| {
+            status_err!("couldn't write {}: {}", path.display(), e);
+            process::exit(1);
+        });
+}
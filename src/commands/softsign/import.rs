@@ -1,11 +1,8 @@
 //! `tmkms softsign import` command
 
-use crate::{config::provider::softsign::KeyFormat, keyring::SecretKeyEncoding, prelude::*};
+use crate::{config::provider::softsign::KeyFormat, prelude::*};
 use abscissa_core::{Command, Options, Runnable};
-use signatory::{
-    ed25519,
-    encoding::{Decode, Encode},
-};
+use signatory::{ed25519, encoding::Decode};
 use std::{path::PathBuf, process};
 use subtle_encoding::IDENTITY;
 use tendermint::{config::PrivValidatorKey, PrivateKey};
@@ -14,12 +11,24 @@ use tendermint::{config::PrivValidatorKey, PrivateKey};
 /// it into the raw format used by the softsign backend (by default)
 #[derive(Command, Debug, Default, Options)]
 pub struct ImportCommand {
+    #[options(help = "encrypt the key with a password (prompted for by default)")]
+    encrypt: bool,
+
     #[options(
         short = "f",
         help = "key format to import: 'json' or 'raw' (default 'json')"
     )]
     format: Option<String>,
 
+    #[options(no_short, help = "read the encryption password from a file")]
+    password_file: Option<PathBuf>,
+
+    #[options(
+        no_short,
+        help = "read the encryption password from an environment variable"
+    )]
+    password_env: Option<String>,
+
     #[options(free, help = "[INPUT] and [OUTPUT] paths for key generation")]
     paths: Vec<PathBuf>,
 }
@@ -47,6 +56,12 @@ impl Runnable for ImportCommand {
             })
             .unwrap_or(KeyFormat::Json);
 
+        let password = super::encryption_password(
+            self.encrypt,
+            self.password_file.as_ref().map(AsRef::as_ref),
+            self.password_env.as_ref().map(AsRef::as_ref),
+        );
+
         let seed = match format {
             KeyFormat::Json => {
                 let private_key = PrivValidatorKey::load_json_file(input_path)
@@ -73,13 +88,13 @@ impl Runnable for ImportCommand {
                 status_err!("invalid format: baes64 (must be 'json' or 'raw')");
                 process::exit(1);
             }
+            KeyFormat::Encrypted => {
+                status_err!("invalid format: encrypted (must be 'json' or 'raw')");
+                process::exit(1);
+            }
         };
 
-        seed.encode_to_file(output_path, &SecretKeyEncoding::default())
-            .unwrap_or_else(|e| {
-                status_err!("couldn't write to {}: {}", output_path.display(), e);
-                process::exit(1);
-            });
+        super::write_key(&seed, output_path, password);
 
         info!("Imported Ed25519 private key to {}", output_path.display());
     }
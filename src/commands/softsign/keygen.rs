@@ -1,13 +1,37 @@
 //! `tmkms softsign keygen` subcommand
 
-use crate::{keyring::SecretKeyEncoding, prelude::*};
+use crate::{keyring::ed25519::softsign::mnemonic, prelude::*};
 use abscissa_core::{Command, Options, Runnable};
-use signatory::{ed25519, encoding::Encode};
+use signatory::ed25519;
 use std::{path::PathBuf, process};
 
 /// `keygen` command
 #[derive(Command, Debug, Default, Options)]
 pub struct KeygenCommand {
+    #[options(help = "encrypt the key with a password (prompted for by default)")]
+    encrypt: bool,
+
+    #[options(
+        short = "m",
+        help = "derive the key from a new 24-word mnemonic phrase (printed)"
+    )]
+    mnemonic: bool,
+
+    #[options(
+        no_short,
+        help = "mnemonic derivation path (default '/tmkms/softsign')"
+    )]
+    derivation_path: Option<String>,
+
+    #[options(no_short, help = "read the encryption password from a file")]
+    password_file: Option<PathBuf>,
+
+    #[options(
+        no_short,
+        help = "read the encryption password from an environment variable"
+    )]
+    password_env: Option<String>,
+
     #[options(free, help = "path where generated key should be created")]
     output_paths: Vec<PathBuf>,
 }
@@ -16,21 +40,56 @@ impl Runnable for KeygenCommand {
     /// Generate an Ed25519 secret key for use with a software provider (i.e. ed25519-dalek)
     fn run(&self) {
         if self.output_paths.len() != 1 {
-            eprintln!("Usage: tmkms softsign keygen [PATH]");
+            eprintln!("Usage: tmkms softsign keygen [--encrypt] [--mnemonic] [PATH]");
+            process::exit(1);
+        }
+
+        if self.derivation_path.is_some() && !self.mnemonic {
+            status_err!("--derivation-path requires --mnemonic");
             process::exit(1);
         }
 
         let output_path = &self.output_paths[0];
 
-        let seed = ed25519::Seed::generate();
-        seed.encode_to_file(output_path, &SecretKeyEncoding::default())
-            .unwrap_or_else(|e| {
-                status_err!("couldn't write to {}: {}", output_path.display(), e);
+        let password = super::encryption_password(
+            self.encrypt,
+            self.password_file.as_ref().map(AsRef::as_ref),
+            self.password_env.as_ref().map(AsRef::as_ref),
+        );
+
+        let seed = if self.mnemonic {
+            let derivation_path = self
+                .derivation_path
+                .as_ref()
+                .map(AsRef::as_ref)
+                .unwrap_or(mnemonic::DEFAULT_DERIVATION_PATH);
+
+            let phrase = mnemonic::generate_phrase();
+
+            let seed = mnemonic::derive_seed(&phrase, derivation_path).unwrap_or_else(|e| {
+                status_err!("{}", e);
                 process::exit(1);
             });
 
+            println!("Write down this 24-word mnemonic phrase and keep it somewhere safe.");
+            println!("It can be used to recover the key with `tmkms softsign recover`:\n");
+            super::print_phrase(&phrase);
+            println!("\nDerivation path: {}\n", derivation_path);
+
+            seed
+        } else {
+            ed25519::Seed::generate()
+        };
+
+        super::write_key(&seed, output_path, password);
+
         info!(
-            "Wrote random Ed25519 private key to {}",
+            "Wrote {} Ed25519 private key to {}",
+            if self.mnemonic {
+                "mnemonic-derived"
+            } else {
+                "random"
+            },
             output_path.display()
         );
     }
@@ -0,0 +1,84 @@
+//! `tmkms softsign recover` subcommand
+
+use crate::{keyring::ed25519::softsign::mnemonic, prelude::*};
+use abscissa_core::{Command, Options, Runnable};
+use std::{
+    io::{self, Write},
+    path::PathBuf,
+    process,
+};
+use zeroize::Zeroizing;
+
+/// `recover` command: re-derive a key from its 24-word mnemonic phrase
+/// (read from STDIN)
+#[derive(Command, Debug, Default, Options)]
+pub struct RecoverCommand {
+    #[options(help = "encrypt the key with a password (prompted for by default)")]
+    encrypt: bool,
+
+    #[options(
+        no_short,
+        help = "mnemonic derivation path (default '/tmkms/softsign')"
+    )]
+    derivation_path: Option<String>,
+
+    #[options(no_short, help = "read the encryption password from a file")]
+    password_file: Option<PathBuf>,
+
+    #[options(
+        no_short,
+        help = "read the encryption password from an environment variable"
+    )]
+    password_env: Option<String>,
+
+    #[options(free, help = "path where the recovered key should be written")]
+    output_paths: Vec<PathBuf>,
+}
+
+impl Runnable for RecoverCommand {
+    /// Recover a softsign key from a mnemonic phrase
+    fn run(&self) {
+        if self.output_paths.len() != 1 {
+            eprintln!("Usage: tmkms softsign recover [--derivation-path PATH] [OUTPUT]");
+            process::exit(1);
+        }
+
+        let output_path = &self.output_paths[0];
+
+        let derivation_path = self
+            .derivation_path
+            .as_ref()
+            .map(AsRef::as_ref)
+            .unwrap_or(mnemonic::DEFAULT_DERIVATION_PATH);
+
+        let password = super::encryption_password(
+            self.encrypt,
+            self.password_file.as_ref().map(AsRef::as_ref),
+            self.password_env.as_ref().map(AsRef::as_ref),
+        );
+
+        print!("Enter 24-word mnemonic phrase (separate words with spaces): ");
+        io::stdout().flush().unwrap();
+
+        let mut input = Zeroizing::new(String::new());
+        io::stdin().read_line(&mut input).unwrap_or_else(|e| {
+            status_err!("couldn't read mnemonic phrase: {}", e);
+            process::exit(1);
+        });
+
+        let seed = mnemonic::parse_phrase(&input)
+            .and_then(|phrase| mnemonic::derive_seed(&phrase, derivation_path))
+            .unwrap_or_else(|e| {
+                status_err!("{}", e);
+                process::exit(1);
+            });
+
+        super::write_key(&seed, output_path, password);
+
+        info!(
+            "Recovered Ed25519 private key (derivation path: {}) to {}",
+            derivation_path,
+            output_path.display()
+        );
+    }
+}
@@ -1,6 +1,6 @@
 //! Start the KMS
 
-use crate::{chain, client::Client, prelude::*};
+use crate::{alert, chain, client::Client, prelude::*, status};
 use abscissa_core::{Command, Options};
 use std::{path::PathBuf, process};
 
@@ -69,6 +69,49 @@ impl StartCommand {
             process::exit(1);
         });
 
+        alert::configure(&config.alert).unwrap_or_else(|e| {
+            status_err!("invalid alert configuration: {}", e);
+            process::exit(1);
+        });
+
+        if let Some(status_config) = &config.status {
+            status::spawn(status_config).unwrap_or_else(|e| {
+                status_err!("couldn't start status endpoint: {}", e);
+                process::exit(1);
+            });
+        }
+
+        #[cfg(feature = "yubihsm")]
+        for yubihsm_config in &config.providers.yubihsm {
+            crate::yubihsm::session_pool::spawn_keepalive(
+                crate::yubihsm::session_pool(),
+                &yubihsm_config.session_pool,
+            )
+            .unwrap_or_else(|e| {
+                status_err!("couldn't start YubiHSM session keepalive: {}", e);
+                process::exit(1);
+            });
+
+            if let Some(audit_log) = &yubihsm_config.audit_log {
+                crate::yubihsm::audit_log::spawn_monitor(audit_log, yubihsm_config.auth.key())
+                    .unwrap_or_else(|e| {
+                        status_err!("couldn't start YubiHSM audit log monitor: {}", e);
+                        process::exit(1);
+                    });
+            }
+        }
+
+        // Make sure the identity keys of all validators using SecretConnection
+        // can be loaded before connecting to any of them
+        for validator in &config.validator {
+            if validator.uses_secret_connection() {
+                validator.load_secret_key().unwrap_or_else(|e| {
+                    status_err!("[{}@{}] {}", &validator.chain_id, &validator.addr, e);
+                    process::exit(1);
+                });
+            }
+        }
+
         // Spawn the validator client threads
         config
             .validator
@@ -0,0 +1,181 @@
+//! `tmkms state` CLI (sub)commands: inspect and repair chain state
+
+mod export;
+mod import;
+mod reset;
+mod set;
+mod show;
+
+pub use self::{
+    export::ExportCommand, import::ImportCommand, reset::ResetCommand, set::SetCommand,
+    show::ShowCommand,
+};
+use crate::{
+    application::app_config,
+    chain::{self, state, State},
+    config::chain::ChainConfig,
+    prelude::*,
+};
+use abscissa_core::{Command, Help, Options, Runnable};
+use std::{path::PathBuf, process};
+use tendermint::consensus;
+
+/// The `state` subcommand
+#[derive(Command, Debug, Options, Runnable)]
+pub enum StateCommand {
+    /// Export chain state
+    #[options(help = "export a chain's state as JSON")]
+    Export(ExportCommand),
+
+    /// Show help for the `state` subcommand
+    #[options(help = "show help for the 'state' subcommand")]
+    Help(Help<Self>),
+
+    /// Import chain state
+    #[options(help = "import a chain's state from JSON")]
+    Import(ImportCommand),
+
+    /// Reset chain state
+    #[options(help = "reset a chain's state to height 0")]
+    Reset(ResetCommand),
+
+    /// Set chain state
+    #[options(help = "set the last signed height/round/step of a chain")]
+    Set(SetCommand),
+
+    /// Show chain state
+    #[options(help = "show the last signed height/round/step of chains")]
+    Show(ShowCommand),
+}
+
+impl StateCommand {
+    /// Does this command need the configuration file?
+    pub(super) fn uses_config(&self) -> bool {
+        !matches!(self, StateCommand::Help(_))
+    }
+
+    pub(super) fn config_path(&self) -> Option<&PathBuf> {
+        match self {
+            StateCommand::Export(export) => export.config.as_ref(),
+            StateCommand::Import(import) => import.config.as_ref(),
+            StateCommand::Reset(reset) => reset.config.as_ref(),
+            StateCommand::Set(set) => set.config.as_ref(),
+            StateCommand::Show(show) => show.config.as_ref(),
+            StateCommand::Help(_) => None,
+        }
+    }
+}
+
+/// Get the chain ID argument of a subcommand, exiting if it's missing
+fn required_chain_id(chain_id: &Option<chain::Id>, usage: &str) -> chain::Id {
+    chain_id.unwrap_or_else(|| {
+        eprintln!("Usage: tmkms state {}", usage);
+        process::exit(1);
+    })
+}
+
+/// Find the state file for the given chain in the configuration
+fn state_file_path(chain_id: &chain::Id) -> PathBuf {
+    app_config()
+        .chain
+        .iter()
+        .find(|chain_config| chain_config.id == *chain_id)
+        .map(ChainConfig::state_file_path)
+        .unwrap_or_else(|| {
+            status_err!(
+                "unknown chain: {} (add it to tmkms.toml's [[chain]] section)",
+                chain_id
+            );
+            process::exit(1);
+        })
+}
+
+/// Load the given chain's state (without creating the state file)
+pub(super) fn load_state(chain_id: &chain::Id) -> State {
+    let path = state_file_path(chain_id);
+
+    if !path.exists() {
+        status_err!("no state file for {} at {}", chain_id, path.display());
+        process::exit(1);
+    }
+
+    State::load_state(&path).unwrap_or_else(|e| {
+        status_err!("couldn't load state for {}: {}", chain_id, e);
+        process::exit(1);
+    })
+}
+
+/// Change the state of the given chain, only allowing it to move forward
+/// unless `force` is set.
+///
+/// Refuses to make changes while another process (i.e. the KMS) holds the
+/// chain's state file.
+fn update_state(chain_id: &chain::Id, new_state: consensus::State, force: bool) {
+    let path = state_file_path(chain_id);
+
+    let _lock = state::Lock::acquire(&path).unwrap_or_else(|e| {
+        status_err!("{}", e);
+        process::exit(1);
+    });
+
+    let mut state = State::load_state(&path).unwrap_or_else(|e| {
+        status_err!("couldn't load state for {}: {}", chain_id, e);
+        process::exit(1);
+    });
+
+    let old_state = state.consensus_state().clone();
+
+    let result = if force {
+        state
+            .overwrite_consensus_state(new_state.clone())
+            .map_err(|e| e.to_string())
+    } else {
+        state
+            .update_consensus_state(new_state.clone())
+            .map_err(|e| e.to_string())
+    };
+
+    if let Err(e) = result {
+        status_err!(
+            "refusing to change state of {} from {} to {}: {} (use --force to override)",
+            chain_id,
+            old_state,
+            new_state,
+            e
+        );
+        process::exit(1);
+    }
+
+    if force {
+        warn!(
+            chain_id = %chain_id,
+            old_state = %old_state,
+            new_state = %new_state,
+            force,
+            "[{}] forcibly changed state from {} to {}",
+            chain_id,
+            old_state,
+            new_state
+        );
+    } else {
+        info!(
+            chain_id = %chain_id,
+            old_state = %old_state,
+            new_state = %new_state,
+            force,
+            "[{}] changed state from {} to {}",
+            chain_id,
+            old_state,
+            new_state
+        );
+    }
+
+    status_ok!(
+        "Updated",
+        "{} state: {} -> {} ({})",
+        chain_id,
+        old_state,
+        new_state,
+        path.display()
+    );
+}
@@ -0,0 +1,68 @@
+//! `tmkms state export` subcommand
+
+use super::{load_state, required_chain_id};
+use crate::{chain, chain::state::PrivValidatorState, prelude::*};
+use abscissa_core::{Command, Options, Runnable};
+use std::{fs, path::PathBuf, process};
+
+/// `state export` command
+#[derive(Command, Debug, Default, Options)]
+pub struct ExportCommand {
+    /// Path to configuration file
+    #[options(short = "c", long = "config", help = "path to tmkms.toml")]
+    pub config: Option<PathBuf>,
+
+    /// Path to write the state to
+    #[options(short = "o", long = "output", help = "output file (default stdout)")]
+    output: Option<PathBuf>,
+
+    /// Export as Tendermint's `priv_validator_state.json`
+    #[options(
+        short = "t",
+        long = "tendermint",
+        help = "export in Tendermint's priv_validator_state.json format"
+    )]
+    tendermint: bool,
+
+    /// Chain to export the state of
+    #[options(free, help = "chain ID")]
+    chain_id: Option<chain::Id>,
+}
+
+impl Runnable for ExportCommand {
+    /// Export the state of a chain as JSON
+    fn run(&self) {
+        let chain_id = required_chain_id(
+            &self.chain_id,
+            "export [-c tmkms.toml] [-o PATH] [--tendermint] CHAIN_ID",
+        );
+
+        let state = load_state(&chain_id);
+        let consensus_state = state.consensus_state();
+
+        let json = if self.tendermint {
+            serde_json::to_string_pretty(&PrivValidatorState::from(consensus_state))
+        } else {
+            serde_json::to_string_pretty(consensus_state)
+        }
+        .unwrap();
+
+        match &self.output {
+            Some(path) => {
+                fs::write(path, json + "\n").unwrap_or_else(|e| {
+                    status_err!("couldn't write {}: {}", path.display(), e);
+                    process::exit(1);
+                });
+
+                status_ok!(
+                    "Exported",
+                    "{} state ({}) to {}",
+                    chain_id,
+                    consensus_state,
+                    path.display()
+                );
+            }
+            None => println!("{}", json),
+        }
+    }
+}
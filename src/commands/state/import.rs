@@ -0,0 +1,67 @@
+//! `tmkms state import` subcommand
+
+use super::update_state;
+use crate::{chain, chain::state::PrivValidatorState, prelude::*};
+use abscissa_core::{Command, Options, Runnable};
+use std::{fs, path::PathBuf, process};
+use tendermint::consensus;
+
+/// `state import` command
+#[derive(Command, Debug, Default, Options)]
+pub struct ImportCommand {
+    /// Path to configuration file
+    #[options(short = "c", long = "config", help = "path to tmkms.toml")]
+    pub config: Option<PathBuf>,
+
+    /// Import from Tendermint's `priv_validator_state.json`
+    #[options(
+        short = "t",
+        long = "tendermint",
+        help = "import from Tendermint's priv_validator_state.json format"
+    )]
+    tendermint: bool,
+
+    /// Allow moving the state backwards
+    #[options(no_short, help = "allow moving the state backwards (DANGER!)")]
+    force: bool,
+
+    /// Chain ID and path to the state to import
+    #[options(free, help = "chain ID and path to import from")]
+    args: Vec<String>,
+}
+
+impl Runnable for ImportCommand {
+    /// Import the state of a chain from JSON
+    fn run(&self) {
+        if self.args.len() != 2 {
+            eprintln!(
+                "Usage: tmkms state import [-c tmkms.toml] [--tendermint] [--force] CHAIN_ID PATH"
+            );
+            process::exit(1);
+        }
+
+        let chain_id: chain::Id = self.args[0].parse().unwrap_or_else(|e| {
+            status_err!("invalid chain ID '{}': {}", &self.args[0], e);
+            process::exit(1);
+        });
+
+        let path = &self.args[1];
+        let json = fs::read_to_string(path).unwrap_or_else(|e| {
+            status_err!("couldn't read {}: {}", path, e);
+            process::exit(1);
+        });
+
+        let new_state = if self.tendermint {
+            serde_json::from_str::<PrivValidatorState>(&json)
+                .map(|state| consensus::State::from(&state))
+        } else {
+            serde_json::from_str::<consensus::State>(&json)
+        }
+        .unwrap_or_else(|e| {
+            status_err!("couldn't parse {}: {}", path, e);
+            process::exit(1);
+        });
+
+        update_state(&chain_id, new_state, self.force);
+    }
+}
@@ -0,0 +1,47 @@
+//! `tmkms state reset` subcommand
+
+use super::{required_chain_id, update_state};
+use crate::{chain, prelude::*};
+use abscissa_core::{Command, Options, Runnable};
+use std::{path::PathBuf, process};
+use tendermint::consensus;
+
+/// `state reset` command
+#[derive(Command, Debug, Default, Options)]
+pub struct ResetCommand {
+    /// Path to configuration file
+    #[options(short = "c", long = "config", help = "path to tmkms.toml")]
+    pub config: Option<PathBuf>,
+
+    /// Confirm resetting the state
+    #[options(no_short, help = "confirm resetting the state (DANGER!)")]
+    force: bool,
+
+    /// Chain to reset the state of
+    #[options(free, help = "chain ID")]
+    chain_id: Option<chain::Id>,
+}
+
+impl Runnable for ResetCommand {
+    /// Reset the state of a chain to height 0
+    fn run(&self) {
+        let chain_id = required_chain_id(&self.chain_id, "reset [-c tmkms.toml] --force CHAIN_ID");
+
+        // Resetting always moves the state backwards
+        if !self.force {
+            status_err!(
+                "resetting the state of {} disables double signing protection; \
+                 rerun with --force if you're sure",
+                chain_id
+            );
+            process::exit(1);
+        }
+
+        let new_state = consensus::State {
+            height: 0u64.into(),
+            ..consensus::State::default()
+        };
+
+        update_state(&chain_id, new_state, true);
+    }
+}
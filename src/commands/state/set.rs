@@ -0,0 +1,60 @@
+//! `tmkms state set` subcommand
+
+use super::{required_chain_id, update_state};
+use crate::chain;
+use abscissa_core::{Command, Options, Runnable};
+use std::{path::PathBuf, process};
+use tendermint::consensus;
+
+/// `state set` command
+#[derive(Command, Debug, Default, Options)]
+pub struct SetCommand {
+    /// Path to configuration file
+    #[options(short = "c", long = "config", help = "path to tmkms.toml")]
+    pub config: Option<PathBuf>,
+
+    /// Last signed height
+    #[options(no_short, help = "last signed height (required)")]
+    height: Option<u64>,
+
+    /// Last signed round
+    #[options(no_short, help = "last signed round (default 0)")]
+    round: i64,
+
+    /// Last signed step
+    #[options(
+        no_short,
+        help = "last signed step (0: proposal, 1: prevote, 2: precommit)"
+    )]
+    step: i8,
+
+    /// Allow moving the state backwards
+    #[options(no_short, help = "allow moving the state backwards (DANGER!)")]
+    force: bool,
+
+    /// Chain to set the state of
+    #[options(free, help = "chain ID")]
+    chain_id: Option<chain::Id>,
+}
+
+impl Runnable for SetCommand {
+    /// Set the last signed height/round/step of a chain
+    fn run(&self) {
+        let usage = "set [-c tmkms.toml] --height H [--round R] [--step S] [--force] CHAIN_ID";
+        let chain_id = required_chain_id(&self.chain_id, usage);
+
+        let height = self.height.unwrap_or_else(|| {
+            eprintln!("Usage: tmkms state {}", usage);
+            process::exit(1);
+        });
+
+        let new_state = consensus::State {
+            height: height.into(),
+            round: self.round,
+            step: self.step,
+            block_id: None,
+        };
+
+        update_state(&chain_id, new_state, self.force);
+    }
+}
@@ -0,0 +1,41 @@
+//! `tmkms state show` subcommand
+
+use super::load_state;
+use crate::{application::app_config, chain, prelude::*};
+use abscissa_core::{Command, Options, Runnable};
+use std::path::PathBuf;
+
+/// `state show` command
+#[derive(Command, Debug, Default, Options)]
+pub struct ShowCommand {
+    /// Path to configuration file
+    #[options(short = "c", long = "config", help = "path to tmkms.toml")]
+    pub config: Option<PathBuf>,
+
+    /// Chain to show the state of (default all)
+    #[options(free, help = "chain ID (default: all chains in tmkms.toml)")]
+    chain_id: Option<chain::Id>,
+}
+
+impl Runnable for ShowCommand {
+    /// Show the last signed height/round/step of chains
+    fn run(&self) {
+        let chain_ids = match self.chain_id {
+            Some(chain_id) => vec![chain_id],
+            None => app_config().chain.iter().map(|chain| chain.id).collect(),
+        };
+
+        for chain_id in &chain_ids {
+            let state = load_state(chain_id);
+            let consensus_state = state.consensus_state();
+            let attr = format!("- {}", chain_id);
+
+            status_attr_ok!(
+                attr,
+                "h/r/s {} (block ID: {})",
+                consensus_state,
+                consensus_state.block_id_prefix()
+            );
+        }
+    }
+}
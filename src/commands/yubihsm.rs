@@ -1,17 +1,27 @@
 //! `tmkms yubihsm` CLI (sub)commands
 
+mod backup;
 mod detect;
 mod keys;
+mod log;
+mod restore;
 mod setup;
 mod test;
 
-pub use self::{detect::DetectCommand, keys::KeysCommand, setup::SetupCommand, test::TestCommand};
+pub use self::{
+    backup::BackupCommand, detect::DetectCommand, keys::KeysCommand, log::LogCommand,
+    restore::RestoreCommand, setup::SetupCommand, test::TestCommand,
+};
 use abscissa_core::{Command, Help, Options, Runnable};
 use std::path::PathBuf;
 
 /// The `yubihsm` subcommand
 #[derive(Command, Debug, Options, Runnable)]
 pub enum YubihsmCommand {
+    /// Back up the contents of the YubiHSM2
+    #[options(help = "back up all exportable objects into an encrypted archive")]
+    Backup(BackupCommand),
+
     /// Detected connected YubiHSM2 devices
     #[options(help = "detect all YubiHSM2 devices connected via USB")]
     Detect(DetectCommand),
@@ -24,6 +34,14 @@ pub enum YubihsmCommand {
     #[options(help = "key management subcommands")]
     Keys(KeysCommand),
 
+    /// Dump and verify the audit log
+    #[options(help = "dump and verify the audit log")]
+    Log(LogCommand),
+
+    /// Restore the contents of the YubiHSM2 from a backup
+    #[options(help = "restore objects from a backup archive")]
+    Restore(RestoreCommand),
+
     /// Perform initial YubiHSM2 device setup
     #[options(help = "initial device setup and configuration")]
     Setup(SetupCommand),
@@ -39,7 +57,10 @@ impl YubihsmCommand {
         crate::yubihsm::mark_cli_command();
 
         match self {
+            YubihsmCommand::Backup(backup) => backup.config.as_ref(),
             YubihsmCommand::Keys(keys) => keys.config_path(),
+            YubihsmCommand::Log(log) => log.config.as_ref(),
+            YubihsmCommand::Restore(restore) => restore.config.as_ref(),
             YubihsmCommand::Setup(setup) => setup.config.as_ref(),
             YubihsmCommand::Test(test) => test.config.as_ref(),
             _ => None,
@@ -55,3 +76,31 @@ impl YubihsmCommand {
         }
     }
 }
+
+/// List the given domains
+fn domain_list(domains: yubihsm::Domain) -> String {
+    if domains == yubihsm::Domain::all() {
+        return "all".to_owned();
+    }
+
+    (0..16)
+        .filter(|i| domains.bits() & (1 << i) != 0)
+        .map(|i| (i + 1).to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// List the given capabilities
+fn capability_list(capabilities: yubihsm::Capability) -> String {
+    if capabilities == yubihsm::Capability::all() {
+        return "all".to_owned();
+    }
+
+    let names = crate::yubihsm::capability_names(capabilities);
+
+    if names.is_empty() {
+        "(none)".to_owned()
+    } else {
+        names.join(", ")
+    }
+}
@@ -0,0 +1,76 @@
+//! Back up the contents of the YubiHSM2
+
+use super::keys::DEFAULT_WRAP_KEY;
+use crate::{prelude::*, yubihsm::backup::Archive};
+use abscissa_core::{Command, Options, Runnable};
+use std::{path::PathBuf, process};
+
+/// The `yubihsm backup` subcommand
+#[derive(Command, Debug, Default, Options)]
+pub struct BackupCommand {
+    /// Path to configuration file
+    #[options(short = "c", long = "config", help = "path to tmkms.toml")]
+    pub config: Option<PathBuf>,
+
+    /// ID of the wrap key to encrypt the backup under
+    #[options(short = "w", long = "wrapkey", help = "wrap key to encrypt the backup")]
+    pub wrap_key_id: Option<u16>,
+
+    /// Path to write the archive to
+    #[options(free, help = "path where the backup archive will be written")]
+    pub path: Option<PathBuf>,
+}
+
+impl Runnable for BackupCommand {
+    /// Export every exportable object in the HSM into a backup archive
+    fn run(&self) {
+        let path = self.path.as_ref().unwrap_or_else(|| {
+            status_err!("no path given for the backup archive");
+            process::exit(1);
+        });
+
+        if path.exists() {
+            status_err!("backup archive {} already exists", path.display());
+            process::exit(1);
+        }
+
+        let wrap_key_id = self.wrap_key_id.unwrap_or(DEFAULT_WRAP_KEY);
+
+        let archive = Archive::create(&crate::yubihsm::client(), wrap_key_id).unwrap_or_else(|e| {
+            status_err!("couldn't back up YubiHSM2: {}", e);
+            process::exit(1);
+        });
+
+        for object in &archive.skipped {
+            status_warn!(
+                "skipping {} 0x{:04x} (label: \"{}\"): not exportable",
+                object.object_type,
+                object.object_id,
+                object.label
+            );
+        }
+
+        archive.write(path).unwrap_or_else(|e| {
+            status_err!("{}", e);
+            process::exit(1);
+        });
+
+        for object in &archive.objects {
+            status_ok!(
+                "Exported",
+                "{} 0x{:04x} (label: \"{}\")",
+                object.object_type,
+                object.object_id,
+                object.label
+            );
+        }
+
+        status_ok!(
+            "Wrote",
+            "backup of {} objects (encrypted under wrap key 0x{:04x}) to {}",
+            archive.objects.len(),
+            wrap_key_id,
+            path.display()
+        );
+    }
+}
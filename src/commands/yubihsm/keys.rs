@@ -1,13 +1,19 @@
 //! YubiHSM2 key management commands
 
+mod attest;
+mod delete;
 mod export;
 mod generate;
 mod import;
+mod info;
 mod list;
 
 use self::{
-    export::ExportCommand, generate::GenerateCommand, import::ImportCommand, list::ListCommand,
+    attest::AttestCommand, delete::DeleteCommand, export::ExportCommand, generate::GenerateCommand,
+    import::ImportCommand, info::InfoCommand, list::ListCommand,
 };
+use super::{capability_list, domain_list};
+use crate::chain;
 use abscissa_core::{Command, Help, Options, Runnable};
 use std::path::PathBuf;
 
@@ -26,6 +32,14 @@ pub const DEFAULT_WRAP_KEY: yubihsm::object::Id = 1;
 /// The `yubihsm keys` subcommand
 #[derive(Command, Debug, Options, Runnable)]
 pub enum KeysCommand {
+    #[options(
+        help = "attest a key was generated inside the HSM device and verify the attestation"
+    )]
+    Attest(AttestCommand),
+
+    #[options(help = "delete a signing key from the HSM device (after checking for a backup)")]
+    Delete(DeleteCommand),
+
     #[options(help = "export an encrypted backup of a signing key inside the HSM device")]
     Export(ExportCommand),
 
@@ -38,6 +52,9 @@ pub enum KeysCommand {
     #[options(help = "import validator signing key for the 'yubihsm keys' subcommand")]
     Import(ImportCommand),
 
+    #[options(help = "show detailed information about a key in the HSM device")]
+    Info(InfoCommand),
+
     #[options(help = "list all suitable Ed25519 keys in the HSM")]
     List(ListCommand),
 }
@@ -46,11 +63,36 @@ impl KeysCommand {
     /// Optional path to the configuration file
     pub(super) fn config_path(&self) -> Option<&PathBuf> {
         match self {
+            KeysCommand::Attest(attest) => attest.config.as_ref(),
+            KeysCommand::Delete(delete) => delete.config.as_ref(),
             KeysCommand::Export(export) => export.config.as_ref(),
             KeysCommand::Generate(generate) => generate.config.as_ref(),
+            KeysCommand::Info(info) => info.config.as_ref(),
             KeysCommand::List(list) => list.config.as_ref(),
             KeysCommand::Import(import) => import.config.as_ref(),
             _ => None,
         }
     }
 }
+
+/// Chains the given key is configured to sign for in `tmkms.toml`
+fn configured_chains(key_id: yubihsm::object::Id) -> Vec<chain::Id> {
+    crate::yubihsm::config()
+        .keys
+        .iter()
+        .filter(|key_config| key_config.key == key_id)
+        .flat_map(|key_config| key_config.chain_ids.iter().cloned())
+        .collect()
+}
+
+/// Describe where a key came from
+fn origin_description(origin: yubihsm::object::Origin) -> &'static str {
+    use yubihsm::object::Origin;
+
+    match origin {
+        Origin::Generated => "generated inside the HSM",
+        Origin::Imported => "imported",
+        Origin::WrappedGenerated => "generated inside an HSM (restored from a backup)",
+        Origin::WrappedImported => "imported (restored from a backup)",
+    }
+}
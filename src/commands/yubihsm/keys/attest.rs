@@ -0,0 +1,204 @@
+//! Attest that keys were generated inside the YubiHSM2
+
+use super::*;
+use crate::{
+    prelude::*,
+    yubihsm::attestation::{self, Certificate},
+};
+use abscissa_core::{Command, Options, Runnable};
+use std::{
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+    process,
+};
+use subtle_encoding::hex;
+use yubihsm::object;
+
+/// The `yubihsm keys attest` subcommand
+#[derive(Command, Debug, Default, Options)]
+pub struct AttestCommand {
+    /// Path to configuration file
+    #[options(short = "c", long = "config", help = "path to tmkms.toml")]
+    pub config: Option<PathBuf>,
+
+    /// Trusted root certificates
+    #[options(
+        short = "r",
+        long = "root",
+        help = "trusted root certificate(s) to verify against, e.g. Yubico's (PEM or DER)"
+    )]
+    pub roots: Vec<PathBuf>,
+
+    /// Intermediate certificates
+    #[options(
+        short = "i",
+        long = "intermediate",
+        help = "intermediate certificate(s) between the device and the root (PEM or DER)"
+    )]
+    pub intermediates: Vec<PathBuf>,
+
+    /// Path to write the attestation certificate chain to
+    #[options(
+        short = "o",
+        long = "output",
+        help = "write the attestation and device certificates to this file (PEM)"
+    )]
+    pub output: Option<PathBuf>,
+
+    /// Key ID to attest
+    #[options(free, help = "key ID to attest")]
+    key_ids: Vec<u16>,
+}
+
+impl Runnable for AttestCommand {
+    /// Produce and verify an attestation certificate for a key in the HSM
+    fn run(&self) {
+        if self.key_ids.len() != 1 {
+            status_err!(
+                "expected exactly 1 key ID to attest, got {}",
+                self.key_ids.len()
+            );
+            process::exit(1);
+        }
+
+        let key_id = self.key_ids[0];
+
+        // Read the trusted certificates first, so mistakes are reported
+        // before we talk to the HSM
+        let roots = read_certificates(&self.roots);
+        let mut intermediates = read_certificates(&self.intermediates);
+
+        let hsm = crate::yubihsm::client();
+
+        let info = hsm
+            .get_object_info(key_id, object::Type::AsymmetricKey)
+            .unwrap_or_else(|e| {
+                status_err!("couldn't get object info for key 0x{:04x}: {}", key_id, e);
+                process::exit(1);
+            });
+
+        let public_key = hsm.get_public_key(key_id).unwrap_or_else(|e| {
+            status_err!("couldn't get public key for key 0x{:04x}: {}", key_id, e);
+            process::exit(1);
+        });
+
+        if public_key.algorithm != yubihsm::asymmetric::Algorithm::Ed25519 {
+            status_err!(
+                "key 0x{:04x} has unsupported algorithm: {:?}",
+                key_id,
+                public_key.algorithm
+            );
+            process::exit(1);
+        }
+
+        println!("Key 0x{:04x}:", key_id);
+        println!("  label:        {}", info.label);
+        println!("  public key:   {}", hex_string(public_key.as_ref()));
+        println!("  origin:       {}", origin_description(info.origin));
+        println!("  domains:      {}", domain_list(info.domains));
+        println!("  capabilities: {}", capability_list(info.capabilities));
+
+        let certificate = hsm
+            .sign_attestation_certificate(key_id, None)
+            .map_err(|e| e.to_string())
+            .and_then(|cert| Certificate::from_der(cert.as_slice()).map_err(|e| e.to_string()))
+            .unwrap_or_else(|e| {
+                status_err!(
+                    "couldn't get attestation certificate for key 0x{:04x}: {}",
+                    key_id,
+                    e
+                );
+                process::exit(1);
+            });
+
+        let device_certificate = hsm
+            .get_opaque(attestation::DEVICE_CERTIFICATE_ID)
+            .map_err(|e| e.to_string())
+            .and_then(|der| Certificate::from_der(&der).map_err(|e| e.to_string()))
+            .unwrap_or_else(|e| {
+                status_err!("couldn't get device attestation certificate: {}", e);
+                process::exit(1);
+            });
+
+        if certificate.ed25519_public_key() != Some(public_key.as_ref()) {
+            status_err!(
+                "attestation certificate doesn't match the public key of key 0x{:04x}",
+                key_id
+            );
+            process::exit(1);
+        }
+
+        if let Some(output) = &self.output {
+            write_certificates(output, &[&certificate, &device_certificate]);
+        }
+
+        if roots.is_empty() {
+            status_warn!("certificate chain not verified (no trusted root given with --root)");
+            return;
+        }
+
+        intermediates.push(device_certificate.clone());
+
+        let chain =
+            attestation::verify_chain(&certificate, &intermediates, &roots).unwrap_or_else(|e| {
+                status_err!("attestation certificate chain is invalid: {}", e);
+                process::exit(1);
+            });
+
+        status_ok!(
+            "Verified",
+            "attestation for key 0x{:04x}: {}",
+            key_id,
+            chain
+                .iter()
+                .map(|cert| format!("'{}'", cert.subject_name()))
+                .collect::<Vec<_>>()
+                .join(" <- ")
+        );
+    }
+}
+
+/// Read certificates from the given files
+fn read_certificates(paths: &[PathBuf]) -> Vec<Certificate> {
+    let mut certificates = vec![];
+
+    for path in paths {
+        certificates.extend(Certificate::read_file(path).unwrap_or_else(|e| {
+            status_err!("{}", e);
+            process::exit(1);
+        }));
+    }
+
+    certificates
+}
+
+/// Write the given certificates to a PEM file
+fn write_certificates(path: &Path, certificates: &[&Certificate]) {
+    let pem = certificates
+        .iter()
+        .map(|cert| cert.to_pem())
+        .collect::<String>();
+
+    OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(pem.as_bytes()))
+        .unwrap_or_else(|e| {
+            status_err!("couldn't write {}: {}", path.display(), e);
+            process::exit(1);
+        });
+
+    status_ok!(
+        "Wrote",
+        "attestation certificate chain to {}",
+        path.display()
+    );
+}
+
+/// Format bytes as uppercase hex
+fn hex_string(bytes: &[u8]) -> String {
+    String::from_utf8(hex::encode_upper(bytes)).unwrap()
+}
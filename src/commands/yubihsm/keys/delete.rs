@@ -0,0 +1,286 @@
+//! Delete keys from the YubiHSM2
+
+use super::*;
+use crate::{
+    error::{Error, ErrorKind::*},
+    prelude::*,
+};
+use abscissa_core::{Command, Options, Runnable};
+use std::{
+    fs::OpenOptions,
+    io::{self, Write},
+    os::unix::fs::OpenOptionsExt,
+    path::{Path, PathBuf},
+    process,
+};
+use subtle_encoding::base64;
+use yubihsm::{object, Capability, Client};
+
+/// The `yubihsm keys delete` subcommand
+#[derive(Command, Debug, Default, Options)]
+pub struct DeleteCommand {
+    /// Path to configuration file
+    #[options(short = "c", long = "config", help = "path to tmkms.toml")]
+    pub config: Option<PathBuf>,
+
+    /// Path to write an encrypted backup of the key to before deleting it
+    #[options(
+        short = "b",
+        long = "backup",
+        help = "path to write an encrypted backup of the key to before deleting it"
+    )]
+    pub backup: Option<PathBuf>,
+
+    /// ID of the wrap key to encrypt the backup under
+    #[options(
+        short = "w",
+        long = "wrapkey",
+        help = "wrap key to encrypt the backup under"
+    )]
+    pub wrap_key_id: Option<u16>,
+
+    /// Delete the key without a backup
+    #[options(
+        no_short,
+        long = "no-backup",
+        help = "delete the key without a backup (it will be lost forever)"
+    )]
+    pub no_backup: bool,
+
+    /// Don't ask for confirmation
+    #[options(short = "y", long = "yes", help = "don't ask for confirmation")]
+    pub yes: bool,
+
+    /// Key ID to delete
+    #[options(free, help = "key ID to delete")]
+    key_ids: Vec<u16>,
+}
+
+impl Runnable for DeleteCommand {
+    /// Delete a key from the HSM
+    fn run(&self) {
+        if self.key_ids.len() != 1 {
+            status_err!(
+                "expected exactly 1 key ID to delete, got {}",
+                self.key_ids.len()
+            );
+            process::exit(1);
+        }
+
+        let key_id = self.key_ids[0];
+
+        match (&self.backup, self.no_backup) {
+            (Some(backup), false) => {
+                if backup.exists() {
+                    status_err!("backup file {} already exists", backup.display());
+                    process::exit(1);
+                }
+            }
+            (None, true) => (),
+            (Some(_), true) => {
+                status_err!("--backup and --no-backup are mutually exclusive");
+                process::exit(1);
+            }
+            (None, false) => {
+                status_err!(
+                    "refusing to delete key 0x{:04x} without a backup: pass --backup with a \
+                     path to write one to (or pass --no-backup)",
+                    key_id
+                );
+                process::exit(1);
+            }
+        }
+
+        let hsm = crate::yubihsm::client();
+
+        let info = hsm
+            .get_object_info(key_id, object::Type::AsymmetricKey)
+            .unwrap_or_else(|e| {
+                status_err!("couldn't get object info for key 0x{:04x}: {}", key_id, e);
+                process::exit(1);
+            });
+
+        if self.backup.is_some()
+            && !info
+                .capabilities
+                .contains(Capability::EXPORTABLE_UNDER_WRAP)
+        {
+            status_err!(
+                "key 0x{:04x} isn't exportable, so it can't be backed up (pass --no-backup)",
+                key_id
+            );
+            process::exit(1);
+        }
+
+        let chains = configured_chains(key_id);
+
+        if !chains.is_empty() {
+            status_warn!(
+                "key 0x{:04x} is configured in tmkms.toml for: {}",
+                key_id,
+                chains
+                    .iter()
+                    .map(|chain_id| chain_id.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+
+        if !self.yes {
+            prompt_for_user_approval(&format!(
+                "Delete key 0x{:04x} (label: \"{}\")? This can't be undone",
+                key_id, info.label
+            ));
+        }
+
+        self.delete(&hsm, key_id).unwrap_or_else(|e| {
+            status_err!("{}", e);
+            process::exit(1);
+        });
+
+        if let Some(backup) = &self.backup {
+            status_ok!(
+                "Exported",
+                "key 0x{:04x} (encrypted under wrap key 0x{:04x}) to {}",
+                key_id,
+                self.wrap_key_id.unwrap_or(DEFAULT_WRAP_KEY),
+                backup.display()
+            );
+        }
+
+        status_ok!("Deleted", "key 0x{:04x}", key_id);
+    }
+}
+
+impl DeleteCommand {
+    /// Write the backup (if any), then delete the key
+    fn delete(&self, hsm: &Client, key_id: u16) -> Result<(), Error> {
+        if let Some(backup) = &self.backup {
+            let wrap_key_id = self.wrap_key_id.unwrap_or(DEFAULT_WRAP_KEY);
+            write_backup(hsm, key_id, wrap_key_id, backup)?;
+        }
+
+        hsm.delete_object(key_id, object::Type::AsymmetricKey)
+            .map_err(|e| {
+                format_err!(YubihsmError, "couldn't delete key 0x{:04x}: {}", key_id, e)
+            })?;
+
+        Ok(())
+    }
+}
+
+/// Export the key under the given wrap key and write it to a new file at `path`
+fn write_backup(hsm: &Client, key_id: u16, wrap_key_id: u16, path: &Path) -> Result<(), Error> {
+    let wrapped_bytes = hsm
+        .export_wrapped(wrap_key_id, object::Type::AsymmetricKey, key_id)
+        .map_err(|e| {
+            format_err!(
+                YubihsmError,
+                "couldn't export key 0x{:04x} under wrap key 0x{:04x}: {}",
+                key_id,
+                wrap_key_id,
+                e
+            )
+        })?;
+
+    OpenOptions::new()
+        .create_new(true)
+        .write(true)
+        .mode(0o600)
+        .open(path)
+        .and_then(|mut backup_file| {
+            backup_file.write_all(&base64::encode(wrapped_bytes.into_vec()))?;
+            backup_file.sync_all()
+        })
+        .map_err(|e| {
+            format_err!(
+                IoError,
+                "couldn't write backup to {}: {}",
+                path.display(),
+                e
+            )
+        })?;
+
+    Ok(())
+}
+
+/// Prompt the user to ensure they want to proceed
+fn prompt_for_user_approval(prompt: &str) {
+    print!("*** {} (y/N): ", prompt);
+    io::stdout().flush().unwrap();
+
+    let mut choice = String::new();
+    io::stdin().read_line(&mut choice).unwrap_or_else(|e| {
+        status_err!("couldn't read confirmation: {}", e);
+        process::exit(1);
+    });
+
+    if choice.trim() != "y" && choice.trim() != "Y" {
+        println!("Aborting");
+        process::exit(1);
+    }
+}
+
+#[cfg(all(test, feature = "yubihsm-mock"))]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+    use yubihsm::{asymmetric, wrap, Connector, Credentials, Domain};
+
+    /// Wrap key used in tests
+    const WRAP_KEY_ID: u16 = 1;
+
+    /// Key deleted in tests
+    const KEY_ID: u16 = 0x100;
+
+    #[test]
+    fn deletes_key_after_writing_backup() {
+        let hsm = Client::open(Connector::mockhsm(), Credentials::default(), true).unwrap();
+
+        hsm.put_wrap_key(
+            WRAP_KEY_ID,
+            "delete wrap key".into(),
+            Domain::all(),
+            Capability::EXPORT_WRAPPED | Capability::IMPORT_WRAPPED,
+            Capability::all(),
+            wrap::Algorithm::Aes256Ccm,
+            [0x42u8; 32].as_ref(),
+        )
+        .unwrap();
+
+        hsm.generate_asymmetric_key(
+            KEY_ID,
+            "test key".into(),
+            Domain::DOM1,
+            Capability::SIGN_EDDSA | Capability::EXPORTABLE_UNDER_WRAP,
+            asymmetric::Algorithm::Ed25519,
+        )
+        .unwrap();
+
+        let public_key = hsm.get_public_key(KEY_ID).unwrap();
+
+        let tempdir = TempDir::new().unwrap();
+        let backup_path = tempdir.path().join("key-0x0100.enc");
+
+        let command = DeleteCommand {
+            backup: Some(backup_path.clone()),
+            wrap_key_id: Some(WRAP_KEY_ID),
+            yes: true,
+            key_ids: vec![KEY_ID],
+            ..Default::default()
+        };
+
+        command.delete(&hsm, KEY_ID).unwrap();
+
+        assert!(hsm
+            .get_object_info(KEY_ID, object::Type::AsymmetricKey)
+            .is_err());
+
+        // The backup can be imported to get the same key back
+        let backup = base64::decode(fs::read(&backup_path).unwrap()).unwrap();
+        hsm.import_wrapped(WRAP_KEY_ID, wrap::Message::from_vec(backup).unwrap())
+            .unwrap();
+        assert_eq!(hsm.get_public_key(KEY_ID).unwrap(), public_key);
+    }
+}
@@ -0,0 +1,81 @@
+//! Show detailed information about keys inside the YubiHSM2
+
+use super::*;
+use crate::prelude::*;
+use abscissa_core::{Command, Options, Runnable};
+use std::{path::PathBuf, process};
+use subtle_encoding::hex;
+use yubihsm::object;
+
+/// The `yubihsm keys info` subcommand
+#[derive(Command, Debug, Default, Options)]
+pub struct InfoCommand {
+    /// Path to configuration file
+    #[options(short = "c", long = "config", help = "path to tmkms.toml")]
+    pub config: Option<PathBuf>,
+
+    /// Key ID to show information about
+    #[options(free, help = "key ID to show information about")]
+    key_ids: Vec<u16>,
+}
+
+impl Runnable for InfoCommand {
+    /// Show the object info for a key in the HSM
+    fn run(&self) {
+        if self.key_ids.len() != 1 {
+            status_err!(
+                "expected exactly 1 key ID to show, got {}",
+                self.key_ids.len()
+            );
+            process::exit(1);
+        }
+
+        let key_id = self.key_ids[0];
+        let hsm = crate::yubihsm::client();
+
+        let info = hsm
+            .get_object_info(key_id, object::Type::AsymmetricKey)
+            .unwrap_or_else(|e| {
+                status_err!("couldn't get object info for key 0x{:04x}: {}", key_id, e);
+                process::exit(1);
+            });
+
+        let public_key = hsm.get_public_key(key_id).unwrap_or_else(|e| {
+            status_err!("couldn't get public key for key 0x{:04x}: {}", key_id, e);
+            process::exit(1);
+        });
+
+        let chains = configured_chains(key_id)
+            .iter()
+            .map(|chain_id| chain_id.to_string())
+            .collect::<Vec<_>>();
+
+        println!("Key 0x{:04x}:", key_id);
+        println!("  label:                  {}", info.label);
+        println!("  algorithm:              {:?}", public_key.algorithm);
+        println!(
+            "  public key:             {}",
+            String::from_utf8(hex::encode_upper(public_key.as_ref())).unwrap()
+        );
+        println!(
+            "  origin:                 {}",
+            origin_description(info.origin)
+        );
+        println!("  sequence:               {}", info.sequence);
+        println!("  domains:                {}", domain_list(info.domains));
+        println!(
+            "  capabilities:           {}",
+            capability_list(info.capabilities)
+        );
+        println!(
+            "  delegated capabilities: {}",
+            capability_list(info.delegated_capabilities)
+        );
+
+        if chains.is_empty() {
+            println!("  chains:                 (not configured in tmkms.toml)");
+        } else {
+            println!("  chains:                 {}", chains.join(", "));
+        }
+    }
+}
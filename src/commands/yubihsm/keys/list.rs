@@ -1,7 +1,9 @@
 //! List keys inside the YubiHSM2
 
+use super::*;
 use crate::{application::app_config, chain, keyring, prelude::*};
 use abscissa_core::{Command, Options, Runnable};
+use serde::Serialize;
 use std::{collections::BTreeMap as Map, path::PathBuf, process};
 use tendermint::{PublicKey, TendermintKey};
 
@@ -11,6 +13,30 @@ pub struct ListCommand {
     /// Path to configuration file
     #[options(short = "c", long = "config", help = "path to tmkms.toml")]
     pub config: Option<PathBuf>,
+
+    /// Output JSON
+    #[options(
+        no_short,
+        long = "json",
+        help = "output JSON (including configured chains)"
+    )]
+    pub json: bool,
+}
+
+/// Key in the `--json` output
+#[derive(Serialize)]
+struct ListedKey {
+    /// Key ID
+    id: u16,
+
+    /// Key label
+    label: String,
+
+    /// Public key (formatted for the key's chain, if it has only one)
+    public_key: String,
+
+    /// Chains the key is configured for
+    chains: Vec<String>,
 }
 
 impl Runnable for ListCommand {
@@ -39,12 +65,16 @@ impl Runnable for ListCommand {
 
         keys.sort_by(|k1, k2| k1.object_id.cmp(&k2.object_id));
 
-        if keys.is_empty() {
+        if keys.is_empty() && !self.json {
             status_err!("no keys in this YubiHSM (#{})", serial_number);
             process::exit(0);
         }
 
-        println!("Listing keys in YubiHSM #{}:", serial_number);
+        if !self.json {
+            println!("Listing keys in YubiHSM #{}:", serial_number);
+        }
+
+        let mut listed_keys = vec![];
 
         for key in &keys {
             let public_key = hsm.get_public_key(key.object_id).unwrap_or_else(|e| {
@@ -74,7 +104,35 @@ impl Runnable for ListCommand {
                 None => tendermint_key.to_hex(),
             };
 
-            status_attr_ok!(key_id, public_key_serialized);
+            if !self.json {
+                status_attr_ok!(key_id, public_key_serialized);
+                continue;
+            }
+
+            let info = hsm
+                .get_object_info(key.object_id, key.object_type)
+                .unwrap_or_else(|e| {
+                    status_err!(
+                        "couldn't get object info for asymmetric key #{}: {}",
+                        key.object_id,
+                        e
+                    );
+                    process::exit(1);
+                });
+
+            listed_keys.push(ListedKey {
+                id: key.object_id,
+                label: info.label.to_string(),
+                public_key: public_key_serialized,
+                chains: configured_chains(key.object_id)
+                    .iter()
+                    .map(|chain_id| chain_id.to_string())
+                    .collect(),
+            });
+        }
+
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&listed_keys).unwrap());
         }
     }
 }
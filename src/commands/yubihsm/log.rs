@@ -0,0 +1,74 @@
+//! Dump and verify the YubiHSM2's audit log
+
+use crate::{prelude::*, yubihsm::audit_log};
+use abscissa_core::{Command, Options, Runnable};
+use std::{path::PathBuf, process};
+
+/// The `yubihsm log` subcommand
+#[derive(Command, Debug, Default, Options)]
+pub struct LogCommand {
+    /// Path to configuration file
+    #[options(short = "c", long = "config", help = "path to tmkms.toml")]
+    pub config: Option<PathBuf>,
+
+    /// Mark the entries as consumed
+    #[options(help = "mark the entries as consumed after verifying them")]
+    consume: bool,
+}
+
+impl Runnable for LogCommand {
+    /// Dump the audit log, verifying its hash chain
+    fn run(&self) {
+        let auth_key = crate::yubihsm::config().auth.key();
+        let hsm = crate::yubihsm::client();
+
+        let log = audit_log::AuditLog::read(&hsm).unwrap_or_else(|e| {
+            status_err!("couldn't read audit log: {}", e);
+            process::exit(1);
+        });
+
+        println!(
+            "{} entries ({} unlogged boot events, {} unlogged auth events)",
+            log.entries.len(),
+            log.unlogged_boot_events,
+            log.unlogged_auth_events
+        );
+
+        for entry in &log.entries {
+            println!("{}", entry);
+        }
+
+        for entry in &log.entries {
+            if let Some(reason) = entry.unexpected(auth_key) {
+                status_warn!("#{}: {}", entry.item, reason);
+            }
+        }
+
+        let problems = audit_log::verify(&log.entries, None);
+
+        if !problems.is_empty() {
+            for problem in &problems {
+                status_err!("{}", problem);
+            }
+
+            process::exit(1);
+        }
+
+        status_ok!(
+            "Verified",
+            "hash chain of {} audit log entries",
+            log.entries.len()
+        );
+
+        if self.consume {
+            if let Some(last_entry) = log.entries.last() {
+                hsm.set_log_index(last_entry.item).unwrap_or_else(|e| {
+                    status_err!("couldn't mark audit log entries as consumed: {}", e);
+                    process::exit(1);
+                });
+
+                status_ok!("Consumed", "audit log entries up to #{}", last_entry.item);
+            }
+        }
+    }
+}
@@ -0,0 +1,76 @@
+//! Restore the contents of the YubiHSM2 from a backup
+
+use crate::{
+    prelude::*,
+    yubihsm::backup::{Archive, RestoreStatus},
+};
+use abscissa_core::{Command, Options, Runnable};
+use std::{path::PathBuf, process};
+
+/// The `yubihsm restore` subcommand
+#[derive(Command, Debug, Default, Options)]
+pub struct RestoreCommand {
+    /// Path to configuration file
+    #[options(short = "c", long = "config", help = "path to tmkms.toml")]
+    pub config: Option<PathBuf>,
+
+    /// Path to the archive to restore
+    #[options(free, help = "path to a backup archive (from 'tmkms yubihsm backup')")]
+    pub path: Option<PathBuf>,
+}
+
+impl Runnable for RestoreCommand {
+    /// Import every object in a backup archive into the HSM
+    fn run(&self) {
+        let path = self.path.as_ref().unwrap_or_else(|| {
+            status_err!("no path given for the backup archive");
+            process::exit(1);
+        });
+
+        let archive = Archive::read(path).unwrap_or_else(|e| {
+            status_err!("{}", e);
+            process::exit(1);
+        });
+
+        println!(
+            "Restoring backup of YubiHSM2 {} made at {} ({} objects, wrap key 0x{:04x})",
+            archive.serial_number,
+            archive.created_at,
+            archive.objects.len(),
+            archive.wrap_key_id
+        );
+
+        let results = archive
+            .restore(&crate::yubihsm::client())
+            .unwrap_or_else(|e| {
+                status_err!("couldn't restore backup: {}", e);
+                process::exit(1);
+            });
+
+        for result in &results {
+            match result.status {
+                RestoreStatus::Imported => status_ok!(
+                    "Imported",
+                    "{} 0x{:04x} (label: \"{}\")",
+                    result.object_type,
+                    result.object_id,
+                    result.label
+                ),
+                RestoreStatus::AlreadyPresent => status_ok!(
+                    "Present",
+                    "{} 0x{:04x} (label: \"{}\") already exists",
+                    result.object_type,
+                    result.object_id,
+                    result.label
+                ),
+            }
+        }
+
+        status_ok!(
+            "Restored",
+            "{} objects from {} (public keys verified)",
+            results.len(),
+            path.display()
+        );
+    }
+}
@@ -1,5 +1,9 @@
 //! Set up a new YubiHSM2 or restore from backup
 
+mod spec;
+
+use self::spec::{Spec, WrapKeySpec, ADMIN_KEY_ID, ADMIN_ROLE_NAME};
+use super::{capability_list, domain_list};
 use crate::prelude::*;
 use abscissa_core::{Command, Options, Runnable};
 use chrono::{SecondsFormat, Utc};
@@ -17,7 +21,7 @@ use subtle_encoding::{bech32::Bech32, hex};
 use yubihsm::{
     authentication, object,
     setup::{Profile, Role},
-    wrap, AuditOption, Capability, Connector, Credentials, Domain,
+    wrap, Capability, Connector, Credentials, Domain,
 };
 use zeroize::{Zeroize, Zeroizing};
 
@@ -34,12 +38,6 @@ const DERIVATION_VERSION: &[u8] = b"1";
 /// This results in a 24-word BIP39 [`mnemonic::Phrase`].
 const KEY_SIZE: usize = 32;
 
-/// Role names
-const ADMIN_ROLE_NAME: &str = "admin";
-const OPERATOR_ROLE_NAME: &str = "operator";
-const AUDITOR_ROLE_NAME: &str = "auditor";
-const VALIDATOR_ROLE_NAME: &str = "validator";
-
 /// The `yubihsm setup` subcommand: performs initial device provisioning
 /// including creation of initial authentication and wrap keys.
 #[derive(Command, Debug, Default, Options)]
@@ -60,6 +58,22 @@ pub struct SetupCommand {
     #[options(short = "r", long = "restore", help = "restore from existing 24-words")]
     pub restore: bool,
 
+    /// Provisioning spec describing the roles and wrap keys to create
+    #[options(
+        short = "s",
+        long = "spec",
+        help = "provisioning spec (TOML) with the roles and wrap keys to create"
+    )]
+    pub spec: Option<PathBuf>,
+
+    /// Only show what would be provisioned
+    #[options(no_short, long = "dry-run", help = "show the provisioning plan ONLY")]
+    pub dry_run: bool,
+
+    /// Compare the device to the provisioning spec
+    #[options(no_short, long = "verify", help = "compare the device to the spec")]
+    pub verify: bool,
+
     /// Write a provisioning report as JSON to the given filename
     #[options(
         short = "w",
@@ -72,6 +86,26 @@ pub struct SetupCommand {
 impl Runnable for SetupCommand {
     /// Perform initial YubiHSM dervice provisioning
     fn run(&self) {
+        let spec = match &self.spec {
+            Some(path) => Spec::read(path).unwrap_or_else(|e| {
+                status_err!("{}", e);
+                exit(1);
+            }),
+            None => Spec::default(),
+        };
+
+        if self.dry_run {
+            println!("The following objects would be created:");
+            println!();
+            print_plan(&spec);
+            exit(0);
+        }
+
+        if self.verify {
+            verify_device(&spec);
+            exit(0);
+        }
+
         let hsm_connector = crate::yubihsm::connector();
         let hsm_serial_number = get_hsm_client(&hsm_connector)
             .device_info()
@@ -87,26 +121,17 @@ impl Runnable for SetupCommand {
             generate_mnemonic_from_hsm_and_os_csprngs(&hsm_connector)
         };
 
-        let roles = derive_roles_from_mnemonic(&mnemonic);
-        let wrap_key = derive_wrap_key_from_mnemonic(&mnemonic, 1);
+        let roles = derive_roles_from_mnemonic(&mnemonic, &spec);
+        let wrap_keys = spec
+            .wrap_keys
+            .iter()
+            .map(|wrap_key| derive_wrap_key_from_mnemonic(&mnemonic, wrap_key))
+            .collect::<Vec<_>>();
 
-        // TODO(tarcieri): support for enabling forced auditing
         let profile = Profile::default()
-            .audit_option(AuditOption::Off)
+            .audit_option(spec.audit)
             .roles(roles)
-            .wrap_keys(vec![wrap_key]);
-
-        let operator_password = RolePassword::derive_from_mnemonic(&mnemonic, OPERATOR_ROLE_NAME);
-        let auditor_password = RolePassword::derive_from_mnemonic(&mnemonic, AUDITOR_ROLE_NAME);
-        let validator_password = RolePassword::derive_from_mnemonic(&mnemonic, VALIDATOR_ROLE_NAME);
-
-        // Re-derive wrap key for display
-        // TODO(tarcieri): allow access to the underlying wrap key secret in `yubihsm` crate to avoid this
-        let wrapkey_material =
-            derive_secret_from_mnemonic(&mnemonic, &[b"wrap", serialize_key_id(1).as_bytes()]);
-
-        let wrapkey_hex =
-            Zeroizing::new(String::from_utf8(hex::encode(wrapkey_material.as_bytes())).unwrap());
+            .wrap_keys(wrap_keys);
 
         if self.print_only {
             if self.restore {
@@ -125,24 +150,59 @@ impl Runnable for SetupCommand {
         }
 
         println!();
-        println!("- key 0x0001: admin:");
+        println!("- key 0x{:04x}: {}:", ADMIN_KEY_ID, ADMIN_ROLE_NAME);
         println!();
         print_mnemonic(&mnemonic);
         println!();
-        println!(
-            "- authkey 0x0002 [operator]:  {}",
-            operator_password.as_str()
-        );
-        println!(
-            "- authkey 0x0003 [auditor]:   {}",
-            auditor_password.as_str()
-        );
-        println!(
-            "- authkey 0x0004 [validator]: {}",
-            validator_password.as_str()
-        );
 
-        println!("- wrapkey 0x0001 [primary]:   {}", wrapkey_hex.as_str());
+        // Width of the `[name]:` column
+        let width = spec
+            .roles
+            .iter()
+            .map(|role| role.name.len())
+            .chain(spec.wrap_keys.iter().map(|wrap_key| wrap_key.label.len()))
+            .max()
+            .unwrap_or(0)
+            + 3;
+
+        for role in &spec.roles {
+            let password = RolePassword::derive_from_mnemonic(&mnemonic, &role.name);
+            let role_name = format!("[{}]:", role.name);
+            println!(
+                "- authkey 0x{:04x} {:<width$} {}",
+                role.key_id,
+                role_name,
+                password.as_str(),
+                width = width
+            );
+        }
+
+        for wrap_key in &spec.wrap_keys {
+            // Re-derive wrap key for display
+            // TODO(tarcieri): allow access to the underlying wrap key secret in `yubihsm` crate to avoid this
+            let wrapkey_material = derive_secret_from_mnemonic(
+                &mnemonic,
+                &[b"wrap", serialize_key_id(wrap_key.key_id).as_bytes()],
+            );
+
+            let wrapkey_hex = Zeroizing::new(
+                String::from_utf8(hex::encode(wrapkey_material.as_bytes())).unwrap(),
+            );
+
+            let wrap_key_label = format!("[{}]:", wrap_key.label);
+            println!(
+                "- wrapkey 0x{:04x} {:<width$} {}",
+                wrap_key.key_id,
+                wrap_key_label,
+                wrapkey_hex.as_str(),
+                width = width
+            );
+        }
+
+        println!();
+        println!("with the following capabilities and domains:");
+        println!();
+        print_plan(&spec);
 
         if self.print_only {
             exit(0);
@@ -152,7 +212,7 @@ impl Runnable for SetupCommand {
 
         let report = yubihsm::setup::erase_device_and_init_with_profile(
             hsm_connector.clone(),
-            crate::yubihsm::config().auth.credentials(),
+            crate::yubihsm::credentials().unwrap_or_else(|e| hsm_error(&e)),
             profile,
         )
         .unwrap_or_else(|e| hsm_error(&e));
@@ -227,7 +287,7 @@ fn print_mnemonic(mnemonic: &mnemonic::Phrase) {
 fn get_hsm_client(hsm_connector: &Connector) -> yubihsm::Client {
     yubihsm::Client::open(
         hsm_connector.clone(),
-        crate::yubihsm::config().auth.credentials(),
+        crate::yubihsm::credentials().unwrap_or_else(|e| hsm_error(&e)),
         false,
     )
     .unwrap_or_else(|e| hsm_error(&e))
@@ -267,50 +327,21 @@ fn generate_mnemonic_from_hsm_and_os_csprngs(hsm_connector: &Connector) -> mnemo
     result
 }
 
-/// Derive the default roles form the given BIP39 `mnemonic::Phrase`
-fn derive_roles_from_mnemonic(mnemonic: &mnemonic::Phrase) -> Vec<Role> {
-    let admin_role = derive_admin_role_from_mnemonic(mnemonic);
-
-    // operator
-    let operator_role = derive_role_from_mnemonic(mnemonic, 2, OPERATOR_ROLE_NAME)
-        .capabilities(
-            Capability::GENERATE_ASYMMETRIC_KEY
-                | Capability::PUT_ASYMMETRIC_KEY
-                | Capability::GENERATE_HMAC_KEY
-                | Capability::PUT_HMAC_KEY
-                | Capability::IMPORT_WRAPPED
-                | Capability::EXPORT_WRAPPED
-                | Capability::GET_OPAQUE
-                | Capability::GET_OPTION
-                | Capability::GET_LOG_ENTRIES
-                | Capability::SIGN_ATTESTATION_CERTIFICATE,
-        )
-        .delegated_capabilities(Capability::all())
-        .domains(Domain::all());
-
-    // auditor
-    let auditor_role = derive_role_from_mnemonic(mnemonic, 3, AUDITOR_ROLE_NAME)
-        .capabilities(
-            Capability::GET_LOG_ENTRIES
-                | Capability::GET_OPTION
-                | Capability::PUT_OPTION
-                | Capability::GET_OPAQUE,
-        )
-        .delegated_capabilities(Capability::empty())
-        .domains(Domain::all());
-
-    // validator
-    let validator_role = derive_role_from_mnemonic(mnemonic, 4, VALIDATOR_ROLE_NAME)
-        .capabilities(
-            Capability::SIGN_ECDSA
-                | Capability::SIGN_EDDSA
-                | Capability::SIGN_ATTESTATION_CERTIFICATE
-                | Capability::GET_LOG_ENTRIES,
-        )
-        .delegated_capabilities(Capability::empty())
-        .domains(Domain::DOM1);
+/// Derive the roles in the given spec (and the admin role) from the given
+/// BIP39 `mnemonic::Phrase`
+fn derive_roles_from_mnemonic(mnemonic: &mnemonic::Phrase, spec: &Spec) -> Vec<Role> {
+    let mut roles = vec![derive_admin_role_from_mnemonic(mnemonic)];
+
+    for role in &spec.roles {
+        roles.push(
+            derive_role_from_mnemonic(mnemonic, role.key_id, &role.name)
+                .capabilities(role.capabilities)
+                .delegated_capabilities(role.delegated_capabilities)
+                .domains(role.domains),
+        );
+    }
 
-    vec![admin_role, operator_role, auditor_role, validator_role]
+    roles
 }
 
 /// Derive the admin role from the given mnemonic.
@@ -319,7 +350,7 @@ fn derive_roles_from_mnemonic(mnemonic: &mnemonic::Phrase) -> Vec<Role> {
 /// authority over the HSM device.
 fn derive_admin_role_from_mnemonic(mnemonic: &mnemonic::Phrase) -> Role {
     let admin_credentials = Credentials::new(
-        1,
+        ADMIN_KEY_ID,
         authentication::Key::derive_from_password(mnemonic.phrase().as_bytes()),
     );
 
@@ -390,41 +421,23 @@ impl Drop for RolePassword {
 }
 
 /// Derive a wrap key from the given BIP39 `mnemonic::Phrase`
-fn derive_wrap_key_from_mnemonic(mnemonic: &mnemonic::Phrase, key_id: object::Id) -> wrap::Key {
-    // Capabilities given to the initial wrap key:
-    // wrap/unwrap both data and objects
-    let wrap_key_capabilities = Capability::EXPORT_WRAPPED
-        | Capability::IMPORT_WRAPPED
-        | Capability::WRAP_DATA
-        | Capability::UNWRAP_DATA;
-
-    // Allow the initial wrap key to create new objects with any other
-    // capabilities.
-    //
-    // To prevent escalation of privilege, no roles except administrators
-    // will be given the capability to export other objects.
-    let wrap_key_delegated_capabilities = Capability::all();
-
-    // Make the wrap key accessible from all domains. This allows it to
-    // import and export objects from any domain.
-    let wrap_key_domains = Domain::all();
-
-    // Label to put on the wrap key
-    let wrap_key_label = create_object_label("primary");
-
+fn derive_wrap_key_from_mnemonic(mnemonic: &mnemonic::Phrase, spec: &WrapKeySpec) -> wrap::Key {
     // Includes the key ID in the derivation path, which allows us to derive
     // other wrap keys from the same seed `mnemonic::Phrase` phrase in the event one
     // has been compromised.
     wrap::Key::from_bytes(
-        key_id,
-        derive_secret_from_mnemonic(mnemonic, &[b"wrap", serialize_key_id(key_id).as_bytes()])
-            .as_bytes(),
+        spec.key_id,
+        derive_secret_from_mnemonic(
+            mnemonic,
+            &[b"wrap", serialize_key_id(spec.key_id).as_bytes()],
+        )
+        .as_bytes(),
     )
     .unwrap()
-    .label(wrap_key_label)
-    .capabilities(wrap_key_capabilities)
-    .delegated_capabilities(wrap_key_delegated_capabilities)
-    .domains(wrap_key_domains)
+    .label(create_object_label(&spec.label))
+    .capabilities(spec.capabilities)
+    .delegated_capabilities(spec.delegated_capabilities)
+    .domains(spec.domains)
 }
 
 /// Serialize a key ID as bytes for use in a derivation path
@@ -473,6 +486,74 @@ fn create_object_label(label_prefix: &str) -> object::Label {
     object::Label::from([label_prefix, &timestamp].join(":").as_ref())
 }
 
+/// Print the roles and wrap keys in the given spec
+fn print_plan(spec: &Spec) {
+    println!(
+        "- authkey 0x{:04x} [{}]: capabilities: all; delegated: all; domains: all",
+        ADMIN_KEY_ID, ADMIN_ROLE_NAME
+    );
+
+    for role in &spec.roles {
+        println!(
+            "- authkey 0x{:04x} [{}]: capabilities: {}; delegated: {}; domains: {}",
+            role.key_id,
+            role.name,
+            capability_list(role.capabilities),
+            capability_list(role.delegated_capabilities),
+            domain_list(role.domains)
+        );
+    }
+
+    for wrap_key in &spec.wrap_keys {
+        println!(
+            "- wrapkey 0x{:04x} [{}]: capabilities: {}; delegated: {}; domains: {}",
+            wrap_key.key_id,
+            wrap_key.label,
+            capability_list(wrap_key.capabilities),
+            capability_list(wrap_key.delegated_capabilities),
+            domain_list(wrap_key.domains)
+        );
+    }
+
+    println!("- audit logging: {:?}", spec.audit);
+}
+
+/// Compare the configured YubiHSM's authentication and wrap keys to the spec
+fn verify_device(spec: &Spec) {
+    let hsm = crate::yubihsm::client();
+
+    let objects = hsm.list_objects(&[]).unwrap_or_else(|e| hsm_error(&e));
+    let mut infos = vec![];
+
+    for object in objects {
+        if object.object_type == object::Type::AuthenticationKey
+            || object.object_type == object::Type::WrapKey
+        {
+            infos.push(
+                hsm.get_object_info(object.object_id, object.object_type)
+                    .unwrap_or_else(|e| hsm_error(&e)),
+            );
+        }
+    }
+
+    let problems = spec.verify(&infos);
+
+    if problems.is_empty() {
+        status_ok!("Verified", "YubiHSM matches the provisioning spec");
+        return;
+    }
+
+    for problem in &problems {
+        status_err!("{}", problem);
+    }
+
+    status_err!(
+        "YubiHSM doesn't match the provisioning spec ({} problems)",
+        problems.len()
+    );
+    exit(1);
+}
+
 /// Prompt the user to ensure they want to proceed
 fn prompt_for_user_approval(prompt: &str) {
     print!("\n*** {} (y/N): ", prompt);
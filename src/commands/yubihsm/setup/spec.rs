@@ -0,0 +1,654 @@
+//! Provisioning specs: declarative descriptions of the roles (authentication
+//! keys) and wrap keys `tmkms yubihsm setup` creates.
+//!
+//! All secrets are still derived from the admin's 24-word mnemonic: role
+//! passwords from the role's name, and wrap keys from their key ID.
+
+use super::super::{capability_list, domain_list};
+use crate::{
+    error::{Error, ErrorKind::*},
+    prelude::*,
+};
+use serde::Deserialize;
+use std::{collections::BTreeSet, fs, path::Path};
+use yubihsm::{object, AuditOption, Capability, Domain};
+
+/// Authentication key ID of the admin role (whose password is the mnemonic)
+pub const ADMIN_KEY_ID: object::Id = 1;
+
+/// Name of the admin role
+pub const ADMIN_ROLE_NAME: &str = "admin";
+
+/// Maximum length of role names and wrap key labels (object labels are
+/// limited to 40 bytes, and get a `:<timestamp>` suffix)
+const MAX_NAME_LENGTH: usize = 19;
+
+/// Spec for the default layout: operator, auditor, and validator roles, and
+/// a primary wrap key
+pub const DEFAULT_SPEC: &str = r#"
+audit = "off"
+
+[[role]]
+name = "operator"
+key_id = 2
+capabilities = [
+    "generate-asymmetric-key",
+    "put-asymmetric-key",
+    "generate-hmac-key",
+    "put-hmac-key",
+    "import-wrapped",
+    "export-wrapped",
+    "get-opaque",
+    "get-option",
+    "get-log-entries",
+    "sign-attestation-certificate",
+]
+delegated_capabilities = "all"
+domains = "all"
+
+[[role]]
+name = "auditor"
+key_id = 3
+capabilities = ["get-log-entries", "get-option", "set-option", "get-opaque"]
+domains = "all"
+
+[[role]]
+name = "validator"
+key_id = 4
+capabilities = ["sign-ecdsa", "sign-eddsa", "sign-attestation-certificate", "get-log-entries"]
+domains = [1]
+
+[[wrap_key]]
+key_id = 1
+label = "primary"
+"#;
+
+/// YubiHSM2 provisioning spec
+#[derive(Clone, Debug)]
+pub struct Spec {
+    /// Audit logging mode
+    pub audit: AuditOption,
+
+    /// Roles to create (in addition to the admin role)
+    pub roles: Vec<RoleSpec>,
+
+    /// Wrap keys to create
+    pub wrap_keys: Vec<WrapKeySpec>,
+}
+
+/// Role (i.e. authentication key) to create
+#[derive(Clone, Debug)]
+pub struct RoleSpec {
+    /// Name of the role (used to derive its password, and as its label)
+    pub name: String,
+
+    /// Authentication key ID
+    pub key_id: object::Id,
+
+    /// Capabilities of the role
+    pub capabilities: Capability,
+
+    /// Capabilities the role can give to objects it creates
+    pub delegated_capabilities: Capability,
+
+    /// Domains the role can access
+    pub domains: Domain,
+}
+
+/// Wrap key to create
+#[derive(Clone, Debug)]
+pub struct WrapKeySpec {
+    /// Wrap key ID (used to derive the key)
+    pub key_id: object::Id,
+
+    /// Label of the wrap key
+    pub label: String,
+
+    /// Capabilities of the wrap key
+    pub capabilities: Capability,
+
+    /// Capabilities objects imported with the wrap key may have
+    pub delegated_capabilities: Capability,
+
+    /// Domains the wrap key can access
+    pub domains: Domain,
+}
+
+impl Spec {
+    /// Read a spec from the given TOML file
+    pub fn read(path: &Path) -> Result<Self, Error> {
+        let toml_string = fs::read_to_string(path)
+            .map_err(|e| format_err!(ConfigError, "couldn't read {}: {}", path.display(), e))?;
+
+        Self::parse(&toml_string)
+            .map_err(|e| format_err!(ConfigError, "{}: {}", path.display(), e).into())
+    }
+
+    /// Parse a spec from a TOML string
+    pub fn parse(toml_string: &str) -> Result<Self, Error> {
+        let file: SpecFile =
+            toml::from_str(toml_string).map_err(|e| format_err!(ConfigError, "{}", e))?;
+
+        let audit = match file.audit.as_str() {
+            "off" => AuditOption::Off,
+            "on" => AuditOption::On,
+            "fix" => AuditOption::Fix,
+            other => fail!(
+                ConfigError,
+                "invalid audit option: {} (expected off, on, or fix)",
+                other
+            ),
+        };
+
+        let mut roles = vec![];
+
+        for role in file.role {
+            let context = format!("role '{}'", role.name);
+
+            roles.push(RoleSpec {
+                capabilities: parse_capabilities(&role.capabilities, &context)?,
+                delegated_capabilities: parse_capabilities(&role.delegated_capabilities, &context)?,
+                domains: parse_domains(&role.domains, &context)?,
+                name: role.name,
+                key_id: role.key_id,
+            });
+        }
+
+        let mut wrap_keys = vec![];
+
+        for wrap_key in file.wrap_key {
+            let context = format!("wrap key 0x{:04x}", wrap_key.key_id);
+
+            wrap_keys.push(WrapKeySpec {
+                capabilities: match &wrap_key.capabilities {
+                    Some(capabilities) => parse_capabilities(capabilities, &context)?,
+                    None => {
+                        Capability::EXPORT_WRAPPED
+                            | Capability::IMPORT_WRAPPED
+                            | Capability::WRAP_DATA
+                            | Capability::UNWRAP_DATA
+                    }
+                },
+                delegated_capabilities: match &wrap_key.delegated_capabilities {
+                    Some(capabilities) => parse_capabilities(capabilities, &context)?,
+                    None => Capability::all(),
+                },
+                domains: match &wrap_key.domains {
+                    Some(domains) => parse_domains(domains, &context)?,
+                    None => Domain::all(),
+                },
+                key_id: wrap_key.key_id,
+                label: wrap_key.label,
+            });
+        }
+
+        let spec = Self {
+            audit,
+            roles,
+            wrap_keys,
+        };
+
+        spec.validate()?;
+        Ok(spec)
+    }
+
+    /// Check role names and key IDs are valid and unique
+    fn validate(&self) -> Result<(), Error> {
+        let mut role_names = BTreeSet::new();
+        let mut role_ids = BTreeSet::new();
+
+        for role in &self.roles {
+            check_name(&role.name, "role name")?;
+
+            if role.name == ADMIN_ROLE_NAME {
+                fail!(
+                    ConfigError,
+                    "role name '{}' is reserved for the admin role",
+                    ADMIN_ROLE_NAME
+                );
+            }
+
+            check_key_id(role.key_id, &format!("role '{}'", role.name))?;
+
+            if role.key_id == ADMIN_KEY_ID {
+                fail!(
+                    ConfigError,
+                    "role '{}': key ID 0x{:04x} is reserved for the admin role",
+                    role.name,
+                    ADMIN_KEY_ID
+                );
+            }
+
+            if !role_names.insert(&role.name) {
+                fail!(ConfigError, "duplicate role name: {}", role.name);
+            }
+
+            if !role_ids.insert(role.key_id) {
+                fail!(ConfigError, "duplicate role key ID: 0x{:04x}", role.key_id);
+            }
+        }
+
+        let mut wrap_key_ids = BTreeSet::new();
+
+        for wrap_key in &self.wrap_keys {
+            check_name(&wrap_key.label, "wrap key label")?;
+            check_key_id(wrap_key.key_id, &format!("wrap key '{}'", wrap_key.label))?;
+
+            if !wrap_key_ids.insert(wrap_key.key_id) {
+                fail!(
+                    ConfigError,
+                    "duplicate wrap key ID: 0x{:04x}",
+                    wrap_key.key_id
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Compare the authentication and wrap keys in a device to this spec,
+    /// returning all differences found
+    pub fn verify(&self, objects: &[object::Info]) -> Vec<String> {
+        let mut problems = vec![];
+        let expected = self.expected_objects();
+
+        for object in &expected {
+            let description = object.description();
+
+            let info = match objects.iter().find(|info| {
+                info.object_type == object.object_type && info.object_id == object.key_id
+            }) {
+                Some(info) => info,
+                None => {
+                    problems.push(format!("missing {}", description));
+                    continue;
+                }
+            };
+
+            let label = info.label.to_string();
+
+            if !label.starts_with(&format!("{}:", object.name)) {
+                problems.push(format!(
+                    "{} has label '{}' (expected '{}:<timestamp>')",
+                    description, label, object.name
+                ));
+            }
+
+            if info.capabilities != object.capabilities {
+                problems.push(format!(
+                    "{} has capabilities: {} (expected: {})",
+                    description,
+                    capability_list(info.capabilities),
+                    capability_list(object.capabilities)
+                ));
+            }
+
+            if info.delegated_capabilities != object.delegated_capabilities {
+                problems.push(format!(
+                    "{} has delegated capabilities: {} (expected: {})",
+                    description,
+                    capability_list(info.delegated_capabilities),
+                    capability_list(object.delegated_capabilities)
+                ));
+            }
+
+            if info.domains != object.domains {
+                problems.push(format!(
+                    "{} has domains: {} (expected: {})",
+                    description,
+                    domain_list(info.domains),
+                    domain_list(object.domains)
+                ));
+            }
+        }
+
+        for info in objects {
+            if info.object_type != object::Type::AuthenticationKey
+                && info.object_type != object::Type::WrapKey
+            {
+                continue;
+            }
+
+            let is_expected = expected.iter().any(|object| {
+                info.object_type == object.object_type && info.object_id == object.key_id
+            });
+
+            if !is_expected {
+                problems.push(format!(
+                    "unexpected {} 0x{:04x} ('{}')",
+                    type_name(info.object_type),
+                    info.object_id,
+                    info.label
+                ));
+            }
+        }
+
+        problems
+    }
+
+    /// Authentication and wrap keys a device provisioned with this spec
+    /// should contain (including the admin role)
+    fn expected_objects(&self) -> Vec<ExpectedObject<'_>> {
+        let mut objects = vec![ExpectedObject {
+            object_type: object::Type::AuthenticationKey,
+            key_id: ADMIN_KEY_ID,
+            name: ADMIN_ROLE_NAME,
+            capabilities: Capability::all(),
+            delegated_capabilities: Capability::all(),
+            domains: Domain::all(),
+        }];
+
+        for role in &self.roles {
+            objects.push(ExpectedObject {
+                object_type: object::Type::AuthenticationKey,
+                key_id: role.key_id,
+                name: &role.name,
+                capabilities: role.capabilities,
+                delegated_capabilities: role.delegated_capabilities,
+                domains: role.domains,
+            });
+        }
+
+        for wrap_key in &self.wrap_keys {
+            objects.push(ExpectedObject {
+                object_type: object::Type::WrapKey,
+                key_id: wrap_key.key_id,
+                name: &wrap_key.label,
+                capabilities: wrap_key.capabilities,
+                delegated_capabilities: wrap_key.delegated_capabilities,
+                domains: wrap_key.domains,
+            });
+        }
+
+        objects
+    }
+}
+
+impl Default for Spec {
+    fn default() -> Self {
+        Self::parse(DEFAULT_SPEC).unwrap()
+    }
+}
+
+/// Object a provisioned device should contain
+struct ExpectedObject<'a> {
+    object_type: object::Type,
+    key_id: object::Id,
+    name: &'a str,
+    capabilities: Capability,
+    delegated_capabilities: Capability,
+    domains: Domain,
+}
+
+impl<'a> ExpectedObject<'a> {
+    /// Describe this object, e.g. `authentication key 0x0002 (operator)`
+    fn description(&self) -> String {
+        format!(
+            "{} 0x{:04x} ({})",
+            type_name(self.object_type),
+            self.key_id,
+            self.name
+        )
+    }
+}
+
+/// Spec file as written
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SpecFile {
+    #[serde(default = "default_audit")]
+    audit: String,
+
+    #[serde(default)]
+    role: Vec<RoleEntry>,
+
+    #[serde(default)]
+    wrap_key: Vec<WrapKeyEntry>,
+}
+
+/// `[[role]]` entry
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RoleEntry {
+    name: String,
+    key_id: object::Id,
+    capabilities: Selection<String>,
+    #[serde(default)]
+    delegated_capabilities: Selection<String>,
+    domains: Selection<usize>,
+}
+
+/// `[[wrap_key]]` entry
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WrapKeyEntry {
+    key_id: object::Id,
+    label: String,
+    capabilities: Option<Selection<String>>,
+    delegated_capabilities: Option<Selection<String>>,
+    domains: Option<Selection<usize>>,
+}
+
+/// Either `"all"` or a list of values
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Selection<T> {
+    Keyword(String),
+    List(Vec<T>),
+}
+
+impl<T> Default for Selection<T> {
+    fn default() -> Self {
+        Selection::List(vec![])
+    }
+}
+
+/// Default audit option
+fn default_audit() -> String {
+    "off".to_owned()
+}
+
+/// Parse a list of capability names (or `"all"`)
+fn parse_capabilities(selection: &Selection<String>, context: &str) -> Result<Capability, Error> {
+    match selection {
+        Selection::Keyword(keyword) if keyword == "all" => Ok(Capability::all()),
+        Selection::Keyword(keyword) => fail!(
+            ConfigError,
+            "{}: invalid capabilities: {} (expected a list or \"all\")",
+            context,
+            keyword
+        ),
+        Selection::List(names) => names.iter().try_fold(Capability::empty(), |acc, name| {
+            name.parse::<Capability>()
+                .map(|capability| acc | capability)
+                .map_err(|_| format_err!(ConfigError, "{}: unknown capability: {}", context, name))
+        }),
+    }
+    .map_err(Into::into)
+}
+
+/// Parse a list of domain numbers (or `"all"`)
+fn parse_domains(selection: &Selection<usize>, context: &str) -> Result<Domain, Error> {
+    let domains = match selection {
+        Selection::Keyword(keyword) if keyword == "all" => Domain::all(),
+        Selection::Keyword(keyword) => fail!(
+            ConfigError,
+            "{}: invalid domains: {} (expected a list or \"all\")",
+            context,
+            keyword
+        ),
+        Selection::List(numbers) => {
+            let mut domains = Domain::empty();
+
+            for &number in numbers {
+                domains |= Domain::at(number)
+                    .map_err(|e| format_err!(ConfigError, "{}: {}", context, e))?;
+            }
+
+            domains
+        }
+    };
+
+    if domains.is_empty() {
+        fail!(ConfigError, "{}: no domains given", context);
+    }
+
+    Ok(domains)
+}
+
+/// Check a role name or wrap key label
+fn check_name(name: &str, what: &str) -> Result<(), Error> {
+    if name.is_empty() || name.len() > MAX_NAME_LENGTH || name.contains(':') {
+        fail!(
+            ConfigError,
+            "invalid {}: '{}' (must be 1-{} characters, without ':')",
+            what,
+            name,
+            MAX_NAME_LENGTH
+        );
+    }
+
+    Ok(())
+}
+
+/// Check a key ID isn't reserved
+fn check_key_id(key_id: object::Id, context: &str) -> Result<(), Error> {
+    // 0xfffe is used by the temporary setup key, and 0xffff is reserved
+    if key_id == 0 || key_id >= 0xfffe {
+        fail!(ConfigError, "{}: invalid key ID: 0x{:04x}", context, key_id);
+    }
+
+    Ok(())
+}
+
+/// Human-readable name of an object type
+fn type_name(object_type: object::Type) -> &'static str {
+    match object_type {
+        object::Type::AuthenticationKey => "authentication key",
+        object::Type::WrapKey => "wrap key",
+        _ => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yubihsm::{authentication, wrap, Algorithm};
+
+    /// Object info for an object provisioned according to `spec`
+    fn provisioned(spec: &Spec) -> Vec<object::Info> {
+        spec.expected_objects()
+            .iter()
+            .map(|object| object::Info {
+                capabilities: object.capabilities,
+                object_id: object.key_id,
+                length: 40,
+                domains: object.domains,
+                object_type: object.object_type,
+                algorithm: match object.object_type {
+                    object::Type::WrapKey => Algorithm::Wrap(wrap::Algorithm::Aes128Ccm),
+                    _ => Algorithm::Authentication(authentication::Algorithm::YubicoAes),
+                },
+                sequence: 0,
+                origin: object::Origin::Imported,
+                label: format!("{}:2020-01-01T00:00:00Z", object.name)
+                    .as_str()
+                    .into(),
+                delegated_capabilities: object.delegated_capabilities,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn default_spec_matches_builtin_layout() {
+        let spec = Spec::default();
+        assert_eq!(spec.audit, AuditOption::Off);
+
+        let names = spec
+            .roles
+            .iter()
+            .map(|r| r.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, &["operator", "auditor", "validator"]);
+
+        let auditor = &spec.roles[1];
+        assert_eq!(auditor.key_id, 3);
+        assert_eq!(
+            auditor.capabilities,
+            Capability::GET_LOG_ENTRIES
+                | Capability::GET_OPTION
+                | Capability::PUT_OPTION
+                | Capability::GET_OPAQUE
+        );
+        assert_eq!(auditor.delegated_capabilities, Capability::empty());
+        assert_eq!(auditor.domains, Domain::all());
+
+        let validator = &spec.roles[2];
+        assert_eq!(validator.key_id, 4);
+        assert_eq!(validator.domains, Domain::DOM1);
+
+        let wrap_key = &spec.wrap_keys[0];
+        assert_eq!(wrap_key.key_id, 1);
+        assert_eq!(wrap_key.delegated_capabilities, Capability::all());
+        assert!(wrap_key.capabilities.contains(Capability::EXPORT_WRAPPED));
+    }
+
+    #[test]
+    fn rejects_invalid_specs() {
+        let role = |fields: &str| format!("[[role]]\n{}\ncapabilities = []\n", fields);
+
+        for invalid in &[
+            "audit = \"sometimes\"".to_owned(),
+            role("name = \"admin\"\nkey_id = 5\ndomains = [1]"),
+            role("name = \"monitor\"\nkey_id = 1\ndomains = [1]"),
+            role("name = \"monitor\"\nkey_id = 5\ndomains = [17]"),
+            role("name = \"monitor\"\nkey_id = 5\ndomains = []"),
+            role("name = \"monitor\"\nkey_id = 5\ndomains = \"some\""),
+            [
+                role("name = \"monitor\"\nkey_id = 5\ndomains = [1]"),
+                role("name = \"monitor2\"\nkey_id = 5\ndomains = [1]"),
+            ]
+            .concat(),
+            "[[role]]\nname = \"monitor\"\nkey_id = 5\ndomains = [1]\n\
+             capabilities = [\"sign-everything\"]"
+                .to_owned(),
+            "[[wrap_key]]\nkey_id = 1\nlabel = \"a-label-which-is-too-long\"".to_owned(),
+        ] {
+            assert!(Spec::parse(invalid).is_err(), "accepted: {}", invalid);
+        }
+    }
+
+    #[test]
+    fn verifies_devices() {
+        let spec = Spec::parse(
+            &[
+                DEFAULT_SPEC,
+                "[[role]]\nname = \"monitor\"\nkey_id = 5\n\
+             capabilities = [\"get-log-entries\"]\ndomains = [1, 2]\n",
+            ]
+            .concat(),
+        )
+        .unwrap();
+
+        let mut objects = provisioned(&spec);
+        assert!(spec.verify(&objects).is_empty());
+
+        // Remove the monitor role and widen the validator's domains
+        objects.retain(|info| info.object_id != 5);
+        objects
+            .iter_mut()
+            .find(|info| info.object_id == 4)
+            .unwrap()
+            .domains = Domain::all();
+
+        let problems = spec.verify(&objects);
+        assert_eq!(problems.len(), 2);
+        assert!(problems[0].contains("authentication key 0x0004 (validator) has domains"));
+        assert!(problems[1].contains("missing authentication key 0x0005 (monitor)"));
+
+        assert_eq!(
+            Spec::default().verify(&provisioned(&spec)),
+            &["unexpected authentication key 0x0005 ('monitor:2020-01-01T00:00:00Z')"]
+        );
+    }
+}
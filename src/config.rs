@@ -1,12 +1,29 @@
 //! Configuration file structures (with serde-derived parser)
 
+pub mod alert;
 pub mod chain;
+pub mod include;
+pub mod interpolate;
+pub mod password;
 pub mod provider;
+pub mod status;
 pub mod validator;
 
 pub use self::validator::*;
-use self::{chain::ChainConfig, provider::ProviderConfig};
+use self::{
+    alert::AlertConfig,
+    chain::ChainConfig,
+    include::{ConfigFile, IncludedConfig},
+    provider::ProviderConfig,
+    status::StatusConfig,
+};
+use crate::{
+    error::{Error, ErrorKind::ConfigError},
+    logging::LogFormat,
+    prelude::*,
+};
 use serde::Deserialize;
+use std::{iter, path::Path};
 
 /// Environment variable containing path to config file
 pub const CONFIG_ENV_VAR: &str = "TMKMS_CONFIG_FILE";
@@ -27,5 +44,55 @@ pub struct KmsConfig {
     pub validator: Vec<ValidatorConfig>,
 
     /// Cryptographic signature provider configuration
+    #[serde(default)]
     pub providers: ProviderConfig,
+
+    /// HTTP health/status endpoint configuration
+    pub status: Option<StatusConfig>,
+
+    /// Alert hooks
+    #[serde(default)]
+    pub alert: Vec<AlertConfig>,
+
+    /// Other configuration files to read `[[chain]]`, `[[validator]]`, and
+    /// `[[providers.*]]` entries from (relative to this file, with `*` and
+    /// `?` wildcards supported in file names)
+    #[serde(default)]
+    pub include: Vec<String>,
+
+    /// Log output format (`text` or `json`)
+    #[serde(default)]
+    pub log_format: LogFormat,
+}
+
+impl KmsConfig {
+    /// Load the configuration from the TOML file at the given path, along
+    /// with any files it includes, interpolating environment variables and
+    /// files (see the `interpolate` module)
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let config_file = ConfigFile::read(path)?;
+        let included_files = config_file.read_included()?;
+
+        let (config, mut value) = config_file.parse::<KmsConfig>()?;
+        include::check_duplicates(iter::once(&config_file).chain(&included_files))?;
+
+        if included_files.is_empty() {
+            return Ok(config);
+        }
+
+        for included_file in &included_files {
+            let (_, included_value) = included_file.parse::<IncludedConfig>()?;
+            include::merge(&mut value, included_value);
+        }
+
+        value.try_into().map_err(|e| {
+            format_err!(
+                ConfigError,
+                "error parsing {} (with included files): {}",
+                path.display(),
+                e
+            )
+            .into()
+        })
+    }
 }
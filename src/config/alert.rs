@@ -0,0 +1,34 @@
+//! Configuration for alert hooks
+
+use crate::alert::EventKind;
+use serde::Deserialize;
+use std::ffi::OsString;
+
+/// Configuration for an alert hook, which is notified of events requiring
+/// operator attention (e.g. attempted double signs).
+///
+/// Exactly one of `cmd` or `url` must be given.
+#[derive(Clone, Default, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct AlertConfig {
+    /// Events to send to this hook (default all)
+    pub events: Option<Vec<EventKind>>,
+
+    /// Command (with arguments) to invoke with a JSON event on stdin
+    pub cmd: Option<Vec<OsString>>,
+
+    /// Webhook URL to POST a JSON event to (`http://` only)
+    pub url: Option<String>,
+
+    /// Timeout (in seconds) for running the command or webhook request
+    /// (default 5)
+    pub timeout_secs: Option<u64>,
+
+    /// Minimum interval (in seconds) between alerts for the same event on the
+    /// same chain/validator (default 60)
+    pub rate_limit_secs: Option<u64>,
+
+    /// How long (in seconds) a validator must be disconnected before a
+    /// `disconnected` event is sent (default 60)
+    pub disconnect_secs: Option<u64>,
+}
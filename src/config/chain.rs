@@ -25,3 +25,14 @@ pub struct ChainConfig {
     /// initial block height if configured
     pub state_hook: Option<HookConfig>,
 }
+
+impl ChainConfig {
+    /// Path to this chain's state file (`<chain_id>_priv_validator_state.json`
+    /// in the current directory unless `state_file` is configured)
+    pub fn state_file_path(&self) -> PathBuf {
+        match &self.state_file {
+            Some(path) => path.to_owned(),
+            None => PathBuf::from(format!("{}_priv_validator_state.json", self.id)),
+        }
+    }
+}
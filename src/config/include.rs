@@ -0,0 +1,479 @@
+//! Configuration files and the files they include (via e.g.
+//! `include = ["conf.d/*.toml"]`)
+
+use super::{
+    chain::ChainConfig, interpolate::interpolate, provider::ProviderConfig, ValidatorConfig,
+};
+use crate::{
+    error::{Error, ErrorKind::ConfigError},
+    prelude::*,
+};
+use serde::{de::DeserializeOwned, Deserialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+use toml::{value::Table, Spanned, Value};
+
+/// Configuration which may be set in included files
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct IncludedConfig {
+    /// Chains the KMS is providing key management service for
+    #[serde(default)]
+    pub chain: Vec<ChainConfig>,
+
+    /// Addresses of validator nodes
+    #[serde(default)]
+    pub validator: Vec<ValidatorConfig>,
+
+    /// Cryptographic signature provider configuration
+    #[serde(default)]
+    pub providers: ProviderConfig,
+}
+
+/// A configuration file which has been read and parsed as TOML (without
+/// interpolation)
+#[derive(Debug)]
+pub struct ConfigFile {
+    /// Path to the file
+    pub path: PathBuf,
+
+    /// Contents of the file
+    pub toml_string: String,
+
+    /// Parsed TOML
+    pub value: Value,
+}
+
+impl ConfigFile {
+    /// Read and parse the TOML file at the given path
+    pub fn read(path: &Path) -> Result<Self, Error> {
+        let toml_string = fs::read_to_string(path)
+            .map_err(|e| format_err!(ConfigError, "couldn't read {}: {}", path.display(), e))?;
+
+        let value = toml::from_str(&toml_string)
+            .map_err(|e| format_err!(ConfigError, "error parsing {}: {}", path.display(), e))?;
+
+        Ok(Self {
+            path: path.to_owned(),
+            toml_string,
+            value,
+        })
+    }
+
+    /// Read the files matching this file's `include` patterns (sorted by path
+    /// within each pattern).
+    ///
+    /// Patterns are relative to the directory containing this file, and may
+    /// contain `*` and `?` wildcards in their file name.
+    pub fn read_included(&self) -> Result<Vec<ConfigFile>, Error> {
+        let mut patterns = match self.value.get("include") {
+            Some(patterns) => patterns.clone(),
+            None => return Ok(vec![]),
+        };
+
+        interpolate(&mut patterns)?;
+
+        let patterns = patterns.try_into::<Vec<String>>().map_err(|e| {
+            format_err!(
+                ConfigError,
+                "error parsing {}: invalid `include`: {}",
+                self.path.display(),
+                e
+            )
+        })?;
+
+        let base_dir = self.path.parent().unwrap_or_else(|| Path::new("."));
+        let mut included = vec![];
+
+        for pattern in &patterns {
+            for path in expand_pattern(&base_dir.join(pattern))? {
+                included.push(ConfigFile::read(&path)?);
+            }
+        }
+
+        Ok(included)
+    }
+
+    /// Interpolate this file's values and parse them as `T`, returning both
+    /// the parsed and interpolated values
+    pub fn parse<T: DeserializeOwned>(&self) -> Result<(T, Value), Error> {
+        let mut value = self.value.clone();
+
+        let result = if interpolate(&mut value)? {
+            value.clone().try_into::<T>()
+        } else {
+            // Parse the original TOML to get the locations of any errors
+            toml::from_str::<T>(&self.toml_string)
+        };
+
+        let parsed = result.map_err(|e| {
+            format_err!(ConfigError, "error parsing {}: {}", self.path.display(), e)
+        })?;
+
+        Ok((parsed, value))
+    }
+}
+
+/// Merge the `[[chain]]`, `[[validator]]`, and `[[providers.*]]` entries of
+/// an included file into the including file's configuration
+pub fn merge(config: &mut Value, included: Value) {
+    let config = config.as_table_mut().expect("config is a table");
+
+    if let Value::Table(included) = included {
+        for (key, value) in included {
+            match value {
+                Value::Table(providers) => {
+                    let table = config
+                        .entry(key)
+                        .or_insert_with(|| Value::Table(Table::new()));
+
+                    if let Value::Table(table) = table {
+                        for (provider, entries) in providers {
+                            append(table, provider, entries);
+                        }
+                    }
+                }
+                entries => append(config, key, entries),
+            }
+        }
+    }
+}
+
+/// Append the given array to the array in `table` under `key`
+fn append(table: &mut Table, key: String, entries: Value) {
+    let array = table.entry(key).or_insert_with(|| Value::Array(vec![]));
+
+    if let (Value::Array(array), Value::Array(entries)) = (array, entries) {
+        array.extend(entries);
+    }
+}
+
+/// Check for `[[chain]]`, `[[validator]]`, and `[[providers.*]]` entries
+/// (and YubiHSM key IDs) which are defined more than once across the given
+/// files
+pub fn check_duplicates<'a>(files: impl Iterator<Item = &'a ConfigFile>) -> Result<(), Error> {
+    /// Entries which must be unique
+    #[derive(Deserialize)]
+    struct Entries {
+        #[serde(default)]
+        chain: Vec<ChainEntry>,
+
+        #[serde(default)]
+        validator: Vec<ValidatorEntry>,
+
+        #[serde(default)]
+        providers: ProviderEntries,
+    }
+
+    #[derive(Deserialize)]
+    struct ChainEntry {
+        id: Spanned<String>,
+    }
+
+    #[derive(Deserialize)]
+    struct ValidatorEntry {
+        chain_id: Spanned<String>,
+        addr: String,
+    }
+
+    #[derive(Default, Deserialize)]
+    struct ProviderEntries {
+        #[serde(default)]
+        softsign: Vec<SoftsignEntry>,
+
+        #[serde(default)]
+        yubihsm: Vec<ProviderEntry>,
+
+        #[serde(default)]
+        ledgertm: Vec<ProviderEntry>,
+    }
+
+    /// Provider which can only be configured once, located by its keys (the
+    /// `[[...]]` header of an array of tables has no position)
+    type ProviderEntry = BTreeMap<Spanned<String>, Value>;
+
+    #[derive(Deserialize)]
+    struct SoftsignEntry {
+        path: Spanned<String>,
+    }
+
+    // Locations each entry is defined at, keyed by description
+    let mut locations: BTreeMap<String, Vec<String>> = BTreeMap::new();
+
+    for file in files {
+        // Files which fail to parse are reported elsewhere
+        let entries = match toml::from_str::<Entries>(&file.toml_string) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+
+        let mut add = |description: String, offset: Option<usize>| {
+            let location = match offset {
+                Some(offset) => format!(
+                    "{}:{}",
+                    file.path.display(),
+                    file.toml_string[..offset].matches('\n').count() + 1
+                ),
+                None => file.path.display().to_string(),
+            };
+
+            locations.entry(description).or_default().push(location);
+        };
+
+        for chain in &entries.chain {
+            let id = interpolate_str(chain.id.get_ref())?;
+            add(format!("[[chain]] `{}`", id), Some(chain.id.start()));
+        }
+
+        for validator in &entries.validator {
+            let chain_id = interpolate_str(validator.chain_id.get_ref())?;
+            let addr = interpolate_str(&validator.addr)?;
+            add(
+                format!("[[validator]] `{}` for `{}`", addr, chain_id),
+                Some(validator.chain_id.start()),
+            );
+        }
+
+        for softsign in &entries.providers.softsign {
+            let path = interpolate_str(softsign.path.get_ref())?;
+            add(
+                format!("[[providers.softsign]] key `{}`", path),
+                Some(softsign.path.start()),
+            );
+        }
+
+        for yubihsm in &entries.providers.yubihsm {
+            add(
+                "[[providers.yubihsm]] section".to_owned(),
+                yubihsm.keys().map(Spanned::start).min(),
+            );
+
+            let keys = yubihsm.iter().find(|(name, _)| name.get_ref() == "keys");
+
+            if let Some((name, Value::Array(keys))) = keys {
+                for id in keys.iter().filter_map(|key| key.get("key")) {
+                    add(
+                        format!("[[providers.yubihsm]] key ID `{}`", id),
+                        Some(name.start()),
+                    );
+                }
+            }
+        }
+
+        for ledgertm in &entries.providers.ledgertm {
+            add(
+                "[[providers.ledgertm]] section".to_owned(),
+                ledgertm.keys().map(Spanned::start).min(),
+            );
+        }
+    }
+
+    let duplicates = locations
+        .iter()
+        .filter(|(_, locations)| locations.len() > 1)
+        .map(|(description, locations)| {
+            format!(
+                "duplicate {} (defined at {})",
+                description,
+                locations.join(", ")
+            )
+        })
+        .collect::<Vec<_>>();
+
+    if duplicates.is_empty() {
+        Ok(())
+    } else {
+        fail!(ConfigError, "{}", duplicates.join("; "))
+    }
+}
+
+/// Interpolate a single string value
+fn interpolate_str(string: &str) -> Result<String, Error> {
+    let mut value = Value::String(string.to_owned());
+    interpolate(&mut value)?;
+    Ok(value.as_str().unwrap().to_owned())
+}
+
+/// Find the files matching the given pattern
+fn expand_pattern(pattern: &Path) -> Result<Vec<PathBuf>, Error> {
+    let (dir, file_pattern) = match (pattern.parent(), pattern.file_name()) {
+        (Some(dir), Some(file_pattern)) => (dir, file_pattern.to_string_lossy()),
+        _ => fail!(
+            ConfigError,
+            "invalid `include` pattern: {}",
+            pattern.display()
+        ),
+    };
+
+    if dir.to_string_lossy().contains(&['*', '?'][..]) {
+        fail!(
+            ConfigError,
+            "invalid `include` pattern: {} (wildcards are only supported in file names)",
+            pattern.display()
+        );
+    }
+
+    if !file_pattern.contains(&['*', '?'][..]) {
+        return Ok(vec![pattern.to_owned()]);
+    }
+
+    let entries = fs::read_dir(dir).map_err(|e| {
+        format_err!(
+            ConfigError,
+            "couldn't read `include` directory {}: {}",
+            dir.display(),
+            e
+        )
+    })?;
+
+    let mut paths = vec![];
+
+    for entry in entries {
+        let path = entry?.path();
+
+        let matches = path
+            .file_name()
+            .map(|name| wildcard_match(&file_pattern, &name.to_string_lossy()))
+            .unwrap_or(false);
+
+        if matches && path.is_file() {
+            paths.push(path);
+        }
+    }
+
+    paths.sort();
+    Ok(paths)
+}
+
+/// Match a file name against a pattern containing `*` (any number of
+/// characters) and `?` (one character) wildcards
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let name = name.chars().collect::<Vec<_>>();
+
+    // Position in the pattern/name of the last `*` (for backtracking)
+    let mut star = None;
+    let (mut p, mut n) = (0, 0);
+
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = star {
+            p = star_p + 1;
+            n = star_n + 1;
+            star = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wildcards() {
+        assert!(wildcard_match("*.toml", "cosmoshub.toml"));
+        assert!(wildcard_match("chain-?.toml", "chain-1.toml"));
+        assert!(wildcard_match("*", "anything"));
+        assert!(!wildcard_match("*.toml", "cosmoshub.toml.bak"));
+        assert!(!wildcard_match("chain-?.toml", "chain-10.toml"));
+    }
+
+    #[test]
+    fn merges_entries() {
+        let mut config: Value = toml::from_str(
+            r#"
+            [[chain]]
+            id = "a"
+
+            [[providers.softsign]]
+            path = "a.key"
+            "#,
+        )
+        .unwrap();
+
+        let included: Value = toml::from_str(
+            r#"
+            [[chain]]
+            id = "b"
+
+            [[validator]]
+            chain_id = "b"
+
+            [[providers.softsign]]
+            path = "b.key"
+
+            [[providers.yubihsm]]
+            serial_number = "0123456789"
+            "#,
+        )
+        .unwrap();
+
+        merge(&mut config, included);
+
+        assert_eq!(config["chain"].as_array().unwrap().len(), 2);
+        assert_eq!(config["validator"].as_array().unwrap().len(), 1);
+        assert_eq!(
+            config["providers"]["softsign"][1]["path"].as_str().unwrap(),
+            "b.key"
+        );
+        assert_eq!(config["providers"]["yubihsm"].as_array().unwrap().len(), 1);
+    }
+
+    /// Create a `ConfigFile` from the given TOML
+    fn config_file(path: &str, toml_string: &str) -> ConfigFile {
+        ConfigFile {
+            path: PathBuf::from(path),
+            toml_string: toml_string.to_owned(),
+            value: toml::from_str(toml_string).unwrap(),
+        }
+    }
+
+    #[test]
+    fn detects_duplicate_providers() {
+        let files = [
+            config_file(
+                "tmkms.toml",
+                "[[providers.yubihsm]]\n\
+                 adapter = { type = \"usb\" }\n\
+                 keys = [{ chain_ids = [\"a\"], key = 1 }]\n\
+                 \n\
+                 [[providers.ledgertm]]\n\
+                 chain_ids = [\"a\"]\n",
+            ),
+            config_file(
+                "conf.d/b.toml",
+                "[providers]\n\
+                 ledgertm = [{ chain_ids = [\"b\"] }]\n\
+                 \n\
+                 [[providers.yubihsm]]\n\
+                 adapter = { type = \"usb\" }\n\
+                 keys = [{ chain_ids = [\"b\"], key = 1 }, { chain_ids = [\"b\"], key = 2 }]\n",
+            ),
+        ];
+
+        let err = check_duplicates(files.iter()).unwrap_err().to_string();
+
+        assert!(err.contains(
+            "duplicate [[providers.yubihsm]] section (defined at tmkms.toml:2, conf.d/b.toml:5)"
+        ));
+        assert!(err.contains(
+            "duplicate [[providers.yubihsm]] key ID `1` (defined at tmkms.toml:3, conf.d/b.toml:6)"
+        ));
+        assert!(err.contains(
+            "duplicate [[providers.ledgertm]] section (defined at tmkms.toml:6, conf.d/b.toml:2)"
+        ));
+        assert!(!err.contains("key ID `2`"));
+    }
+}
@@ -0,0 +1,209 @@
+//! Interpolation of environment variables (`${VAR}`) and files (`file:`)
+//! in configuration values
+
+use crate::{
+    error::{Error, ErrorKind::ConfigError},
+    prelude::*,
+};
+use std::{env, fs, mem};
+use toml::Value;
+use zeroize::{Zeroize, Zeroizing};
+
+/// Prefix of values which are read from a file
+pub const FILE_PREFIX: &str = "file:";
+
+/// Interpolate all string values in the given TOML value (in place),
+/// returning whether any values were changed.
+///
+/// - `${VAR}` anywhere in a string is replaced with the value of the `VAR`
+///   environment variable (`$${` is a literal `${`)
+/// - strings beginning with `file:` are replaced with the contents of the
+///   named file (after interpolating environment variables in its path),
+///   with trailing whitespace removed
+pub fn interpolate(value: &mut Value) -> Result<bool, Error> {
+    interpolate_value(value, "")
+}
+
+/// Interpolate the given value, using `key` to identify it in errors
+fn interpolate_value(value: &mut Value, key: &str) -> Result<bool, Error> {
+    let mut changed = false;
+
+    match value {
+        Value::String(string) => {
+            if let Some(interpolated) = interpolate_str(string, key)? {
+                // Values may be secrets (e.g. passwords)
+                mem::replace(string, interpolated).zeroize();
+                changed = true;
+            }
+        }
+        Value::Array(array) => {
+            for (i, element) in array.iter_mut().enumerate() {
+                changed |= interpolate_value(element, &format!("{}[{}]", key, i))?;
+            }
+        }
+        Value::Table(table) => {
+            for (name, element) in table.iter_mut() {
+                let element_key = if key.is_empty() {
+                    name.to_owned()
+                } else {
+                    format!("{}.{}", key, name)
+                };
+
+                changed |= interpolate_value(element, &element_key)?;
+            }
+        }
+        _ => (),
+    }
+
+    Ok(changed)
+}
+
+/// Interpolate a string, returning `None` if it doesn't need interpolation
+fn interpolate_str(string: &str, key: &str) -> Result<Option<String>, Error> {
+    if let Some(path) = string.strip_prefix(FILE_PREFIX) {
+        let path = expand_env_vars(path, key)?;
+
+        let contents = Zeroizing::new(fs::read_to_string(path.as_str()).map_err(|e| {
+            format_err!(
+                ConfigError,
+                "{}: couldn't read {}: {}",
+                key,
+                path.as_str(),
+                e
+            )
+        })?);
+
+        Ok(Some(contents.trim_end().to_owned()))
+    } else if string.contains('$') {
+        let expanded = expand_env_vars(string, key)?;
+
+        if expanded.as_str() == string {
+            Ok(None)
+        } else {
+            Ok(Some(expanded.as_str().to_owned()))
+        }
+    } else {
+        Ok(None)
+    }
+}
+
+/// Replace `${VAR}` references with the values of environment variables
+fn expand_env_vars(string: &str, key: &str) -> Result<Zeroizing<String>, Error> {
+    let mut expanded = Zeroizing::new(String::with_capacity(string.len()));
+    let mut rest = string;
+
+    while let Some(pos) = rest.find('$') {
+        expanded.push_str(&rest[..pos]);
+        rest = &rest[pos..];
+
+        if rest.starts_with("$${") {
+            expanded.push_str("${");
+            rest = &rest[3..];
+        } else if rest.starts_with("${") {
+            let end = rest
+                .find('}')
+                .ok_or_else(|| format_err!(ConfigError, "{}: unterminated `${{` in value", key))?;
+
+            let name = &rest[2..end];
+
+            if !is_valid_var_name(name) {
+                fail!(
+                    ConfigError,
+                    "{}: invalid environment variable name: `{}`",
+                    key,
+                    name
+                );
+            }
+
+            let value = Zeroizing::new(env::var(name).map_err(|e| {
+                format_err!(
+                    ConfigError,
+                    "{}: environment variable `{}`: {}",
+                    key,
+                    name,
+                    e
+                )
+            })?);
+
+            expanded.push_str(&value);
+            rest = &rest[end + 1..];
+        } else {
+            expanded.push('$');
+            rest = &rest[1..];
+        }
+    }
+
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+/// Is the given string a valid environment variable name?
+fn is_valid_var_name(name: &str) -> bool {
+    let mut chars = name.chars();
+
+    match chars.next() {
+        Some(c) if c == '_' || c.is_ascii_alphabetic() => {
+            chars.all(|c| c == '_' || c.is_ascii_alphanumeric())
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn expands_env_vars() {
+        env::set_var("TMKMS_TEST_INTERPOLATE_HOST", "10.0.0.1");
+
+        let mut value: Value = toml::from_str(
+            r#"
+            addr = "tcp://${TMKMS_TEST_INTERPOLATE_HOST}:26658"
+            cmd = ["echo", "$${TMKMS_TEST_INTERPOLATE_HOST}", "$5"]
+            "#,
+        )
+        .unwrap();
+
+        assert!(interpolate(&mut value).unwrap());
+        assert_eq!(value["addr"].as_str().unwrap(), "tcp://10.0.0.1:26658");
+        assert_eq!(
+            value["cmd"][1].as_str().unwrap(),
+            "${TMKMS_TEST_INTERPOLATE_HOST}"
+        );
+        assert_eq!(value["cmd"][2].as_str().unwrap(), "$5");
+    }
+
+    #[test]
+    fn reads_files() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(file, "hunter2").unwrap();
+
+        env::set_var("TMKMS_TEST_INTERPOLATE_DIR", file.path().parent().unwrap());
+
+        let mut value: Value = toml::from_str(&format!(
+            r#"password = "file:${{TMKMS_TEST_INTERPOLATE_DIR}}/{}""#,
+            file.path().file_name().unwrap().to_str().unwrap()
+        ))
+        .unwrap();
+
+        assert!(interpolate(&mut value).unwrap());
+        assert_eq!(value["password"].as_str().unwrap(), "hunter2");
+    }
+
+    #[test]
+    fn reports_missing_env_vars() {
+        let mut value: Value = toml::from_str(
+            r#"
+            [[validator]]
+            addr = "tcp://${TMKMS_TEST_INTERPOLATE_UNSET}:26658"
+            "#,
+        )
+        .unwrap();
+
+        let err = interpolate(&mut value).unwrap_err().to_string();
+        assert!(err.contains("validator[0].addr"));
+        assert!(err.contains("TMKMS_TEST_INTERPOLATE_UNSET"));
+    }
+}
@@ -0,0 +1,33 @@
+//! Passwords protecting keys (e.g. YubiHSM auth keys or encrypted softsign
+//! keys)
+
+use abscissa_core::secret::{CloneableSecret, DebugSecret};
+use serde::Deserialize;
+use zeroize::Zeroize;
+
+/// Password (zeroized on drop)
+#[derive(Clone, Deserialize, Zeroize)]
+#[serde(deny_unknown_fields)]
+#[zeroize(drop)]
+pub struct Password(String);
+
+impl Password {
+    /// Borrow this password as bytes
+    pub fn as_bytes(&self) -> &[u8] {
+        self.0.as_bytes()
+    }
+}
+
+impl From<String> for Password {
+    fn from(password: String) -> Self {
+        Password(password)
+    }
+}
+
+impl CloneableSecret for Password {}
+
+impl DebugSecret for Password {
+    fn debug_secret() -> &'static str {
+        "REDACTED PASSWORD"
+    }
+}
@@ -2,9 +2,11 @@
 
 use crate::{
     chain,
+    config::password::Password,
     error::{Error, ErrorKind::ConfigError},
     prelude::*,
 };
+use abscissa_core::secret::Secret;
 use serde::Deserialize;
 use std::{
     path::{Path, PathBuf},
@@ -24,13 +26,22 @@ pub struct SoftsignConfig {
     /// Path to a file containing a cryptographic key
     // TODO: use `abscissa_core::Secret` to wrap this `PathBuf`
     pub path: SoftPrivateKey,
+
+    /// Password for `encrypted` keys (prompted for if not set)
+    pub password: Option<Secret<Password>>,
 }
 
 /// Software-backed private key (stored in a file)
-#[derive(Deserialize, Debug)]
+#[derive(Clone, Deserialize, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct SoftPrivateKey(PathBuf);
 
+impl From<PathBuf> for SoftPrivateKey {
+    fn from(path: PathBuf) -> Self {
+        SoftPrivateKey(path)
+    }
+}
+
 impl AsRef<Path> for SoftPrivateKey {
     /// Borrow this private key as a path
     fn as_ref(&self) -> &Path {
@@ -52,6 +63,10 @@ pub enum KeyFormat {
     /// JSON
     #[serde(rename = "json")]
     Json,
+
+    /// Password-encrypted JSON
+    #[serde(rename = "encrypted")]
+    Encrypted,
 }
 
 impl Default for KeyFormat {
@@ -69,6 +84,7 @@ impl FromStr for KeyFormat {
             "raw" => KeyFormat::Raw,
             "base64" => KeyFormat::Base64,
             "json" => KeyFormat::Json,
+            "encrypted" => KeyFormat::Encrypted,
             other => fail!(ConfigError, "invalid key format: {}", other),
         };
 
@@ -1,12 +1,20 @@
 //! Configuration for the `YubiHSM` backend
 
-use crate::{chain, prelude::*};
-use abscissa_core::secret::{CloneableSecret, DebugSecret, ExposeSecret, Secret};
+pub use crate::config::password::Password;
+use crate::{
+    chain,
+    error::{Error, ErrorKind::*},
+    prelude::*,
+};
+use abscissa_core::secret::{ExposeSecret, Secret};
 use serde::Deserialize;
-use std::{fs, path::PathBuf, process};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
 use tendermint::net;
 use yubihsm::Credentials;
-use zeroize::{Zeroize, Zeroizing};
+use zeroize::Zeroizing;
 
 /// The (optional) `[providers.yubihsm]` config section
 #[derive(Clone, Deserialize, Debug)]
@@ -25,6 +33,13 @@ pub struct YubihsmConfig {
     /// Serial number of the YubiHSM to connect to
     pub serial_number: Option<String>,
 
+    /// Monitor the YubiHSM's audit log while the KMS is running
+    pub audit_log: Option<AuditLogConfig>,
+
+    /// Pool of sessions used for signing
+    #[serde(default)]
+    pub session_pool: SessionPoolConfig,
+
     /// Configuration for `yubihsm-connector` compatible HTTP server.
     #[cfg(feature = "yubihsm-server")]
     pub connector_server: Option<ConnectorServerConfig>,
@@ -70,42 +85,141 @@ pub enum AuthConfig {
         /// Password to use to authenticate to the YubiHSM
         password: Secret<Password>,
     },
+    /// Read password from an environment variable
+    Env {
+        /// Authentication key ID to use to authenticate to the YubiHSM
+        key: u16,
+
+        /// Name of the environment variable containing the password
+        password_env: String,
+    },
+    /// Read password from a systemd credential (i.e. a file in
+    /// `$CREDENTIALS_DIRECTORY`, see `LoadCredential=` in `systemd.exec(5)`)
+    Credential {
+        /// Authentication key ID to use to authenticate to the YubiHSM
+        key: u16,
+
+        /// Name of the credential containing the password
+        password_credential: String,
+    },
+    /// Prompt for the password on the terminal at startup
+    Prompt {
+        /// Authentication key ID to use to authenticate to the YubiHSM
+        key: u16,
+
+        /// Prompt for the password (must be `true`)
+        password_prompt: bool,
+    },
 }
 
 impl AuthConfig {
-    /// Get the `yubihsm::Credentials` for this `AuthConfig`
-    pub fn credentials(&self) -> Credentials {
+    /// Get the authentication key ID
+    pub fn key(&self) -> u16 {
+        match self {
+            AuthConfig::Path { key, .. }
+            | AuthConfig::String { key, .. }
+            | AuthConfig::Env { key, .. }
+            | AuthConfig::Credential { key, .. }
+            | AuthConfig::Prompt { key, .. } => *key,
+        }
+    }
+
+    /// Get the `yubihsm::Credentials` for this `AuthConfig` (prompting for
+    /// the password if configured to)
+    pub fn credentials(&self) -> Result<Credentials, Error> {
+        let password = match self {
+            AuthConfig::Prompt {
+                key,
+                password_prompt: true,
+            } => {
+                let prompt = format!("Enter password for YubiHSM2 auth key 0x{:04x}: ", key);
+
+                let password = rpassword::read_password_from_tty(Some(&prompt))
+                    .map_err(|e| format_err!(IoError, "couldn't read YubiHSM password: {}", e))?;
+
+                Zeroizing::new(password.into_bytes())
+            }
+            _ => self.read_password()?,
+        };
+
+        Ok(Credentials::from_password(self.key(), password.as_slice()))
+    }
+
+    /// Check the password can be read, without prompting for it
+    pub fn check(&self) -> Result<(), Error> {
         match self {
-            AuthConfig::Path { key, password_file } => {
-                let password =
-                    Zeroizing::new(fs::read_to_string(password_file).unwrap_or_else(|e| {
-                        status_err!("couldn't read key from {}: {}", password_file.display(), e);
-                        process::exit(1);
-                    }));
-
-                // TODO(tarcieri): constant-time string trimming
-                let password_trimmed = password.trim_end();
-                Credentials::from_password(*key, password_trimmed.as_bytes())
+            AuthConfig::Prompt {
+                password_prompt: true,
+                ..
+            } => Ok(()),
+            _ => self.read_password().map(|_| ()),
+        }
+    }
+
+    /// Read the password from a non-interactive source
+    fn read_password(&self) -> Result<Zeroizing<Vec<u8>>, Error> {
+        match self {
+            AuthConfig::Path { password_file, .. } => read_password_file(password_file),
+            AuthConfig::String { password, .. } => {
+                Ok(Zeroizing::new(password.expose_secret().as_bytes().to_vec()))
             }
-            AuthConfig::String { key, password } => {
-                Credentials::from_password(*key, password.expose_secret().0.as_bytes())
+            AuthConfig::Env { password_env, .. } => env::var(password_env)
+                .map(|password| Zeroizing::new(password.into_bytes()))
+                .map_err(|e| {
+                    format_err!(
+                        ConfigError,
+                        "couldn't read YubiHSM password from ${}: {}",
+                        password_env,
+                        e
+                    )
+                    .into()
+                }),
+            AuthConfig::Credential {
+                password_credential,
+                ..
+            } => {
+                if password_credential.is_empty() || password_credential.contains('/') {
+                    fail!(
+                        ConfigError,
+                        "invalid credential name: {:?}",
+                        password_credential
+                    );
+                }
+
+                let dir = env::var_os(CREDENTIALS_DIRECTORY).ok_or_else(|| {
+                    format_err!(
+                        ConfigError,
+                        "${} isn't set (is tmkms running under systemd with LoadCredential=?)",
+                        CREDENTIALS_DIRECTORY
+                    )
+                })?;
+
+                read_password_file(&Path::new(&dir).join(password_credential))
             }
+            AuthConfig::Prompt { .. } => fail!(
+                ConfigError,
+                "YubiHSM auth has `password_prompt = false` and no other password source"
+            ),
         }
     }
 }
 
-/// Password to the YubiHSM
-#[derive(Clone, Deserialize, Zeroize)]
-#[serde(deny_unknown_fields)]
-#[zeroize(drop)]
-pub struct Password(String);
+/// Environment variable systemd sets to the directory containing credentials
+const CREDENTIALS_DIRECTORY: &str = "CREDENTIALS_DIRECTORY";
 
-impl CloneableSecret for Password {}
+/// Read a password from the given file (without trailing whitespace)
+fn read_password_file(path: &Path) -> Result<Zeroizing<Vec<u8>>, Error> {
+    let password = Zeroizing::new(fs::read_to_string(path).map_err(|e| {
+        format_err!(
+            IoError,
+            "couldn't read YubiHSM password from {}: {}",
+            path.display(),
+            e
+        )
+    })?);
 
-impl DebugSecret for Password {
-    fn debug_secret() -> &'static str {
-        "REDACTED PASSWORD"
-    }
+    // TODO(tarcieri): constant-time string trimming
+    Ok(Zeroizing::new(password.trim_end().as_bytes().to_vec()))
 }
 
 /// Signing key configuration
@@ -119,6 +233,80 @@ pub struct SigningKeyConfig {
     pub key: u16,
 }
 
+/// Audit log monitoring configuration
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AuditLogConfig {
+    /// How often to poll the audit log (in seconds)
+    #[serde(default = "audit_log_interval_secs_default")]
+    pub interval_secs: u64,
+
+    /// Mark entries as consumed once they've been logged (needed to keep the
+    /// YubiHSM usable when its `force_audit` option is enabled)
+    #[serde(default)]
+    pub consume: bool,
+}
+
+/// Session pool configuration
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SessionPoolConfig {
+    /// Number of authenticated sessions to open for signing
+    #[serde(default = "session_pool_size_default")]
+    pub size: usize,
+
+    /// How long a signing request waits for a session before failing
+    /// (in milliseconds)
+    #[serde(default = "session_pool_timeout_ms_default")]
+    pub timeout_ms: u64,
+
+    /// How often to ping idle sessions so they don't time out (in seconds,
+    /// or 0 to disable)
+    #[serde(default = "session_pool_keepalive_secs_default")]
+    pub keepalive_secs: u64,
+
+    /// Reauthenticate sessions which have been open longer than this (in
+    /// seconds)
+    #[serde(default = "session_pool_max_session_age_secs_default")]
+    pub max_session_age_secs: u64,
+}
+
+impl Default for SessionPoolConfig {
+    fn default() -> Self {
+        Self {
+            size: session_pool_size_default(),
+            timeout_ms: session_pool_timeout_ms_default(),
+            keepalive_secs: session_pool_keepalive_secs_default(),
+            max_session_age_secs: session_pool_max_session_age_secs_default(),
+        }
+    }
+}
+
+/// Default value for `SessionPoolConfig { size }`
+fn session_pool_size_default() -> usize {
+    2
+}
+
+/// Default value for `SessionPoolConfig { timeout_ms }`
+fn session_pool_timeout_ms_default() -> u64 {
+    1000
+}
+
+/// Default value for `SessionPoolConfig { keepalive_secs }`
+fn session_pool_keepalive_secs_default() -> u64 {
+    10
+}
+
+/// Default value for `SessionPoolConfig { max_session_age_secs }`
+fn session_pool_max_session_age_secs_default() -> u64 {
+    3600
+}
+
+/// Default value for `AuditLogConfig { interval_secs }`
+fn audit_log_interval_secs_default() -> u64 {
+    60
+}
+
 /// Default value for `AdapterConfig::Usb { timeout_ms }`
 fn usb_timeout_ms_default() -> u64 {
     1000
@@ -145,3 +333,63 @@ pub struct CliConfig {
     /// prompt for a password from the terminal.
     pub auth_key: Option<u16>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    /// Parse an `auth = { ... }` setting
+    fn parse_auth(auth: &str) -> AuthConfig {
+        #[derive(Deserialize)]
+        struct Wrapper {
+            auth: AuthConfig,
+        }
+
+        toml::from_str::<Wrapper>(&format!("auth = {}", auth))
+            .unwrap()
+            .auth
+    }
+
+    #[test]
+    fn password_from_env() {
+        env::set_var("TMKMS_TEST_YUBIHSM_PASSWORD", "password");
+        let auth = parse_auth(r#"{ key = 2, password_env = "TMKMS_TEST_YUBIHSM_PASSWORD" }"#);
+
+        assert_eq!(auth.key(), 2);
+        assert_eq!(auth.read_password().unwrap().as_slice(), b"password");
+        assert!(auth.credentials().is_ok());
+    }
+
+    #[test]
+    fn password_from_missing_env() {
+        let auth = parse_auth(r#"{ key = 2, password_env = "TMKMS_TEST_MISSING_PASSWORD" }"#);
+        let err = auth.check().unwrap_err();
+        assert!(err.to_string().contains("$TMKMS_TEST_MISSING_PASSWORD"));
+    }
+
+    #[test]
+    fn password_from_credential() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("yubihsm-password"), "password\n").unwrap();
+        env::set_var(CREDENTIALS_DIRECTORY, dir.path());
+
+        let auth = parse_auth(r#"{ key = 3, password_credential = "yubihsm-password" }"#);
+        assert_eq!(auth.read_password().unwrap().as_slice(), b"password");
+
+        let auth = parse_auth(r#"{ key = 3, password_credential = "../yubihsm-password" }"#);
+        let err = auth.check().unwrap_err();
+        assert!(err.to_string().contains("invalid credential name"));
+    }
+
+    #[test]
+    fn password_prompt() {
+        let auth = parse_auth("{ key = 4, password_prompt = true }");
+        assert_eq!(auth.key(), 4);
+        assert!(auth.check().is_ok());
+
+        let auth = parse_auth("{ key = 4, password_prompt = false }");
+        assert!(auth.check().is_err());
+    }
+}
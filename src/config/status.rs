@@ -0,0 +1,12 @@
+//! Configuration for the HTTP health/status endpoint
+
+use serde::Deserialize;
+use tendermint::net;
+
+/// HTTP health/status endpoint configuration
+#[derive(Clone, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct StatusConfig {
+    /// Address to listen on (`tcp://` only)
+    pub laddr: net::Address,
+}
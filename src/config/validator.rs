@@ -1,17 +1,27 @@
 //! Validator configuration
 
+#[cfg(feature = "softsign")]
+use super::{
+    password::Password,
+    provider::softsign::{KeyFormat, SoftPrivateKey},
+};
 use crate::{
     error::{Error, ErrorKind::*},
     keyring::SecretKeyEncoding,
     prelude::*,
 };
+#[cfg(feature = "softsign")]
+use abscissa_core::secret::Secret;
 use serde::Deserialize;
 use signatory::{
     ed25519,
     encoding::{Decode, Encode},
+    public_key::PublicKeyed,
+    signature::Signer,
 };
-use std::path::PathBuf;
-use tendermint::{chain, net};
+use signatory_dalek::Ed25519Signer;
+use std::path::{Path, PathBuf};
+use tendermint::{chain, net, node};
 
 /// Validator configuration
 #[derive(Clone, Deserialize, Debug)]
@@ -30,17 +40,109 @@ pub struct ValidatorConfig {
     /// Optional timeout value in seconds
     pub timeout: Option<u16>,
 
-    /// Path to our Ed25519 identity key (if applicable)
-    pub secret_key: Option<PathBuf>,
+    /// Our Ed25519 identity key (if applicable)
+    pub secret_key: Option<SecretKeyConfig>,
+
+    /// Generate the identity key if `secret_key` is a path which doesn't exist?
+    /// (default: false)
+    #[serde(default)]
+    pub auto_generate_identity: bool,
 
     /// Height at which to stop signing
     pub max_height: Option<tendermint::block::Height>,
+
+    /// Expected credentials of the validator process on the other end of a
+    /// `unix://` socket (if applicable)
+    pub peer_credentials: Option<PeerCredentialsConfig>,
+
+    /// Use SecretConnection on `unix://` sockets (requires `secret_key`)
+    pub secret_connection: Option<UnixSecretConnectionConfig>,
+}
+
+/// Expected credentials of a peer connected via a Unix domain socket.
+///
+/// Only the fields which are set are checked.
+#[derive(Clone, Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct PeerCredentialsConfig {
+    /// User ID of the peer process
+    pub uid: Option<u32>,
+
+    /// Group ID of the peer process
+    pub gid: Option<u32>,
+
+    /// Process ID of the peer process
+    pub pid: Option<i32>,
 }
 
+/// SecretConnection configuration for `unix://` validators
+#[derive(Clone, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct UnixSecretConnectionConfig {
+    /// Node ID the validator is expected to authenticate as
+    pub peer_id: Option<node::Id>,
+}
+
+/// Ed25519 identity key used to authenticate the KMS to validators
+#[derive(Clone, Deserialize, Debug)]
+#[serde(untagged)]
+pub enum SecretKeyConfig {
+    /// Path to a key file on disk (generated if it doesn't exist)
+    Path(PathBuf),
+
+    /// Key stored in a signing provider
+    #[cfg(any(feature = "softsign", feature = "yubihsm"))]
+    Provider(ProviderKeyConfig),
+}
+
+/// Reference to an identity key stored in a signing provider
+#[cfg(any(feature = "softsign", feature = "yubihsm"))]
+#[derive(Clone, Deserialize, Debug)]
+#[serde(deny_unknown_fields, tag = "provider")]
+pub enum ProviderKeyConfig {
+    /// Ed25519 key in the YubiHSM
+    #[cfg(feature = "yubihsm")]
+    #[serde(rename = "yubihsm")]
+    Yubihsm {
+        /// Signing key ID
+        key: u16,
+    },
+
+    /// Software-backed key in any format supported by the softsign provider
+    #[cfg(feature = "softsign")]
+    #[serde(rename = "softsign")]
+    SoftSign {
+        /// Private key file format
+        key_format: Option<KeyFormat>,
+
+        /// Path to a file containing a cryptographic key
+        path: SoftPrivateKey,
+
+        /// Password for `encrypted` keys (prompted for if not set)
+        password: Option<Secret<Password>>,
+    },
+}
+
+/// Identity key signer.
+///
+/// Identity keys aren't registered in any chain's keyring (they shouldn't be
+/// consensus keys), so rather than looking up the keyring's `Signer` this is
+/// built with the same provider code (e.g. the YubiHSM session pool).
+pub type SecretKeySigner = Box<dyn Signer<ed25519::Signature> + Send + Sync>;
+
 impl ValidatorConfig {
-    /// Load the configured secret key from disk
-    pub fn load_secret_key(&self) -> Result<ed25519::Seed, Error> {
-        let secret_key_path = self.secret_key.as_ref().ok_or_else(|| {
+    /// Will connections to this validator be encrypted with SecretConnection
+    /// (i.e. do they need `secret_key`)?
+    pub fn uses_secret_connection(&self) -> bool {
+        match self.addr {
+            net::Address::Tcp { .. } => true,
+            net::Address::Unix { .. } => self.secret_connection.is_some(),
+        }
+    }
+
+    /// Load the configured secret key, returning its public key and a signer
+    pub fn load_secret_key(&self) -> Result<(ed25519::PublicKey, SecretKeySigner), Error> {
+        let secret_key = self.secret_key.as_ref().ok_or_else(|| {
             format_err!(
                 VerificationError,
                 "config error: no `secret_key` for validator {}",
@@ -48,29 +150,87 @@ impl ValidatorConfig {
             )
         })?;
 
-        let seed = if secret_key_path.exists() {
-            ed25519::Seed::decode_from_file(secret_key_path, &SecretKeyEncoding::default())
-                .map_err(|e| {
-                    format_err!(
-                        ConfigError,
-                        "error loading Secret Connection key from {}: {}",
-                        secret_key_path.display(),
-                        e
-                    )
-                })?
-        } else {
-            let s = ed25519::Seed::generate();
-            s.encode_to_file(&secret_key_path, &SecretKeyEncoding::default())
-                .map_err(|_| {
-                    format_err!(IoError, "couldn't write: {}", secret_key_path.display())
-                })?;
-            s
-        };
-
-        Ok(seed)
+        secret_key.load(self.auto_generate_identity)
     }
 }
 
+impl SecretKeyConfig {
+    /// Load this secret key, returning its public key and a signer.
+    ///
+    /// If `auto_generate` is set, key files which don't exist are generated.
+    pub fn load(
+        &self,
+        auto_generate: bool,
+    ) -> Result<(ed25519::PublicKey, SecretKeySigner), Error> {
+        match self {
+            SecretKeyConfig::Path(path) => {
+                boxed_signer(Ed25519Signer::from(&load_seed(path, auto_generate)?))
+            }
+            #[cfg(any(feature = "softsign", feature = "yubihsm"))]
+            SecretKeyConfig::Provider(provider) => match provider {
+                #[cfg(feature = "yubihsm")]
+                ProviderKeyConfig::Yubihsm { key } => {
+                    boxed_signer(crate::keyring::ed25519::yubihsm::signer(*key)?)
+                }
+                #[cfg(feature = "softsign")]
+                ProviderKeyConfig::SoftSign {
+                    key_format,
+                    path,
+                    password,
+                } => {
+                    let seed = crate::keyring::ed25519::softsign::load_seed(
+                        path,
+                        key_format.unwrap_or_default(),
+                        password.as_ref(),
+                    )?;
+
+                    boxed_signer(Ed25519Signer::from(&seed))
+                }
+            },
+        }
+    }
+}
+
+/// Load the secret key seed at the given path, generating it if it doesn't
+/// exist and `auto_generate` is set
+fn load_seed(secret_key_path: &Path, auto_generate: bool) -> Result<ed25519::Seed, Error> {
+    let seed = if secret_key_path.exists() {
+        ed25519::Seed::decode_from_file(secret_key_path, &SecretKeyEncoding::default()).map_err(
+            |e| {
+                format_err!(
+                    ConfigError,
+                    "error loading Secret Connection key from {}: {}",
+                    secret_key_path.display(),
+                    e
+                )
+            },
+        )?
+    } else if auto_generate {
+        let s = ed25519::Seed::generate();
+        s.encode_to_file(secret_key_path, &SecretKeyEncoding::default())
+            .map_err(|_| format_err!(IoError, "couldn't write: {}", secret_key_path.display()))?;
+        s
+    } else {
+        fail!(
+            ConfigError,
+            "identity key {} does not exist (create it with `tmkms identity generate` \
+             or set `auto_generate_identity = true`)",
+            secret_key_path.display()
+        );
+    };
+
+    Ok(seed)
+}
+
+/// Box a signer, obtaining its public key
+fn boxed_signer<S>(signer: S) -> Result<(ed25519::PublicKey, SecretKeySigner), Error>
+where
+    S: PublicKeyed<ed25519::PublicKey> + Signer<ed25519::Signature> + Send + Sync + 'static,
+{
+    let public_key = signer.public_key().map_err(|_| Error::from(InvalidKey))?;
+    Ok((public_key, Box::new(signer)))
+}
+
 /// Default value for the `ValidatorConfig` reconnect field
 fn reconnect_default() -> bool {
     true
@@ -4,11 +4,60 @@ pub mod secret_connection;
 pub mod tcp;
 pub mod unix;
 
-use self::{secret_connection::SecretConnection, unix::UnixConnection};
-use std::io;
+use self::{
+    secret_connection::{PublicKey, SecretConnection, TryClone},
+    unix::UnixConnection,
+};
+use crate::{
+    error::{Error, ErrorKind::*},
+    prelude::*,
+};
+use signatory::{ed25519, signature::Signer};
+use std::{io, time::Duration};
+use subtle::ConstantTimeEq;
+use tendermint::node;
+
+/// Default timeout in seconds
+pub const DEFAULT_TIMEOUT: u16 = 10;
 
 /// Connections to a validator
 pub trait Connection: io::Read + io::Write + Sync + Send {}
 
 impl<T> Connection for SecretConnection<T> where T: io::Read + io::Write + Sync + Send {}
 impl<T> Connection for UnixConnection<T> where T: io::Read + io::Write + Sync + Send {}
+
+/// Perform the SecretConnection handshake over the given socket, verifying
+/// the remote peer ID if one is expected
+pub(crate) fn secret_handshake<IoHandler>(
+    socket: IoHandler,
+    peer_addr: &str,
+    public_key: &ed25519::PublicKey,
+    signer: &dyn Signer<ed25519::Signature>,
+    peer_id: &Option<node::Id>,
+    timeout: Duration,
+) -> Result<SecretConnection<IoHandler>, Error>
+where
+    IoHandler: io::Read + io::Write + Send + Sync + TryClone + 'static,
+{
+    let public_key = PublicKey::from(*public_key);
+
+    info!("KMS node ID: {}", &public_key);
+
+    let connection = SecretConnection::new_with_timeout(socket, &public_key, signer, timeout)?;
+
+    let actual_peer_id = connection.remote_pubkey().peer_id();
+
+    if let Some(expected_peer_id) = peer_id {
+        if expected_peer_id.ct_eq(&actual_peer_id).unwrap_u8() == 0 {
+            fail!(
+                VerificationError,
+                "{}: peer ID mismatch! (expected {}, got {})",
+                peer_addr,
+                expected_peer_id,
+                actual_peer_id
+            );
+        }
+    }
+
+    Ok(connection)
+}
@@ -6,7 +6,10 @@ mod nonce;
 mod public_key;
 
 pub use self::{amino_types::AuthSigMessage, kdf::Kdf, nonce::Nonce, public_key::PublicKey};
-use crate::error::{Error, ErrorKind};
+use crate::{
+    error::{Error, ErrorKind},
+    prelude::*,
+};
 use bytes::BufMut;
 use chacha20poly1305::{
     aead::{generic_array::GenericArray, Aead, NewAead},
@@ -23,6 +26,11 @@ use std::{
     convert::TryInto,
     io::{self, Read, Write},
     marker::{Send, Sync},
+    net::{Shutdown, TcpStream},
+    os::unix::net::UnixStream,
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
 };
 use subtle::ConstantTimeEq;
 use x25519_dalek::{EphemeralSecret, PublicKey as EphemeralPublic};
@@ -35,6 +43,50 @@ const DATA_LEN_SIZE: usize = 4;
 const DATA_MAX_SIZE: usize = 1024;
 const TOTAL_FRAME_SIZE: usize = DATA_MAX_SIZE + DATA_LEN_SIZE;
 
+/// Size of an encrypted frame on the wire (frame plus MAC tag)
+const SEALED_FRAME_SIZE: usize = TAG_SIZE + TOTAL_FRAME_SIZE;
+
+/// Size of an amino-encoded ephemeral public key message (see `share_eph_pubkey`)
+const EPH_PUBKEY_MSG_SIZE: usize = 34;
+
+/// Default amount of time to wait for the handshake to complete
+pub const DEFAULT_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// I/O handlers which can be duplicated into independent handles to the same
+/// underlying stream.
+///
+/// This allows the handshake to send and receive at the same time, the same
+/// way the Go implementation does, so two peers which both write before they
+/// read can't deadlock on each other.
+pub trait TryClone: Sized {
+    /// Create a new handle to the same underlying stream
+    fn try_clone(&self) -> io::Result<Self>;
+
+    /// Shut down both directions of the underlying stream, unblocking any
+    /// reads or writes in progress on other handles to it
+    fn shutdown(&self) -> io::Result<()>;
+}
+
+impl TryClone for TcpStream {
+    fn try_clone(&self) -> io::Result<Self> {
+        TcpStream::try_clone(self)
+    }
+
+    fn shutdown(&self) -> io::Result<()> {
+        TcpStream::shutdown(self, Shutdown::Both)
+    }
+}
+
+impl TryClone for UnixStream {
+    fn try_clone(&self) -> io::Result<Self> {
+        UnixStream::try_clone(self)
+    }
+
+    fn shutdown(&self) -> io::Result<()> {
+        UnixStream::shutdown(self, Shutdown::Both)
+    }
+}
+
 /// Encrypted connection between peers in a Tendermint network
 pub struct SecretConnection<IoHandler: Read + Write + Send + Sync> {
     io_handler: IoHandler,
@@ -52,19 +104,47 @@ impl<IoHandler: Read + Write + Send + Sync> SecretConnection<IoHandler> {
         self.remote_pubkey
     }
 
-    /// Performs handshake and returns a new authenticated SecretConnection.
+    /// Performs handshake and returns a new authenticated SecretConnection,
+    /// waiting at most `DEFAULT_HANDSHAKE_TIMEOUT` for the peer.
     pub fn new(
-        mut handler: IoHandler,
+        handler: IoHandler,
+        local_pubkey: &PublicKey,
+        local_privkey: &dyn Signer<ed25519::Signature>,
+    ) -> Result<SecretConnection<IoHandler>, Error>
+    where
+        IoHandler: TryClone + 'static,
+    {
+        Self::new_with_timeout(
+            handler,
+            local_pubkey,
+            local_privkey,
+            DEFAULT_HANDSHAKE_TIMEOUT,
+        )
+    }
+
+    /// Performs handshake and returns a new authenticated SecretConnection,
+    /// failing if the handshake hasn't completed within the given timeout.
+    ///
+    /// The timeout is enforced independently of the underlying transport, so
+    /// it also applies to I/O handlers without socket-level timeouts.
+    pub fn new_with_timeout(
+        handler: IoHandler,
         local_pubkey: &PublicKey,
         local_privkey: &dyn Signer<ed25519::Signature>,
-    ) -> Result<SecretConnection<IoHandler>, Error> {
+        timeout: Duration,
+    ) -> Result<SecretConnection<IoHandler>, Error>
+    where
+        IoHandler: TryClone + 'static,
+    {
+        let deadline = Instant::now() + timeout;
+
         // Generate ephemeral keys for perfect forward secrecy.
         let (local_eph_pubkey, local_eph_privkey) = gen_eph_keys();
 
         // Write local ephemeral pubkey and receive one too.
         // NOTE: every 32-byte string is accepted as a Curve25519 public key
         // (see DJB's Curve25519 paper: http://cr.yp.to/ecdh/curve25519-20060209.pdf)
-        let remote_eph_pubkey = share_eph_pubkey(&mut handler, &local_eph_pubkey)?;
+        let remote_eph_pubkey = share_eph_pubkey(&handler, &local_eph_pubkey, deadline)?;
 
         // Compute common shared secret.
         let shared_secret = EphemeralSecret::diffie_hellman(local_eph_privkey, &remote_eph_pubkey);
@@ -111,7 +191,7 @@ impl<IoHandler: Read + Write + Send + Sync> SecretConnection<IoHandler> {
         // Share (in secret) each other's pubkey & challenge signature
         let auth_sig_msg = match local_pubkey {
             PublicKey::Ed25519(ref pk) => {
-                share_auth_signature(&mut sc, pk.as_bytes(), local_signature)?
+                share_auth_signature(&mut sc, pk.as_bytes(), local_signature, deadline)?
             }
         };
 
@@ -131,11 +211,45 @@ impl<IoHandler: Read + Write + Send + Sync> SecretConnection<IoHandler> {
         Ok(sc)
     }
 
+    /// Encrypt the given data into one or more sealed frames, advancing the
+    /// send nonce for each frame
+    fn seal(&mut self, data: &[u8]) -> Result<Vec<u8>, Error> {
+        let mut sealed_frames = Vec::with_capacity(SEALED_FRAME_SIZE);
+
+        for chunk in data.chunks(DATA_MAX_SIZE) {
+            let mut sealed_frame = [0u8; SEALED_FRAME_SIZE];
+            self.encrypt(chunk, &mut sealed_frame)?;
+            self.send_nonce.increment();
+            sealed_frames.extend_from_slice(&sealed_frame);
+        }
+
+        Ok(sealed_frames)
+    }
+
+    /// Decrypt a single sealed frame, advancing the receive nonce and
+    /// returning the chunk of data it contains
+    fn open(&mut self, sealed_frame: &[u8]) -> Result<Vec<u8>, Error> {
+        let mut frame = [0u8; TOTAL_FRAME_SIZE];
+        self.decrypt(sealed_frame, &mut frame)?;
+        self.recv_nonce.increment();
+
+        let chunk_length = u32::from_le_bytes(frame[..DATA_LEN_SIZE].try_into().unwrap()) as usize;
+
+        if chunk_length > DATA_MAX_SIZE {
+            fail!(
+                ErrorKind::ProtocolError,
+                "chunk_length is greater than dataMaxSize"
+            );
+        }
+
+        Ok(frame[DATA_LEN_SIZE..(DATA_LEN_SIZE + chunk_length)].to_vec())
+    }
+
     /// Encrypt AEAD authenticated data
     fn encrypt(
         &self,
         chunk: &[u8],
-        sealed_frame: &mut [u8; TAG_SIZE + TOTAL_FRAME_SIZE],
+        sealed_frame: &mut [u8; SEALED_FRAME_SIZE],
     ) -> Result<(), Error> {
         debug_assert!(chunk.len() <= TOTAL_FRAME_SIZE - DATA_LEN_SIZE);
         sealed_frame[..DATA_LEN_SIZE].copy_from_slice(&(chunk.len() as u32).to_le_bytes());
@@ -194,48 +308,21 @@ where
     fn read(&mut self, data: &mut [u8]) -> Result<usize, io::Error> {
         if !self.recv_buffer.is_empty() {
             let n = cmp::min(data.len(), self.recv_buffer.len());
-            data.copy_from_slice(&self.recv_buffer[..n]);
-            let mut leftover_portion = vec![0; self.recv_buffer.len().checked_sub(n).unwrap()];
-            leftover_portion.clone_from_slice(&self.recv_buffer[n..]);
-            self.recv_buffer = leftover_portion;
-
+            data[..n].copy_from_slice(&self.recv_buffer[..n]);
+            self.recv_buffer.drain(..n);
             return Ok(n);
         }
 
-        let mut sealed_frame = [0u8; TAG_SIZE + TOTAL_FRAME_SIZE];
+        let mut sealed_frame = [0u8; SEALED_FRAME_SIZE];
         self.io_handler.read_exact(&mut sealed_frame)?;
 
-        // decrypt the frame
-        let mut frame = [0u8; TOTAL_FRAME_SIZE];
-        let res = self.decrypt(&sealed_frame, &mut frame);
-
-        if res.is_err() {
-            return Err(io::Error::new(
-                io::ErrorKind::Other,
-                res.err().unwrap().to_string(),
-            ));
-        }
-
-        self.recv_nonce.increment();
-        // end decryption
-
-        let chunk_length = u32::from_le_bytes(frame[..4].try_into().unwrap());
-
-        if chunk_length as usize > DATA_MAX_SIZE {
-            return Err(io::Error::new(
-                io::ErrorKind::Other,
-                "chunk_length is greater than dataMaxSize",
-            ));
-        }
-
-        let mut chunk = vec![0; chunk_length as usize];
-        chunk.clone_from_slice(
-            &frame[DATA_LEN_SIZE..(DATA_LEN_SIZE.checked_add(chunk_length as usize).unwrap())],
-        );
+        let chunk = self
+            .open(&sealed_frame)
+            .map_err(|e| io::Error::other(e.to_string()))?;
 
         let n = cmp::min(data.len(), chunk.len());
         data[..n].copy_from_slice(&chunk[..n]);
-        self.recv_buffer.copy_from_slice(&chunk[n..]);
+        self.recv_buffer = chunk[n..].to_vec();
 
         Ok(n)
     }
@@ -248,33 +335,12 @@ where
     // Writes encrypted frames of `sealedFrameSize`
     // CONTRACT: data smaller than dataMaxSize is read atomically.
     fn write(&mut self, data: &[u8]) -> Result<usize, io::Error> {
-        let mut n = 0usize;
-        let mut data_copy = &data[..];
-        while !data_copy.is_empty() {
-            let chunk: &[u8];
-            if DATA_MAX_SIZE < data.len() {
-                chunk = &data[..DATA_MAX_SIZE];
-                data_copy = &data_copy[DATA_MAX_SIZE..];
-            } else {
-                chunk = data_copy;
-                data_copy = &[0u8; 0];
-            }
-            let sealed_frame = &mut [0u8; TAG_SIZE + TOTAL_FRAME_SIZE];
-            let res = self.encrypt(chunk, sealed_frame);
-            if res.is_err() {
-                return Err(io::Error::new(
-                    io::ErrorKind::Other,
-                    res.err().unwrap().to_string(),
-                ));
-            }
-            self.send_nonce.increment();
-            // end encryption
-
-            self.io_handler.write_all(&sealed_frame[..])?;
-            n = n.checked_add(chunk.len()).unwrap();
-        }
+        let sealed_frames = self
+            .seal(data)
+            .map_err(|e| io::Error::other(e.to_string()))?;
 
-        Ok(n)
+        self.io_handler.write_all(&sealed_frames)?;
+        Ok(data.len())
     }
 
     fn flush(&mut self) -> Result<(), io::Error> {
@@ -291,15 +357,14 @@ fn gen_eph_keys() -> (EphemeralPublic, EphemeralSecret) {
 }
 
 /// Returns remote_eph_pubkey
-fn share_eph_pubkey<IoHandler: Read + Write + Send + Sync>(
-    handler: &mut IoHandler,
+fn share_eph_pubkey<IoHandler>(
+    handler: &IoHandler,
     local_eph_pubkey: &EphemeralPublic,
-) -> Result<EphemeralPublic, Error> {
-    // Send our pubkey and receive theirs in tandem.
-    // TODO(ismail): on the go side this is done in parallel, here we do send and receive after
-    // each other. thread::spawn would require a static lifetime.
-    // Should still work though.
-
+    deadline: Instant,
+) -> Result<EphemeralPublic, Error>
+where
+    IoHandler: Read + Write + TryClone + Send + 'static,
+{
     let mut buf = vec![0; 0];
     let local_eph_pubkey_vec = local_eph_pubkey.as_bytes();
     // Note: this is not regular protobuf encoding but raw length prefixed amino encoding;
@@ -309,15 +374,10 @@ fn share_eph_pubkey<IoHandler: Read + Write + Send + Sync>(
     buf.put_slice(local_eph_pubkey_vec); // raw bytes
 
     // TODO(ismail): we probably do *not* need the double length delimiting here or in tendermint)
-    // this is the sending part of:
+    // Send our pubkey and receive theirs in tandem, as in:
     // https://github.com/tendermint/tendermint/blob/013b9cef642f875634c614019ab13b17570778ad/p2p/conn/secret_connection.go#L208-L238
-    handler.write_all(&buf)?;
-
-    let mut buf = vec![0; 34];
-    handler.read_exact(&mut buf)?;
+    let buf = exchange(handler, buf, EPH_PUBKEY_MSG_SIZE, deadline)?;
 
-    // this is the receiving part of:
-    // https://github.com/tendermint/tendermint/blob/013b9cef642f875634c614019ab13b17570778ad/p2p/conn/secret_connection.go#L208-L238
     let mut remote_eph_pubkey_fixed: [u8; 32] = Default::default();
     if buf[0] != 33 || buf[1] != 32 {
         return Err(ErrorKind::ProtocolError.into());
@@ -333,6 +393,92 @@ fn share_eph_pubkey<IoHandler: Read + Write + Send + Sync>(
     }
 }
 
+/// Message sent or received by one half of an `exchange`
+enum Exchanged {
+    /// Outgoing message has been written
+    Sent(io::Result<()>),
+
+    /// Incoming message has been read
+    Received(io::Result<Vec<u8>>),
+}
+
+/// Send `outgoing` and receive `incoming_len` bytes at the same time, using
+/// independent handles to the underlying stream.
+///
+/// Fails with a timeout error if both halves haven't completed by `deadline`.
+/// If either half fails, the stream is shut down so neither is left blocked.
+fn exchange<IoHandler>(
+    handler: &IoHandler,
+    outgoing: Vec<u8>,
+    incoming_len: usize,
+    deadline: Instant,
+) -> Result<Vec<u8>, Error>
+where
+    IoHandler: Read + Write + TryClone + Send + 'static,
+{
+    let (sender, receiver) = mpsc::channel();
+
+    let mut writer = handler.try_clone()?;
+    let mut reader = handler.try_clone()?;
+
+    let write_sender = sender.clone();
+    let write_thread = thread::spawn(move || {
+        let result = writer.write_all(&outgoing).and_then(|_| writer.flush());
+        // the receiver is gone if the handshake already failed
+        let _ = write_sender.send(Exchanged::Sent(result));
+    });
+
+    let read_thread = thread::spawn(move || {
+        let mut incoming = vec![0u8; incoming_len];
+        let result = reader.read_exact(&mut incoming).map(|_| incoming);
+        let _ = sender.send(Exchanged::Received(result));
+    });
+
+    let result = await_exchange(&receiver, deadline);
+
+    if result.is_err() {
+        // unblock whichever half is still waiting on the peer
+        let _ = handler.shutdown();
+    }
+
+    // neither thread can panic, and both have finished (or been unblocked)
+    let _ = write_thread.join();
+    let _ = read_thread.join();
+
+    result
+}
+
+/// Wait for both halves of an `exchange` to complete, returning the
+/// incoming message
+fn await_exchange(
+    receiver: &mpsc::Receiver<Exchanged>,
+    deadline: Instant,
+) -> Result<Vec<u8>, Error> {
+    let mut sent = false;
+    let mut received = None;
+
+    while !sent || received.is_none() {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+
+        match receiver.recv_timeout(remaining) {
+            Ok(Exchanged::Sent(result)) => {
+                result?;
+                sent = true;
+            }
+            Ok(Exchanged::Received(result)) => received = Some(result?),
+            Err(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "timed out during SecretConnection handshake",
+                )
+                .into())
+            }
+        }
+    }
+
+    Ok(received.unwrap())
+}
+
 /// Reject the blacklist of degenerate points listed on <https://cr.yp.to/ecdh.html>
 ///
 /// These points contain low-order elements. Rejecting them is suggested in
@@ -402,31 +548,166 @@ fn sign_challenge(
         .map_err(|_| ErrorKind::CryptoError.into())
 }
 
-// TODO(ismail): change from DecodeError to something more generic
-// this can also fail while writing / sending
-fn share_auth_signature<IoHandler: Read + Write + Send + Sync>(
+/// Returns the remote peer's `AuthSigMessage`
+fn share_auth_signature<IoHandler>(
     sc: &mut SecretConnection<IoHandler>,
     pubkey: &[u8; 32],
     signature: ed25519::Signature,
-) -> Result<AuthSigMessage, Error> {
+    deadline: Instant,
+) -> Result<AuthSigMessage, Error>
+where
+    IoHandler: Read + Write + TryClone + Send + Sync + 'static,
+{
     let amsg = AuthSigMessage {
         key: pubkey.to_vec(),
         sig: signature.as_ref().to_vec(),
     };
     let mut buf: Vec<u8> = vec![];
     amsg.encode_length_delimited(&mut buf)?;
-    sc.write_all(&buf)?;
 
-    let mut rbuf = vec![0; 106]; // 100 = 32 + 64 + (amino overhead = 2 fields + 2 lengths + 4 prefix bytes + total length)
-    sc.read_exact(&mut rbuf)?;
+    // The auth signature message always fits in a single frame, so frames
+    // can be sealed and opened here and exchanged in the clear
+    let sealed_frame = sc.seal(&buf)?;
+    let remote_sealed_frame = exchange(&sc.io_handler, sealed_frame, SEALED_FRAME_SIZE, deadline)?;
+    let rbuf = sc.open(&remote_sealed_frame)?;
 
-    // TODO: proper error handling:
     Ok(AuthSigMessage::decode_length_delimited(rbuf.as_ref())?)
 }
 
-#[cfg(tests)]
+#[cfg(test)]
 mod tests {
     use super::*;
+    use signatory::public_key::PublicKeyed;
+    use signatory_dalek::Ed25519Signer;
+    use std::sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{RecvTimeoutError, SyncSender, TrySendError},
+        Arc, Mutex,
+    };
+
+    /// How often a blocked pipe checks whether it's been shut down
+    const PIPE_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+    /// Receiving half of a pipe, along with any partially read message
+    type PipeReceiver = Arc<Mutex<(mpsc::Receiver<Vec<u8>>, Vec<u8>)>>;
+
+    /// One end of an unbuffered, in-memory duplex pipe.
+    ///
+    /// Writes block until the other end reads them, so two ends which both
+    /// write before reading will deadlock unless they operate full-duplex.
+    #[derive(Clone)]
+    struct Pipe {
+        sender: SyncSender<Vec<u8>>,
+        receiver: PipeReceiver,
+        shutdown: Arc<AtomicBool>,
+    }
+
+    impl Pipe {
+        /// Create both ends of a duplex pipe
+        fn duplex() -> (Pipe, Pipe) {
+            let (a_sender, b_receiver) = mpsc::sync_channel(0);
+            let (b_sender, a_receiver) = mpsc::sync_channel(0);
+
+            let a = Pipe {
+                sender: a_sender,
+                receiver: Arc::new(Mutex::new((a_receiver, vec![]))),
+                shutdown: Arc::new(AtomicBool::new(false)),
+            };
+
+            let b = Pipe {
+                sender: b_sender,
+                receiver: Arc::new(Mutex::new((b_receiver, vec![]))),
+                shutdown: Arc::new(AtomicBool::new(false)),
+            };
+
+            (a, b)
+        }
+    }
+
+    impl TryClone for Pipe {
+        fn try_clone(&self) -> io::Result<Self> {
+            Ok(self.clone())
+        }
+
+        fn shutdown(&self) -> io::Result<()> {
+            self.shutdown.store(true, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    impl Read for Pipe {
+        fn read(&mut self, data: &mut [u8]) -> io::Result<usize> {
+            let mut receiver = self.receiver.lock().unwrap();
+            let (ref incoming, ref mut buffer) = *receiver;
+
+            while buffer.is_empty() {
+                if self.shutdown.load(Ordering::SeqCst) {
+                    return Ok(0);
+                }
+
+                match incoming.recv_timeout(PIPE_POLL_INTERVAL) {
+                    Ok(bytes) => *buffer = bytes,
+                    Err(RecvTimeoutError::Timeout) => (),
+                    Err(RecvTimeoutError::Disconnected) => return Ok(0),
+                }
+            }
+
+            let n = cmp::min(data.len(), buffer.len());
+            data[..n].copy_from_slice(&buffer[..n]);
+            buffer.drain(..n);
+            Ok(n)
+        }
+    }
+
+    impl Write for Pipe {
+        fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+            let mut message = data.to_vec();
+
+            loop {
+                if self.shutdown.load(Ordering::SeqCst) {
+                    return Err(io::ErrorKind::BrokenPipe.into());
+                }
+
+                match self.sender.try_send(message) {
+                    Ok(()) => return Ok(data.len()),
+                    Err(TrySendError::Full(unsent)) => {
+                        message = unsent;
+                        thread::sleep(PIPE_POLL_INTERVAL);
+                    }
+                    Err(TrySendError::Disconnected(_)) => {
+                        return Err(io::ErrorKind::BrokenPipe.into())
+                    }
+                }
+            }
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Generate a random identity keypair
+    fn identity() -> (PublicKey, Ed25519Signer) {
+        let signer = Ed25519Signer::from(&ed25519::Seed::generate());
+        (PublicKey::from(signer.public_key().unwrap()), signer)
+    }
+
+    /// Connection along with the identity key of the peer which opened it
+    type Peer = (SecretConnection<Pipe>, PublicKey);
+
+    /// Perform a handshake between both ends of a pipe, returning both connections
+    fn handshake() -> (Peer, Peer) {
+        let (pipe_a, pipe_b) = Pipe::duplex();
+        let (pubkey_a, signer_a) = identity();
+        let (pubkey_b, signer_b) = identity();
+
+        let peer_b = thread::spawn(move || SecretConnection::new(pipe_b, &pubkey_b, &signer_b));
+
+        let conn_a = SecretConnection::new(pipe_a, &pubkey_a, &signer_a).unwrap();
+        let conn_b = peer_b.join().unwrap().unwrap();
+
+        ((conn_a, pubkey_a), (conn_b, pubkey_b))
+    }
 
     #[test]
     fn test_sort() {
@@ -446,22 +727,66 @@ mod tests {
 
     #[test]
     fn test_dh_compatibility() {
-        let local_priv = &[
+        let local_priv = [
             15, 54, 189, 54, 63, 255, 158, 244, 56, 168, 155, 63, 246, 79, 208, 192, 35, 194, 39,
             232, 170, 187, 179, 36, 65, 36, 237, 12, 225, 176, 201, 54,
         ];
-        let remote_pub = &[
+        let remote_pub = [
             193, 34, 183, 46, 148, 99, 179, 185, 242, 148, 38, 40, 37, 150, 76, 251, 25, 51, 46,
             143, 189, 201, 169, 218, 37, 136, 51, 144, 88, 196, 10, 20,
         ];
 
         // generated using computeDHSecret in go
-        let expected_dh = &[
+        let expected_dh = [
             92, 56, 205, 118, 191, 208, 49, 3, 226, 150, 30, 205, 230, 157, 163, 7, 36, 28, 223,
             84, 165, 43, 78, 38, 126, 200, 40, 217, 29, 36, 43, 37,
         ];
-        let got_dh = diffie_hellman(local_priv, remote_pub);
+        let got_dh = x25519_dalek::x25519(local_priv, remote_pub);
+
+        assert_eq!(expected_dh, got_dh);
+    }
 
-        assert_eq!(expected_dh, &got_dh);
+    #[test]
+    fn test_handshake_full_duplex() {
+        let ((conn_a, pubkey_a), (conn_b, pubkey_b)) = handshake();
+        assert_eq!(conn_a.remote_pubkey(), pubkey_b);
+        assert_eq!(conn_b.remote_pubkey(), pubkey_a);
+    }
+
+    #[test]
+    fn test_read_write_after_handshake() {
+        let ((mut conn_a, _), (mut conn_b, _)) = handshake();
+        let message = b"hello from the other side".to_vec();
+
+        let sender = thread::spawn(move || {
+            conn_a.write_all(&message).unwrap();
+            message
+        });
+
+        let mut received = [0u8; 25];
+        conn_b.read_exact(&mut received).unwrap();
+        assert_eq!(&received[..], sender.join().unwrap().as_slice());
+    }
+
+    #[test]
+    fn test_handshake_timeout() {
+        // keep the other end alive, but never answer
+        let (pipe_a, pipe_b) = Pipe::duplex();
+        let (pubkey_a, signer_a) = identity();
+        let timeout = Duration::from_millis(100);
+
+        let started_at = Instant::now();
+        let result = SecretConnection::new_with_timeout(pipe_a, &pubkey_a, &signer_a, timeout);
+        let err = result.err().expect("handshake should have timed out");
+
+        assert_eq!(*err.kind(), ErrorKind::IoError);
+        assert!(started_at.elapsed() < DEFAULT_HANDSHAKE_TIMEOUT);
+
+        // the handshake's reader and writer threads (and their handles to
+        // `pipe_a`) must be gone, rather than blocked forever
+        match pipe_b.sender.try_send(vec![]) {
+            Err(TrySendError::Disconnected(_)) => (),
+            other => panic!("handshake threads still running: {:?}", other),
+        }
     }
 }
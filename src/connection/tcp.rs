@@ -1,54 +1,61 @@
 //! TCP socket connection to a validator
 
-use super::secret_connection::{PublicKey, SecretConnection};
-use crate::{
-    error::{Error, ErrorKind::*},
-    prelude::*,
+use super::{secret_connection::SecretConnection, secret_handshake, DEFAULT_TIMEOUT};
+use crate::error::Error;
+use signatory::{ed25519, signature::Signer};
+use std::{
+    net::{TcpListener, TcpStream},
+    time::Duration,
 };
-use signatory::{ed25519, public_key::PublicKeyed};
-use signatory_dalek::Ed25519Signer;
-use std::{net::TcpStream, time::Duration};
-use subtle::ConstantTimeEq;
 use tendermint::node;
 
-/// Default timeout in seconds
-const DEFAULT_TIMEOUT: u16 = 10;
-
-/// Open a TCP socket connection encrypted with SecretConnection
+/// Open a TCP socket connection encrypted with SecretConnection, authenticating
+/// with the given identity key
 pub fn open_secret_connection(
     host: &str,
     port: u16,
-    secret_key: &ed25519::Seed,
+    public_key: &ed25519::PublicKey,
+    signer: &dyn Signer<ed25519::Signature>,
     peer_id: &Option<node::Id>,
     timeout: Option<u16>,
 ) -> Result<SecretConnection<TcpStream>, Error> {
-    let signer = Ed25519Signer::from(secret_key);
-    let public_key = PublicKey::from(signer.public_key().map_err(|_| Error::from(InvalidKey))?);
-
-    info!("KMS node ID: {}", &public_key);
+    let peer_addr = format!("{}:{}", host, port);
+    let socket = TcpStream::connect(&peer_addr)?;
+    handshake(socket, &peer_addr, public_key, signer, peer_id, timeout)
+}
 
-    let socket = TcpStream::connect(format!("{}:{}", host, port))?;
+/// Accept an incoming TCP socket connection and encrypt it with
+/// SecretConnection (i.e. act as the server side of the handshake)
+pub fn accept_secret_connection(
+    listener: &TcpListener,
+    public_key: &ed25519::PublicKey,
+    signer: &dyn Signer<ed25519::Signature>,
+    peer_id: &Option<node::Id>,
+    timeout: Option<u16>,
+) -> Result<SecretConnection<TcpStream>, Error> {
+    let (socket, peer_addr) = listener.accept()?;
+    handshake(
+        socket,
+        &peer_addr.to_string(),
+        public_key,
+        signer,
+        peer_id,
+        timeout,
+    )
+}
 
+/// Apply timeouts to the given socket and perform the SecretConnection handshake
+fn handshake(
+    socket: TcpStream,
+    peer_addr: &str,
+    public_key: &ed25519::PublicKey,
+    signer: &dyn Signer<ed25519::Signature>,
+    peer_id: &Option<node::Id>,
+    timeout: Option<u16>,
+) -> Result<SecretConnection<TcpStream>, Error> {
     let timeout = Duration::from_secs(timeout.unwrap_or(DEFAULT_TIMEOUT).into());
     socket.set_read_timeout(Some(timeout))?;
     socket.set_write_timeout(Some(timeout))?;
 
-    let connection = SecretConnection::new(socket, &public_key, &signer)?;
-    let actual_peer_id = connection.remote_pubkey().peer_id();
-
-    // TODO(tarcieri): move this into `SecretConnection::new`
-    if let Some(expected_peer_id) = peer_id {
-        if expected_peer_id.ct_eq(&actual_peer_id).unwrap_u8() == 0 {
-            fail!(
-                VerificationError,
-                "{}:{}: validator peer ID mismatch! (expected {}, got {})",
-                host,
-                port,
-                expected_peer_id,
-                actual_peer_id
-            );
-        }
-    }
-
-    Ok(connection)
+    secret_handshake(socket, peer_addr, public_key, signer, peer_id, timeout)
 }
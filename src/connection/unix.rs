@@ -1,7 +1,20 @@
 //! Unix domain socket connection to a validator
 
-use std::io;
-use std::marker::{Send, Sync};
+use super::{secret_connection::SecretConnection, secret_handshake, DEFAULT_TIMEOUT};
+use crate::{
+    config::PeerCredentialsConfig,
+    error::{Error, ErrorKind::*},
+    prelude::*,
+};
+use signatory::{ed25519, signature::Signer};
+use std::{
+    io,
+    marker::{Send, Sync},
+    os::unix::net::UnixStream,
+    path::Path,
+    time::Duration,
+};
+use tendermint::node;
 
 /// Protocol implementation of the UNIX socket domain connection
 pub struct UnixConnection<IoHandler> {
@@ -39,3 +52,200 @@ where
         self.socket.flush()
     }
 }
+
+/// Credentials of the process on the other end of a Unix domain socket
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct PeerCredentials {
+    /// User ID
+    pub uid: u32,
+
+    /// Group ID
+    pub gid: u32,
+
+    /// Process ID
+    pub pid: i32,
+}
+
+impl PeerCredentials {
+    /// Get the credentials of the peer connected to the given socket
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn from_socket(socket: &UnixStream) -> Result<Self, Error> {
+        use nix::sys::socket::{getsockopt, sockopt};
+        use std::os::unix::io::AsRawFd;
+
+        let creds = getsockopt(socket.as_raw_fd(), sockopt::PeerCredentials)
+            .map_err(|e| format_err!(IoError, "couldn't get peer credentials: {}", e))?;
+
+        Ok(Self {
+            uid: creds.uid(),
+            gid: creds.gid(),
+            pid: creds.pid(),
+        })
+    }
+
+    /// Get the credentials of the peer connected to the given socket
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    pub fn from_socket(_socket: &UnixStream) -> Result<Self, Error> {
+        fail!(
+            ConfigError,
+            "peer credential checks are only supported on Linux"
+        );
+    }
+}
+
+/// Connect to a validator's Unix domain socket, applying timeouts and
+/// verifying the peer's credentials (if configured)
+pub fn connect(
+    path: &Path,
+    timeout: Option<u16>,
+    peer_credentials: Option<&PeerCredentialsConfig>,
+) -> Result<UnixStream, Error> {
+    let socket = UnixStream::connect(path)?;
+
+    let timeout = Duration::from_secs(timeout.unwrap_or(DEFAULT_TIMEOUT).into());
+    socket.set_read_timeout(Some(timeout))?;
+    socket.set_write_timeout(Some(timeout))?;
+
+    if let Some(expected) = peer_credentials {
+        let actual = PeerCredentials::from_socket(&socket)?;
+
+        let checks = [
+            ("UID", expected.uid.map(i64::from), i64::from(actual.uid)),
+            ("GID", expected.gid.map(i64::from), i64::from(actual.gid)),
+            ("PID", expected.pid.map(i64::from), i64::from(actual.pid)),
+        ];
+
+        for (name, expected_value, actual_value) in &checks {
+            if let Some(expected_value) = expected_value {
+                if expected_value != actual_value {
+                    fail!(
+                        VerificationError,
+                        "unix://{}: peer {} mismatch! (expected {}, got {})",
+                        path.display(),
+                        name,
+                        expected_value,
+                        actual_value
+                    );
+                }
+            }
+        }
+
+        debug!(
+            "unix://{}: verified peer credentials: {:?}",
+            path.display(),
+            actual
+        );
+    }
+
+    Ok(socket)
+}
+
+/// Encrypt a connected Unix domain socket with SecretConnection, authenticating
+/// with the given identity key
+pub fn open_secret_connection(
+    socket: UnixStream,
+    path: &Path,
+    public_key: &ed25519::PublicKey,
+    signer: &dyn Signer<ed25519::Signature>,
+    peer_id: &Option<node::Id>,
+    timeout: Option<u16>,
+) -> Result<SecretConnection<UnixStream>, Error> {
+    let peer_addr = format!("unix://{}", path.display());
+    let timeout = Duration::from_secs(timeout.unwrap_or(DEFAULT_TIMEOUT).into());
+    secret_handshake(socket, &peer_addr, public_key, signer, peer_id, timeout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::secret_connection::PublicKey;
+    use nix::unistd::{getgid, getuid};
+    use signatory::public_key::PublicKeyed;
+    use signatory_dalek::Ed25519Signer;
+    use std::{os::unix::net::UnixListener, path::PathBuf, process, thread};
+    use tempfile::TempDir;
+
+    /// Bind a listener to a socket in a fresh temporary directory
+    fn listen() -> (TempDir, PathBuf, UnixListener) {
+        let tempdir = TempDir::new().unwrap();
+        let path = tempdir.path().join("validator.sock");
+        let listener = UnixListener::bind(&path).unwrap();
+        (tempdir, path, listener)
+    }
+
+    #[test]
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn test_peer_credentials() {
+        let (a, _b) = UnixStream::pair().unwrap();
+        let creds = PeerCredentials::from_socket(&a).unwrap();
+
+        assert_eq!(creds.uid, getuid().as_raw());
+        assert_eq!(creds.gid, getgid().as_raw());
+        assert_eq!(creds.pid, process::id() as i32);
+    }
+
+    #[test]
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn test_connect_verifies_peer_credentials() {
+        let (_tempdir, path, _listener) = listen();
+
+        let expected = PeerCredentialsConfig {
+            uid: Some(getuid().as_raw()),
+            pid: Some(process::id() as i32),
+            ..Default::default()
+        };
+
+        connect(&path, None, Some(&expected)).unwrap();
+
+        let unexpected = PeerCredentialsConfig {
+            uid: Some(getuid().as_raw().wrapping_add(1)),
+            ..Default::default()
+        };
+
+        let err = connect(&path, None, Some(&unexpected)).unwrap_err();
+        assert_eq!(*err.kind(), VerificationError);
+    }
+
+    #[test]
+    fn test_secret_connection() {
+        let (_tempdir, path, listener) = listen();
+
+        let validator_signer = Ed25519Signer::from(&ed25519::Seed::generate());
+        let validator_public_key = validator_signer.public_key().unwrap();
+        let validator_peer_id = PublicKey::from(validator_public_key).peer_id();
+
+        let validator = thread::spawn(move || {
+            let (socket, _) = listener.accept().unwrap();
+            secret_handshake(
+                socket,
+                "kms",
+                &validator_public_key,
+                &validator_signer,
+                &None,
+                Duration::from_secs(DEFAULT_TIMEOUT.into()),
+            )
+            .unwrap()
+        });
+
+        let kms_signer = Ed25519Signer::from(&ed25519::Seed::generate());
+        let kms_public_key = kms_signer.public_key().unwrap();
+
+        let socket = connect(&path, None, None).unwrap();
+        let kms_conn = open_secret_connection(
+            socket,
+            &path,
+            &kms_public_key,
+            &kms_signer,
+            &Some(validator_peer_id),
+            None,
+        )
+        .unwrap();
+
+        let validator_conn = validator.join().unwrap();
+        assert_eq!(
+            validator_conn.remote_pubkey(),
+            PublicKey::from(kms_public_key)
+        );
+        assert_eq!(kms_conn.remote_pubkey().peer_id(), validator_peer_id);
+    }
+}
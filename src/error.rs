@@ -18,6 +18,10 @@ pub enum ErrorKind {
     #[cfg(feature = "yubihsm")]
     AccessError,
 
+    /// Error delivering an alert
+    #[error("alert delivery failed")]
+    AlertError,
+
     /// Error in configuration file
     #[error("config error")]
     ConfigError,
@@ -50,6 +54,10 @@ pub enum ErrorKind {
     #[error("I/O error")]
     IoError,
 
+    /// Chain state is locked by another process
+    #[error("state locked")]
+    LockError,
+
     /// KMS internal panic
     #[error("internal crash")]
     PanicError,
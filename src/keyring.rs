@@ -48,8 +48,13 @@ impl KeyRing {
         };
 
         info!(
+            provider = %provider,
+            key_type,
+            public_key = %public_key_serialized,
             "[keyring:{}] added {} key {}",
-            provider, key_type, public_key_serialized
+            provider,
+            key_type,
+            public_key_serialized
         );
 
         if let Some(other) = self.keys.insert(public_key, signer) {
@@ -6,11 +6,70 @@ use crate::{
     error::{Error, ErrorKind::*},
     keyring::{ed25519::Signer, SigningProvider},
     prelude::*,
+    status,
 };
-use signatory::public_key::PublicKeyed;
+use signatory::{ed25519::Signature, public_key::PublicKeyed, signature};
 use signatory_ledger_tm::Ed25519LedgerTmAppSigner;
+use std::{fmt, sync::Arc};
 use tendermint::TendermintKey;
 
+/// APDU class of the Tendermint validator app
+const CLA: u8 = 0x56;
+
+/// APDU instruction for getting the app version
+const INS_GET_VERSION: u8 = 0x00;
+
+/// APDU return code for successful commands
+const APDU_OK: u16 = 0x9000;
+
+/// Version of the Tendermint validator app running on the Ledger
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct AppVersion {
+    /// Is the app a test build?
+    pub test_mode: bool,
+
+    /// Major version
+    pub major: u8,
+
+    /// Minor version
+    pub minor: u8,
+
+    /// Patch version
+    pub patch: u8,
+}
+
+impl AppVersion {
+    /// Parse the response to a `GET_VERSION` command
+    pub fn from_response(data: &[u8]) -> Result<Self, Error> {
+        if data.len() < 4 {
+            fail!(
+                ProtocolError,
+                "malformed Ledger app version response ({} bytes)",
+                data.len()
+            );
+        }
+
+        Ok(AppVersion {
+            test_mode: data[0] != 0,
+            major: data[1],
+            minor: data[2],
+            patch: data[3],
+        })
+    }
+}
+
+impl fmt::Display for AppVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+
+        if self.test_mode {
+            write!(f, " (test mode)")?;
+        }
+
+        Ok(())
+    }
+}
+
 /// Create Ledger Tendermint signer object from the given configuration
 pub fn init(
     chain_registry: &mut chain::Registry,
@@ -28,7 +87,7 @@ pub fn init(
         );
     }
 
-    let provider = Ed25519LedgerTmAppSigner::connect().map_err(|_| Error::from(SigningError))?;
+    let provider = Arc::new(connect()?);
     let public_key = provider.public_key().map_err(|_| Error::from(InvalidKey))?;
 
     // TODO(tarcieri): support for adding account keys into keyrings
@@ -37,12 +96,81 @@ pub fn init(
     let signer = Signer::new(
         SigningProvider::LedgerTm,
         consensus_pubkey,
-        Box::new(provider),
+        Box::new(SharedSigner(provider.clone())),
     );
 
     for chain_id in &ledgertm_configs[0].chain_ids {
         chain_registry.add_to_keyring(chain_id, signer.clone())?;
     }
 
+    status::register_provider("ledgertm", move || {
+        provider
+            .public_key()
+            .map(|_| ())
+            .map_err(|e| format_err!(SigningError, "{}", e).into())
+    });
+
     Ok(())
 }
+
+/// Connect to the Tendermint validator app on the Ledger
+pub fn connect() -> Result<Ed25519LedgerTmAppSigner, Error> {
+    Ed25519LedgerTmAppSigner::connect()
+        .map_err(|e| format_err!(SigningError, "couldn't connect to Ledger: {}", e).into())
+}
+
+/// Get the version of the Tendermint validator app running on the Ledger
+pub fn app_version() -> Result<AppVersion, Error> {
+    let app = ledger::LedgerApp::new()
+        .map_err(|e| format_err!(SigningError, "couldn't connect to Ledger: {}", e))?;
+
+    let response = app
+        .exchange(ledger::ApduCommand {
+            cla: CLA,
+            ins: INS_GET_VERSION,
+            p1: 0x00,
+            p2: 0x00,
+            length: 0,
+            data: vec![],
+        })
+        .map_err(|e| format_err!(SigningError, "couldn't get Ledger app version: {}", e))?;
+
+    if response.retcode != APDU_OK {
+        fail!(
+            SigningError,
+            "couldn't get Ledger app version: {}",
+            ledger::map_apdu_error(response.retcode)
+        );
+    }
+
+    AppVersion::from_response(&response.data)
+}
+
+/// Ledger signer shared between the keyring and the provider health check
+struct SharedSigner(Arc<Ed25519LedgerTmAppSigner>);
+
+impl signature::Signer<Signature> for SharedSigner {
+    fn try_sign(&self, msg: &[u8]) -> Result<Signature, signature::Error> {
+        signature::Signer::try_sign(self.0.as_ref(), msg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_app_version() {
+        let version = AppVersion::from_response(&[0, 0, 4, 1]).unwrap();
+        assert_eq!(version.to_string(), "0.4.1");
+
+        let version = AppVersion::from_response(&[0xff, 1, 2, 3, 0]).unwrap();
+        assert!(version.test_mode);
+        assert_eq!(version.to_string(), "1.2.3 (test mode)");
+    }
+
+    #[test]
+    fn rejects_short_app_version() {
+        assert!(AppVersion::from_response(&[0, 0, 4]).is_err());
+    }
+}
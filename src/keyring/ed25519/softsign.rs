@@ -2,17 +2,25 @@
 //!
 //! This is mainly intended for testing/CI. Ideally real validators will use HSMs
 
+pub mod encrypted;
+pub mod mnemonic;
+
+use self::encrypted::EncryptedKey;
 use super::Signer;
 use crate::{
     chain,
-    config::provider::softsign::{KeyFormat, SoftsignConfig},
+    config::{
+        password::Password,
+        provider::softsign::{KeyFormat, SoftPrivateKey, SoftsignConfig},
+    },
     error::{Error, ErrorKind::*},
     keyring::{SecretKeyEncoding, SigningProvider},
     prelude::*,
 };
+use abscissa_core::secret::{ExposeSecret, Secret};
 use signatory::{ed25519, encoding::Decode, public_key::PublicKeyed};
 use signatory_dalek::Ed25519Signer;
-use std::{fs, process};
+use std::fs;
 use tendermint::{config::PrivValidatorKey, PrivateKey, TendermintKey};
 
 /// Create software-backed Ed25519 signer objects from the given configuration
@@ -32,14 +40,43 @@ pub fn init(chain_registry: &mut chain::Registry, configs: &[SoftsignConfig]) ->
 
     let config = &configs[0];
     let key_format = config.key_format.as_ref().cloned().unwrap_or_default();
+    let seed = load_seed(&config.path, key_format, config.password.as_ref())?;
+
+    let provider = Ed25519Signer::from(&seed);
+    let public_key = provider.public_key().map_err(|_| Error::from(InvalidKey))?;
 
+    // TODO(tarcieri): support for adding account keys into keyrings
+    let consensus_pubkey = TendermintKey::ConsensusKey(public_key.into());
+
+    let signer = Signer::new(
+        SigningProvider::SoftSign,
+        consensus_pubkey,
+        Box::new(provider),
+    );
+
+    for chain_id in &config.chain_ids {
+        chain_registry.add_to_keyring(chain_id, signer.clone())?;
+    }
+
+    Ok(())
+}
+
+/// Load an Ed25519 seed from a softsign key file in the given format.
+///
+/// Encrypted keys are decrypted with the given password, prompting for it
+/// if none was given.
+pub fn load_seed(
+    path: &SoftPrivateKey,
+    key_format: KeyFormat,
+    password: Option<&Secret<Password>>,
+) -> Result<ed25519::Seed, Error> {
     let seed = match key_format {
         KeyFormat::Base64 => {
-            let base64 = fs::read_to_string(&config.path).map_err(|e| {
+            let base64 = fs::read_to_string(path).map_err(|e| {
                 format_err!(
                     ConfigError,
                     "couldn't read key from {}: {}",
-                    &config.path.as_ref().display(),
+                    path.as_ref().display(),
                     e
                 )
             })?;
@@ -52,18 +89,18 @@ pub fn init(chain_registry: &mut chain::Registry, configs: &[SoftsignConfig]) ->
                     format_err!(
                         ConfigError,
                         "can't decode key from {}: {}",
-                        config.path.as_ref().display(),
+                        path.as_ref().display(),
                         e
                     )
                 },
             )?
         }
         KeyFormat::Raw => {
-            let bytes = fs::read(&config.path).map_err(|e| {
+            let bytes = fs::read(path).map_err(|e| {
                 format_err!(
                     ConfigError,
                     "couldn't read key from {}: {}",
-                    &config.path.as_ref().display(),
+                    path.as_ref().display(),
                     e
                 )
             })?;
@@ -72,16 +109,20 @@ pub fn init(chain_registry: &mut chain::Registry, configs: &[SoftsignConfig]) ->
                 format_err!(
                     ConfigError,
                     "malformed 'raw' softsign key: {}",
-                    config.path.as_ref().display(),
+                    path.as_ref().display(),
                 )
             })?
         }
         KeyFormat::Json => {
-            let private_key = PrivValidatorKey::load_json_file(&config.path)
-                .unwrap_or_else(|e| {
-                    status_err!("couldn't load {}: {}", config.path.as_ref().display(), e);
-                    process::exit(1);
-                })
+            let private_key = PrivValidatorKey::load_json_file(path)
+                .map_err(|e| {
+                    format_err!(
+                        ConfigError,
+                        "couldn't load {}: {}",
+                        path.as_ref().display(),
+                        e
+                    )
+                })?
                 .priv_key;
 
             match private_key {
@@ -91,23 +132,35 @@ pub fn init(chain_registry: &mut chain::Registry, configs: &[SoftsignConfig]) ->
                 }
             }
         }
-    };
+        KeyFormat::Encrypted => {
+            let encrypted_key = EncryptedKey::read(path.as_ref())?;
 
-    let provider = Ed25519Signer::from(&seed);
-    let public_key = provider.public_key().map_err(|_| Error::from(InvalidKey))?;
+            match password {
+                Some(password) => encrypted_key.decrypt(password.expose_secret()),
+                None => encrypted_key.decrypt(&prompt_for_password(path)?),
+            }?
+        }
+    };
 
-    // TODO(tarcieri): support for adding account keys into keyrings
-    let consensus_pubkey = TendermintKey::ConsensusKey(public_key.into());
+    Ok(seed)
+}
 
-    let signer = Signer::new(
-        SigningProvider::SoftSign,
-        consensus_pubkey,
-        Box::new(provider),
+/// Prompt for the password for the encrypted key at the given path
+fn prompt_for_password(path: &SoftPrivateKey) -> Result<Password, Error> {
+    let prompt = format!(
+        "Enter password for softsign key {}: ",
+        path.as_ref().display()
     );
 
-    for chain_id in &config.chain_ids {
-        chain_registry.add_to_keyring(chain_id, signer.clone())?;
-    }
-
-    Ok(())
+    rpassword::read_password_from_tty(Some(&prompt))
+        .map(Password::from)
+        .map_err(|e| {
+            format_err!(
+                ConfigError,
+                "no `password` configured for {} and couldn't prompt for it: {}",
+                path.as_ref().display(),
+                e
+            )
+            .into()
+        })
 }
@@ -0,0 +1,207 @@
+//! Password-encrypted softsign keys.
+//!
+//! The key is encrypted with ChaCha20Poly1305 under a key derived from the
+//! password with PBKDF2-HMAC-SHA256, and stored as JSON along with the
+//! parameters needed to decrypt it.
+
+use crate::{
+    config::password::Password,
+    error::{Error, ErrorKind::*},
+    prelude::*,
+};
+use chacha20poly1305::{
+    aead::{generic_array::GenericArray, Aead, NewAead},
+    ChaCha20Poly1305,
+};
+use hmac::Hmac;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use signatory::ed25519;
+use std::{fs, io::Write, os::unix::fs::OpenOptionsExt, path::Path};
+use subtle_encoding::base64;
+use zeroize::Zeroizing;
+
+/// Default number of PBKDF2 iterations for newly encrypted keys
+pub const DEFAULT_ITERATIONS: u32 = 100_000;
+
+/// Size of the PBKDF2 salt
+const SALT_SIZE: usize = 16;
+
+/// Size of the ChaCha20Poly1305 key
+const KEY_SIZE: usize = 32;
+
+/// Size of the ChaCha20Poly1305 nonce
+const NONCE_SIZE: usize = 12;
+
+/// Password-encrypted Ed25519 seed
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct EncryptedKey {
+    /// Key derivation function
+    kdf: Kdf,
+
+    /// Number of KDF iterations
+    iterations: u32,
+
+    /// KDF salt (Base64)
+    salt: String,
+
+    /// Encryption algorithm
+    cipher: Cipher,
+
+    /// Nonce (Base64)
+    nonce: String,
+
+    /// Encrypted seed (Base64)
+    ciphertext: String,
+}
+
+/// Key derivation functions
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+enum Kdf {
+    /// PBKDF2-HMAC-SHA256
+    #[serde(rename = "pbkdf2-sha256")]
+    Pbkdf2Sha256,
+}
+
+/// Encryption algorithms
+#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+enum Cipher {
+    /// ChaCha20Poly1305
+    #[serde(rename = "chacha20poly1305")]
+    ChaCha20Poly1305,
+}
+
+impl EncryptedKey {
+    /// Encrypt the given seed with a password, using the given number of
+    /// PBKDF2 iterations
+    pub fn encrypt(
+        seed: &ed25519::Seed,
+        password: &Password,
+        iterations: u32,
+    ) -> Result<Self, Error> {
+        let mut salt = [0u8; SALT_SIZE];
+        let mut nonce = [0u8; NONCE_SIZE];
+        getrandom::getrandom(&mut salt).map_err(|e| format_err!(CryptoError, "{}", e))?;
+        getrandom::getrandom(&mut nonce).map_err(|e| format_err!(CryptoError, "{}", e))?;
+
+        let key = derive_key(password, &salt, iterations);
+
+        let ciphertext = ChaCha20Poly1305::new(GenericArray::clone_from_slice(key.as_ref()))
+            .encrypt(GenericArray::from_slice(&nonce), seed.as_secret_slice())
+            .map_err(|_| format_err!(CryptoError, "couldn't encrypt key"))?;
+
+        Ok(Self {
+            kdf: Kdf::Pbkdf2Sha256,
+            iterations,
+            salt: encode(&salt),
+            cipher: Cipher::ChaCha20Poly1305,
+            nonce: encode(&nonce),
+            ciphertext: encode(&ciphertext),
+        })
+    }
+
+    /// Decrypt the seed with the given password
+    pub fn decrypt(&self, password: &Password) -> Result<ed25519::Seed, Error> {
+        let salt = decode(&self.salt, "salt")?;
+        let nonce = decode(&self.nonce, "nonce")?;
+        let ciphertext = decode(&self.ciphertext, "ciphertext")?;
+
+        if nonce.len() != NONCE_SIZE {
+            fail!(InvalidKey, "malformed encrypted key: bad nonce length");
+        }
+
+        let key = derive_key(password, &salt, self.iterations);
+
+        let seed_bytes = Zeroizing::new(
+            ChaCha20Poly1305::new(GenericArray::clone_from_slice(key.as_ref()))
+                .decrypt(GenericArray::from_slice(&nonce), ciphertext.as_slice())
+                .map_err(|_| format_err!(CryptoError, "couldn't decrypt key (wrong password?)"))?,
+        );
+
+        ed25519::Seed::from_bytes(seed_bytes.as_slice())
+            .ok_or_else(|| format_err!(InvalidKey, "malformed encrypted key: bad seed").into())
+    }
+
+    /// Read an encrypted key from the given file
+    pub fn read(path: &Path) -> Result<Self, Error> {
+        let json = fs::read_to_string(path).map_err(|e| {
+            format_err!(
+                ConfigError,
+                "couldn't read key from {}: {}",
+                path.display(),
+                e
+            )
+        })?;
+
+        serde_json::from_str(&json).map_err(|e| {
+            format_err!(
+                ConfigError,
+                "malformed encrypted key in {}: {}",
+                path.display(),
+                e
+            )
+            .into()
+        })
+    }
+
+    /// Write this encrypted key to the given file (readable only by the
+    /// current user)
+    pub fn write(&self, path: &Path) -> Result<(), Error> {
+        let mut json = serde_json::to_string_pretty(self)?;
+        json.push('\n');
+
+        fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(path)
+            .and_then(|mut file| file.write_all(json.as_bytes()))
+            .map_err(|e| format_err!(IoError, "couldn't write {}: {}", path.display(), e))?;
+
+        Ok(())
+    }
+}
+
+/// Derive an encryption key from a password
+fn derive_key(password: &Password, salt: &[u8], iterations: u32) -> Zeroizing<[u8; KEY_SIZE]> {
+    let mut key = Zeroizing::new([0u8; KEY_SIZE]);
+    pbkdf2::pbkdf2::<Hmac<Sha256>>(password.as_bytes(), salt, iterations as usize, key.as_mut());
+    key
+}
+
+/// Encode bytes as Base64
+fn encode(bytes: &[u8]) -> String {
+    String::from_utf8(base64::encode(bytes)).unwrap()
+}
+
+/// Decode the named Base64 field
+fn decode(field: &str, name: &str) -> Result<Vec<u8>, Error> {
+    base64::decode(field)
+        .map_err(|_| format_err!(InvalidKey, "malformed encrypted key: bad {}", name).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Use fewer iterations to keep tests fast
+    const TEST_ITERATIONS: u32 = 1000;
+
+    #[test]
+    fn encrypt_and_decrypt() {
+        let seed = ed25519::Seed::generate();
+        let password = Password::from("correct horse battery staple".to_owned());
+        let encrypted_key = EncryptedKey::encrypt(&seed, &password, TEST_ITERATIONS).unwrap();
+
+        let json = serde_json::to_string(&encrypted_key).unwrap();
+        let encrypted_key: EncryptedKey = serde_json::from_str(&json).unwrap();
+
+        let decrypted_seed = encrypted_key.decrypt(&password).unwrap();
+        assert_eq!(seed.as_secret_slice(), decrypted_seed.as_secret_slice());
+
+        let wrong_password = Password::from("hunter2".to_owned());
+        assert!(encrypted_key.decrypt(&wrong_password).is_err());
+    }
+}
@@ -0,0 +1,107 @@
+//! Softsign keys derived from 24-word BIP39 mnemonic phrases.
+//!
+//! Keys are derived from the phrase's entropy using `hkd32` (the same scheme
+//! `tmkms yubihsm setup` uses), so the same phrase and derivation path always
+//! produce the same key.
+
+use crate::{
+    error::{Error, ErrorKind::*},
+    prelude::*,
+};
+use hkd32::{mnemonic, KeyMaterial};
+use signatory::ed25519;
+
+/// Language of mnemonic phrases
+pub const LANGUAGE: mnemonic::Language = mnemonic::Language::English;
+
+/// Derivation path used when none is given
+pub const DEFAULT_DERIVATION_PATH: &str = "/tmkms/softsign";
+
+/// Generate a random 24-word mnemonic phrase
+pub fn generate_phrase() -> mnemonic::Phrase {
+    mnemonic::Phrase::random(LANGUAGE)
+}
+
+/// Parse (and checksum) a mnemonic phrase, ignoring extra whitespace
+pub fn parse_phrase(phrase: &str) -> Result<mnemonic::Phrase, Error> {
+    let words = phrase.split_whitespace().collect::<Vec<_>>();
+
+    mnemonic::Phrase::new(words.join(" "), LANGUAGE).map_err(|_| {
+        format_err!(
+            InvalidKey,
+            "invalid mnemonic phrase (expected 24 words with a valid checksum)"
+        )
+        .into()
+    })
+}
+
+/// Derive an Ed25519 seed from the given mnemonic phrase and derivation path
+/// (e.g. `/tmkms/softsign/cosmoshub-3`)
+pub fn derive_seed(phrase: &mnemonic::Phrase, path: &str) -> Result<ed25519::Seed, Error> {
+    let path = path
+        .parse::<hkd32::PathBuf>()
+        .map_err(|_| format_err!(ConfigError, "invalid derivation path: {}", path))?;
+
+    if path.is_root() {
+        fail!(ConfigError, "cannot derive keys for the root path");
+    }
+
+    let key_material = KeyMaterial::from_bytes(phrase.entropy())
+        .map_err(|_| format_err!(InvalidKey, "invalid mnemonic phrase entropy"))?
+        .derive_subkey(path);
+
+    Ok(ed25519::Seed::from_bytes(key_material.as_bytes()).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use signatory::public_key::PublicKeyed;
+    use signatory_dalek::Ed25519Signer;
+    use subtle_encoding::hex;
+
+    /// Test vectors: phrase, derivation path, seed, and public key
+    const TEST_VECTORS: &[(&str, &str, &str, &str)] = &[
+        (
+            "abandon abandon abandon abandon abandon abandon abandon abandon \
+             abandon abandon abandon abandon abandon abandon abandon abandon \
+             abandon abandon abandon abandon abandon abandon abandon art",
+            "/tmkms/softsign",
+            "efce63b9ec83b40e7a3cb47b2f8fb783adcb544041852b1fdabcbb5ef787378d",
+            "5781132a728e5d522f13c9bd2552036ee3abd80c2e98440ea3f55a37834c913d",
+        ),
+        (
+            "legal winner thank year wave sausage worth useful legal winner \
+             thank year wave sausage worth useful legal winner thank year \
+             wave sausage worth title",
+            "/tmkms/softsign/cosmoshub-3",
+            "abde1a6b216c0ae79ff23c3a2771cd5d07d42cb9fc951a2273932f27afb13430",
+            "f7fef8dcf376a6e6bd08a3508bb2f62512c56f3c5b5dfee37b4483c6472415d5",
+        ),
+    ];
+
+    #[test]
+    fn derives_test_vectors() {
+        for &(phrase, path, seed_hex, public_key_hex) in TEST_VECTORS {
+            let seed = derive_seed(&parse_phrase(phrase).unwrap(), path).unwrap();
+            assert_eq!(seed.as_secret_slice(), &hex::decode(seed_hex).unwrap()[..]);
+
+            let public_key = Ed25519Signer::from(&seed).public_key().unwrap();
+            assert_eq!(
+                public_key.as_bytes(),
+                &hex::decode(public_key_hex).unwrap()[..]
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_phrases_and_paths() {
+        let (phrase, _, _, _) = TEST_VECTORS[0];
+        assert!(parse_phrase(&phrase.replace(" art", " abandon")).is_err());
+
+        let phrase = parse_phrase(phrase).unwrap();
+        assert!(derive_seed(&phrase, "/").is_err());
+        assert!(derive_seed(&phrase, "tmkms/softsign").is_err());
+        assert!(derive_seed(&phrase, "/tmkms//softsign").is_err());
+    }
+}
@@ -6,8 +6,9 @@ use crate::{
     error::{Error, ErrorKind::*},
     keyring::{ed25519::Signer, SigningProvider},
     prelude::*,
+    status,
 };
-use signatory::public_key::PublicKeyed;
+use signatory::{ed25519, public_key::PublicKeyed, signature};
 use tendermint::TendermintKey;
 
 /// Create hardware-backed YubiHSM signer objects from the given configuration
@@ -28,20 +29,17 @@ pub fn init(
         );
     }
 
+    // Open the signing sessions now rather than on the first signature
+    let session_pool = crate::yubihsm::session_pool();
+
     for config in &yubihsm_configs[0].keys {
-        let signer = yubihsm::ed25519::Signer::create(crate::yubihsm::client().clone(), config.key)
-            .map_err(|_| {
-                format_err!(
-                    InvalidKey,
-                    "YubiHSM key ID 0x{:04x} is not a valid Ed25519 signing key",
-                    config.key
-                )
-            })?;
+        let signer = signer(config.key)?;
 
         let public_key = signer.public_key().map_err(|_| {
             format_err!(
                 InvalidKey,
-                "couldn't get public key for YubiHSM key ID 0x{:04x}"
+                "couldn't get public key for YubiHSM key ID 0x{:04x}",
+                config.key
             )
         })?;
 
@@ -55,5 +53,63 @@ pub fn init(
         }
     }
 
+    status::register_provider("yubihsm", move || {
+        crate::yubihsm::client()
+            .device_info()
+            .map_err(|e| format_err!(YubihsmError, "{}", e))?;
+
+        session_pool.health()
+    });
+
+    status::register_metrics("yubihsm_session_pool", move || {
+        serde_json::to_value(session_pool.metrics()).unwrap()
+    });
+
     Ok(())
 }
+
+/// Create a signer for the Ed25519 key with the given ID in the YubiHSM
+pub fn signer(key_id: u16) -> Result<PooledSigner, Error> {
+    let public_key = crate::yubihsm::client()
+        .get_public_key(key_id)
+        .ok()
+        .and_then(|public_key| public_key.ed25519())
+        .ok_or_else(|| {
+            format_err!(
+                InvalidKey,
+                "YubiHSM key ID 0x{:04x} is not a valid Ed25519 signing key",
+                key_id
+            )
+        })?;
+
+    Ok(PooledSigner { key_id, public_key })
+}
+
+/// Ed25519 signer which signs using a session from the YubiHSM session pool
+pub struct PooledSigner {
+    /// ID of the signing key
+    key_id: u16,
+
+    /// Public key of the signing key
+    public_key: ed25519::PublicKey,
+}
+
+impl PublicKeyed<ed25519::PublicKey> for PooledSigner {
+    fn public_key(&self) -> Result<ed25519::PublicKey, signature::Error> {
+        Ok(self.public_key)
+    }
+}
+
+impl signature::Signer<ed25519::Signature> for PooledSigner {
+    fn try_sign(&self, msg: &[u8]) -> Result<ed25519::Signature, signature::Error> {
+        let client = crate::yubihsm::session_pool().acquire().map_err(|e| {
+            warn!(
+                "couldn't sign with YubiHSM key 0x{:04x}: {}",
+                self.key_id, e
+            );
+            signature::Error::from_source(e)
+        })?;
+
+        Ok(client.sign_ed25519(self.key_id, msg)?)
+    }
+}
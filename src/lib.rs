@@ -10,6 +10,7 @@ compile_error!(
      yubihsm, ledgertm, softsign (e.g. --features=yubihsm)"
 );
 
+pub mod alert;
 pub mod application;
 pub mod chain;
 pub mod client;
@@ -18,9 +19,11 @@ pub mod config;
 pub mod connection;
 pub mod error;
 pub mod keyring;
+pub mod logging;
 pub mod prelude;
 pub mod rpc;
 pub mod session;
+pub mod status;
 
 #[cfg(feature = "yubihsm")]
 pub mod yubihsm;
@@ -0,0 +1,230 @@
+//! Log output formats.
+//!
+//! The default `text` format is abscissa's standard human-readable output.
+//! The `json` format emits one JSON object per line containing the event's
+//! timestamp, level, target, message, and any structured fields attached to
+//! it (e.g. `chain_id`, `height`, `latency_ms`), for ingestion by log
+//! aggregation systems.
+
+use crate::{commands::KmsCommand, config::interpolate::interpolate};
+use abscissa_core::{Component, Configurable, FrameworkError, FrameworkErrorKind};
+use chrono::{SecondsFormat, Utc};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::fmt::{self, Debug};
+use tracing::{
+    field::{Field, Visit},
+    Event,
+};
+use tracing_log::{LogTracer, NormalizeEvent};
+use tracing_subscriber::{
+    fmt::{Context, FormatEvent},
+    FmtSubscriber,
+};
+
+/// Log output formats
+#[derive(Copy, Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+pub enum LogFormat {
+    /// Human-readable text (default)
+    #[default]
+    #[serde(rename = "text")]
+    Text,
+
+    /// One JSON object per line
+    #[serde(rename = "json")]
+    Json,
+}
+
+impl LogFormat {
+    /// Determine the log format for the given command.
+    ///
+    /// Components (including the tracing subscriber) are registered before
+    /// the configuration is loaded, so this reads just the `log_format` key
+    /// from the config file, falling back to the text format on errors (which
+    /// will be reported when the config is actually loaded).
+    pub fn for_command(command: &KmsCommand) -> Self {
+        command
+            .config_path()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|toml_string| toml::from_str::<toml::Value>(&toml_string).ok())
+            .and_then(|config| config.get("log_format").cloned())
+            .and_then(|mut log_format| {
+                interpolate(&mut log_format).ok()?;
+                log_format.try_into().ok()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// Abscissa component which installs a `tracing` subscriber emitting
+/// JSON-formatted events
+#[derive(Component, Debug)]
+pub struct JsonTracing {}
+
+impl JsonTracing {
+    /// Install the JSON tracing subscriber with the given filter
+    pub fn new(filter: &str) -> Result<Self, FrameworkError> {
+        // Route events emitted through the `log` crate to `tracing`
+        LogTracer::init().map_err(|e| FrameworkErrorKind::ComponentError.context(e))?;
+
+        let subscriber = FmtSubscriber::builder()
+            .with_env_filter(filter)
+            .on_event(JsonFormat)
+            .finish();
+
+        tracing::subscriber::set_global_default(subscriber)
+            .map_err(|e| FrameworkErrorKind::ComponentError.context(e))?;
+
+        Ok(Self {})
+    }
+}
+
+/// Formats `tracing` events as single-line JSON objects
+#[derive(Copy, Clone, Debug, Default)]
+pub struct JsonFormat;
+
+impl<N> FormatEvent<N> for JsonFormat {
+    fn format_event(
+        &self,
+        _ctx: &Context<'_, N>,
+        writer: &mut dyn fmt::Write,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let normalized_metadata = event.normalized_metadata();
+        let metadata = normalized_metadata
+            .as_ref()
+            .unwrap_or_else(|| event.metadata());
+
+        let mut fields = JsonVisitor::default();
+        event.record(&mut fields);
+
+        let mut object = Map::new();
+        object.insert(
+            "timestamp".to_owned(),
+            Utc::now()
+                .to_rfc3339_opts(SecondsFormat::Millis, true)
+                .into(),
+        );
+        object.insert("level".to_owned(), metadata.level().to_string().into());
+        object.insert("target".to_owned(), metadata.target().into());
+
+        if let Some(message) = fields.message {
+            object.insert("message".to_owned(), message.into());
+        }
+
+        object.insert("fields".to_owned(), Value::Object(fields.fields));
+
+        let json = serde_json::to_string(&object).map_err(|_| fmt::Error)?;
+        writeln!(writer, "{}", json)
+    }
+}
+
+/// Collects the fields of an event into a JSON map
+#[derive(Default)]
+struct JsonVisitor {
+    /// The event's message, if it has one
+    message: Option<String>,
+
+    /// Structured fields attached to the event
+    fields: Map<String, Value>,
+}
+
+impl JsonVisitor {
+    /// Record a field value
+    fn insert(&mut self, field: &Field, value: Value) {
+        let name = field.name();
+
+        // Skip metadata fields added by `tracing-log` for `log` crate events
+        if name.starts_with("log.") {
+            return;
+        }
+
+        if name == "message" {
+            self.message = Some(match value {
+                Value::String(s) => s,
+                other => other.to_string(),
+            });
+        } else {
+            self.fields.insert(name.to_owned(), value);
+        }
+    }
+}
+
+impl Visit for JsonVisitor {
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.insert(field, value.into());
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.insert(field, value.into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.insert(field, format!("{:?}", value).into());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        io,
+        sync::{Arc, Mutex},
+    };
+
+    /// Buffer shared between the test and the subscriber's writer
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_json_format() {
+        let buffer = Buffer::default();
+        let writer = buffer.clone();
+
+        let subscriber = FmtSubscriber::builder()
+            .with_writer(move || writer.clone())
+            .on_event(JsonFormat)
+            .finish();
+
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(
+                chain_id = "cosmoshub-3",
+                height = 42i64,
+                latency_ms = 3u64,
+                block_id = %"ABCDEF",
+                "signed {}",
+                "Proposal"
+            );
+        });
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        assert_eq!(output.lines().count(), 1);
+
+        let event: Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(event["level"], "INFO");
+        assert_eq!(event["message"], "signed Proposal");
+        assert_eq!(event["fields"]["chain_id"], "cosmoshub-3");
+        assert_eq!(event["fields"]["height"], 42);
+        assert_eq!(event["fields"]["latency_ms"], 3);
+        assert_eq!(event["fields"]["block_id"], "ABCDEF");
+        assert!(event["timestamp"].is_string());
+    }
+}
@@ -1,20 +1,26 @@
 //! A session with a validator node
 
 use crate::{
+    alert::{self, EventKind},
     chain::{self, state::StateErrorKind},
     config::ValidatorConfig,
-    connection::{tcp, unix::UnixConnection, Connection},
+    connection::{
+        tcp,
+        unix::{self, UnixConnection},
+        Connection,
+    },
     error::{Error, ErrorKind::*},
     prelude::*,
     rpc::{Request, Response, TendermintRequest},
+    status,
 };
 use prost_amino::Message;
-use std::{fmt::Debug, os::unix::net::UnixStream, time::Instant};
+use std::{fmt::Debug, time::Instant};
 use tendermint::{
     amino_types::{
         PingRequest, PingResponse, PubKeyRequest, PubKeyResponse, RemoteError, SignedMsgType,
     },
-    consensus, net,
+    consensus, net, node,
 };
 
 /// Encrypted session with a validator node
@@ -24,64 +30,101 @@ pub struct Session {
 
     /// TCP connection to a validator node
     connection: Box<dyn Connection>,
+
+    /// Node ID of the validator (if the connection is authenticated)
+    peer_id: Option<node::Id>,
 }
 
 impl Session {
     /// Open a session using the given validator configuration
     #[allow(clippy::cognitive_complexity)] // TODO(tarcieri): needs refactoring
     pub fn open(config: ValidatorConfig) -> Result<Self, Error> {
-        let connection: Box<dyn Connection> = match &config.addr {
+        let (connection, peer_id): (Box<dyn Connection>, _) = match &config.addr {
             net::Address::Tcp {
                 peer_id,
                 host,
                 port,
             } => {
+                if config.peer_credentials.is_some() || config.secret_connection.is_some() {
+                    fail!(
+                        ConfigError,
+                        "{}: `peer_credentials` and `secret_connection` only apply to \
+                         `unix://` validators",
+                        &config.addr
+                    );
+                }
+
                 debug!("{}: Connecting to {}...", &config.chain_id, &config.addr);
 
-                let seed = config.load_secret_key()?;
-                let conn =
-                    tcp::open_secret_connection(host, *port, &seed, peer_id, config.timeout)?;
+                let (public_key, signer) = config.load_secret_key()?;
+                let conn = tcp::open_secret_connection(
+                    host,
+                    *port,
+                    &public_key,
+                    signer.as_ref(),
+                    peer_id,
+                    config.timeout,
+                )?;
 
-                info!(
-                    "[{}@{}] connected to validator successfully",
-                    &config.chain_id, &config.addr
-                );
+                let remote_peer_id = conn.remote_pubkey().peer_id();
 
                 if peer_id.is_none() {
                     // TODO(tarcieri): make peer verification mandatory
-                    warn!(
-                        "[{}] {}: unverified validator peer ID! ({})",
-                        &config.chain_id,
-                        &config.addr,
-                        conn.remote_pubkey().peer_id()
-                    );
+                    warn_unverified_peer(&config, &remote_peer_id);
                 }
 
-                Box::new(conn)
+                (Box::new(conn), Some(remote_peer_id))
             }
             net::Address::Unix { path } => {
-                if let Some(timeout) = config.timeout {
-                    warn!("timeouts not supported with Unix sockets: {}", timeout);
-                }
-
                 debug!(
                     "{}: Connecting to socket at {}...",
                     &config.chain_id, &config.addr
                 );
 
-                let socket = UnixStream::connect(path)?;
-                let conn = UnixConnection::new(socket);
-
-                info!(
-                    "[{}@{}] connected to validator successfully",
-                    &config.chain_id, &config.addr
-                );
-
-                Box::new(conn)
+                let socket = unix::connect(path, config.timeout, config.peer_credentials.as_ref())?;
+
+                match &config.secret_connection {
+                    Some(secret_connection) => {
+                        let (public_key, signer) = config.load_secret_key()?;
+                        let peer_id = &secret_connection.peer_id;
+                        let conn = unix::open_secret_connection(
+                            socket,
+                            path,
+                            &public_key,
+                            signer.as_ref(),
+                            peer_id,
+                            config.timeout,
+                        )?;
+
+                        let remote_peer_id = conn.remote_pubkey().peer_id();
+
+                        if peer_id.is_none() {
+                            warn_unverified_peer(&config, &remote_peer_id);
+                        }
+
+                        (Box::new(conn), Some(remote_peer_id))
+                    }
+                    None => (Box::new(UnixConnection::new(socket)), None),
+                }
             }
         };
 
-        Ok(Self { config, connection })
+        info!(
+            chain_id = %config.chain_id,
+            addr = %config.addr,
+            peer_id = %display_peer_id(&peer_id),
+            "[{}@{}] connected to validator successfully",
+            &config.chain_id,
+            &config.addr
+        );
+
+        status::session_connected(&config, peer_id);
+
+        Ok(Self {
+            config,
+            connection,
+            peer_id,
+        })
     }
 
     /// Main request loop
@@ -93,6 +136,8 @@ impl Session {
     /// Handle an incoming request from the validator
     fn handle_request(&mut self) -> Result<bool, Error> {
         let request = Request::read(&mut self.connection)?;
+        status::session_request(&self.config);
+
         debug!(
             "[{}:{}] received request: {:?}",
             &self.config.chain_id, &self.config.addr, &request
@@ -141,27 +186,44 @@ impl Session {
         let (_, request_state) = parse_request(&request)?;
         let mut chain_state = chain.state.lock().unwrap();
 
-        if let Err(e) = chain_state.update_consensus_state(request_state) {
-            // Report double signing error back to the validator
-            if e.kind() == StateErrorKind::DoubleSign {
-                return self.handle_double_signing(
-                    request,
-                    &chain_state.consensus_state().block_id_prefix(),
-                );
-            } else {
-                return Err(e.into());
+        if let Err(e) = chain_state.update_consensus_state(request_state.clone()) {
+            match e.kind() {
+                // Report double signing error back to the validator
+                StateErrorKind::DoubleSign => {
+                    return self.handle_double_signing(
+                        request,
+                        &chain_state.consensus_state().block_id_prefix(),
+                    );
+                }
+                StateErrorKind::HeightRegression
+                | StateErrorKind::RoundRegression
+                | StateErrorKind::StepRegression => alert::notify(
+                    alert::Event::new(EventKind::Regression, &self.config, &e)
+                        .with_consensus_state(request_state),
+                ),
+                StateErrorKind::SyncError => (),
             }
+
+            return Err(e.into());
         }
 
         if let Some(max_height) = self.config.max_height {
             if let Some(height) = request.height() {
                 if height > max_height.value() as i64 {
-                    fail!(
+                    let e: Error = format_err!(
                         ExceedMaxHeight,
                         "attempted to sign at height {} which is greater than {}",
                         height,
                         max_height,
+                    )
+                    .into();
+
+                    alert::notify(
+                        alert::Event::new(EventKind::ExceedMaxHeight, &self.config, &e)
+                            .with_consensus_state(request_state),
                     );
+
+                    return Err(e);
                 }
             }
         }
@@ -171,7 +233,16 @@ impl Session {
 
         // TODO(ismail): figure out which key to use here instead of taking the only key
         let started_at = Instant::now();
-        let signature = chain.keyring.sign_ed25519(None, &to_sign)?;
+        let signature = match chain.keyring.sign_ed25519(None, &to_sign) {
+            Ok(signature) => signature,
+            Err(e) => {
+                alert::notify(
+                    alert::Event::new(EventKind::ProviderFailure, &self.config, &e)
+                        .with_consensus_state(request_state),
+                );
+                return Err(e);
+            }
+        };
 
         self.log_signing_request(&request, started_at).unwrap();
 
@@ -204,15 +275,25 @@ impl Session {
         R: TendermintRequest + Debug,
     {
         let (msg_type, request_state) = parse_request(request)?;
+        let latency_ms = started_at.elapsed().as_millis() as u64;
 
         info!(
+            chain_id = %self.config.chain_id,
+            addr = %self.config.addr,
+            peer_id = %display_peer_id(&self.peer_id),
+            msg_type = ?msg_type,
+            height = request_state.height.value(),
+            round = request_state.round,
+            step = request_state.step,
+            block_id = %display_block_id(&request_state),
+            latency_ms,
             "[{}@{}] signed {:?}:{} at h/r/s {} ({} ms)",
             &self.config.chain_id,
             &self.config.addr,
             msg_type,
             request_state.block_id_prefix(),
             request_state,
-            started_at.elapsed().as_millis(),
+            latency_ms,
         );
 
         Ok(())
@@ -230,6 +311,15 @@ impl Session {
         let (msg_type, request_state) = parse_request(&request)?;
 
         error!(
+            chain_id = %self.config.chain_id,
+            addr = %self.config.addr,
+            peer_id = %display_peer_id(&self.peer_id),
+            msg_type = ?msg_type,
+            height = request_state.height.value(),
+            round = request_state.round,
+            step = request_state.step,
+            block_id = %display_block_id(&request_state),
+            error_kind = ?DoubleSign,
             "[{}:{}] attempted double sign {:?} at h/r/s: {} ({} != {})",
             &self.config.chain_id,
             &self.config.addr,
@@ -239,11 +329,57 @@ impl Session {
             request_state.block_id_prefix()
         );
 
+        let message = format!(
+            "attempted double sign {:?} at h/r/s: {} ({} != {})",
+            msg_type,
+            request_state,
+            original_block_id,
+            request_state.block_id_prefix()
+        );
+
+        alert::notify(
+            alert::Event::new(EventKind::DoubleSign, &self.config, message)
+                .with_consensus_state(request_state),
+        );
+
         let remote_err = RemoteError::double_sign(request.height().unwrap());
         Ok(request.build_response(Some(remote_err)))
     }
 }
 
+impl Drop for Session {
+    fn drop(&mut self) {
+        status::session_disconnected(&self.config);
+    }
+}
+
+/// Log a warning about connecting to a validator without verifying its peer ID
+fn warn_unverified_peer(config: &ValidatorConfig, peer_id: &node::Id) {
+    warn!(
+        chain_id = %config.chain_id,
+        addr = %config.addr,
+        peer_id = %peer_id,
+        "[{}] {}: unverified validator peer ID! ({})",
+        &config.chain_id,
+        &config.addr,
+        peer_id
+    );
+}
+
+/// Format an optional peer ID for logging
+fn display_peer_id(peer_id: &Option<node::Id>) -> String {
+    peer_id.map(|id| id.to_string()).unwrap_or_default()
+}
+
+/// Format the (full) block ID of a consensus state for logging
+fn display_block_id(state: &consensus::State) -> String {
+    state
+        .block_id
+        .as_ref()
+        .map(ToString::to_string)
+        .unwrap_or_default()
+}
+
 /// Parse the consensus state from an incoming request
 // TODO(tarcieri): fix the upstream Amino parser to do this correctly for us
 fn parse_request<R>(request: &R) -> Result<(SignedMsgType, consensus::State), Error>
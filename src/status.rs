@@ -0,0 +1,227 @@
+//! Health and status reporting (served via an optional HTTP endpoint)
+
+mod server;
+
+pub use self::server::spawn;
+use crate::{chain, config::ValidatorConfig, error::Error};
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use std::{collections::BTreeMap, sync::Mutex, time::Duration};
+use tendermint::{consensus, node};
+
+/// Status of validator sessions, keyed by `chain_id@addr`
+static SESSIONS: Lazy<Mutex<BTreeMap<String, SessionStatus>>> = Lazy::new(Default::default);
+
+/// Health checks for signing providers
+static PROVIDER_CHECKS: Lazy<Mutex<Vec<ProviderCheck>>> = Lazy::new(Default::default);
+
+/// Metrics reported by signing providers, keyed by name
+static METRICS: Lazy<Mutex<BTreeMap<String, MetricsSource>>> = Lazy::new(Default::default);
+
+/// Function which checks if a signing provider is healthy
+type HealthCheck = Box<dyn Fn() -> Result<(), Error> + Send + Sync>;
+
+/// Function which returns a signing provider's current metrics
+type MetricsSource = Box<dyn Fn() -> serde_json::Value + Send + Sync>;
+
+/// Registered health check for a signing provider
+struct ProviderCheck {
+    /// Name of the provider
+    name: String,
+
+    /// Health check function
+    check: HealthCheck,
+}
+
+/// Status of the KMS
+#[derive(Clone, Debug, Serialize)]
+pub struct Status {
+    /// Are all signing providers healthy?
+    pub ready: bool,
+
+    /// Status of each registered chain
+    pub chains: Vec<ChainStatus>,
+
+    /// Status of each validator session
+    pub sessions: Vec<SessionStatus>,
+
+    /// Health of each signing provider
+    pub providers: Vec<ProviderStatus>,
+
+    /// Metrics reported by signing providers
+    pub metrics: BTreeMap<String, serde_json::Value>,
+}
+
+/// Status of a chain
+#[derive(Clone, Debug, Serialize)]
+pub struct ChainStatus {
+    /// Chain ID
+    pub id: chain::Id,
+
+    /// Height, round, step and block ID we last signed at
+    pub last_signed: consensus::State,
+}
+
+/// Status of a session with a validator
+#[derive(Clone, Debug, Serialize)]
+pub struct SessionStatus {
+    /// Chain ID of the validator
+    pub chain_id: chain::Id,
+
+    /// Address of the validator
+    pub addr: String,
+
+    /// Are we currently connected to the validator?
+    pub connected: bool,
+
+    /// Node ID of the validator (if connected via SecretConnection)
+    pub peer_id: Option<node::Id>,
+
+    /// When we were last disconnected (or registered), if not connected
+    pub disconnected_since: Option<DateTime<Utc>>,
+
+    /// When we last received a request from the validator
+    pub last_request: Option<DateTime<Utc>>,
+}
+
+/// Health of a signing provider
+#[derive(Clone, Debug, Serialize)]
+pub struct ProviderStatus {
+    /// Name of the provider
+    pub name: String,
+
+    /// Did the provider's health check succeed?
+    pub healthy: bool,
+
+    /// Error from the health check (if any)
+    pub error: Option<String>,
+}
+
+/// Register a validator session which has not (yet) connected
+pub fn register_session(config: &ValidatorConfig) {
+    update_session(config, |_| ());
+}
+
+/// Record that we have connected to a validator
+pub fn session_connected(config: &ValidatorConfig, peer_id: Option<node::Id>) {
+    update_session(config, |session| {
+        session.connected = true;
+        session.peer_id = peer_id;
+        session.disconnected_since = None;
+    });
+}
+
+/// Record that we have received a request from a validator
+pub fn session_request(config: &ValidatorConfig) {
+    update_session(config, |session| session.last_request = Some(Utc::now()));
+}
+
+/// Record that we have disconnected from a validator
+pub fn session_disconnected(config: &ValidatorConfig) {
+    update_session(config, |session| {
+        session.connected = false;
+        session.disconnected_since = Some(Utc::now());
+    });
+}
+
+/// Get how long we have been disconnected from a validator (if we are)
+pub fn session_disconnected_for(config: &ValidatorConfig) -> Option<Duration> {
+    SESSIONS
+        .lock()
+        .unwrap()
+        .get(&session_name(config))
+        .and_then(|session| session.disconnected_since)
+        .and_then(|since| Utc::now().signed_duration_since(since).to_std().ok())
+}
+
+/// Update the status of the session for the given validator, registering it
+/// if it hasn't been seen before
+fn update_session(config: &ValidatorConfig, f: impl FnOnce(&mut SessionStatus)) {
+    // TODO(tarcieri): better handle `PoisonError` here?
+    let mut sessions = SESSIONS.lock().unwrap();
+
+    let session = sessions
+        .entry(session_name(config))
+        .or_insert_with(|| SessionStatus {
+            chain_id: config.chain_id,
+            addr: config.addr.to_string(),
+            connected: false,
+            peer_id: None,
+            disconnected_since: Some(Utc::now()),
+            last_request: None,
+        });
+
+    f(session);
+}
+
+/// Name of the session for the given validator
+fn session_name(config: &ValidatorConfig) -> String {
+    format!("{}@{}", &config.chain_id, &config.addr)
+}
+
+/// Register a health check for a signing provider
+pub fn register_provider<F>(name: &str, check: F)
+where
+    F: Fn() -> Result<(), Error> + Send + Sync + 'static,
+{
+    PROVIDER_CHECKS.lock().unwrap().push(ProviderCheck {
+        name: name.to_owned(),
+        check: Box::new(check),
+    });
+}
+
+/// Register a source of metrics for a signing provider
+pub fn register_metrics<F>(name: &str, metrics: F)
+where
+    F: Fn() -> serde_json::Value + Send + Sync + 'static,
+{
+    METRICS
+        .lock()
+        .unwrap()
+        .insert(name.to_owned(), Box::new(metrics));
+}
+
+/// Get the current status of the KMS, running all provider health checks
+pub fn status() -> Status {
+    let providers = PROVIDER_CHECKS
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|provider| {
+            let error = (provider.check)().err().map(|e| e.to_string());
+
+            ProviderStatus {
+                name: provider.name.clone(),
+                healthy: error.is_none(),
+                error,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let chains = chain::REGISTRY
+        .get()
+        .chains()
+        .map(|chain| ChainStatus {
+            id: chain.id,
+            last_signed: chain.state.lock().unwrap().consensus_state().clone(),
+        })
+        .collect();
+
+    let sessions = SESSIONS.lock().unwrap().values().cloned().collect();
+
+    let metrics = METRICS
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(name, metrics)| (name.clone(), metrics()))
+        .collect();
+
+    Status {
+        ready: providers.iter().all(|provider| provider.healthy),
+        chains,
+        sessions,
+        providers,
+        metrics,
+    }
+}
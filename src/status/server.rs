@@ -0,0 +1,86 @@
+//! HTTP server for the `/livez`, `/readyz` (a.k.a. `/healthz`) and `/status`
+//! endpoints
+
+use super::status;
+use crate::{
+    config::status::StatusConfig,
+    error::{Error, ErrorKind::*},
+    prelude::*,
+};
+use std::{io::Cursor, thread};
+use tendermint::net;
+use tiny_http::{Header, Request, Response, Server};
+
+/// Start the HTTP status server in a background thread
+pub fn spawn(config: &StatusConfig) -> Result<(), Error> {
+    let addr = match &config.laddr {
+        net::Address::Tcp { host, port, .. } => format!("{}:{}", host, port),
+        net::Address::Unix { .. } => fail!(
+            ConfigError,
+            "status endpoint only supports `tcp://` addresses: {}",
+            &config.laddr
+        ),
+    };
+
+    let server = Server::http(&addr)
+        .map_err(|e| format_err!(IoError, "couldn't bind status endpoint to {}: {}", addr, e))?;
+
+    info!("status endpoint listening on http://{}", addr);
+
+    thread::Builder::new()
+        .name("status".to_owned())
+        .spawn(move || {
+            for request in server.incoming_requests() {
+                handle_request(request);
+            }
+        })?;
+
+    Ok(())
+}
+
+/// Respond to a request to the status server
+fn handle_request(request: Request) {
+    // Ignore any query string (e.g. `/healthz?probe=1`)
+    let path = request.url().split('?').next().unwrap();
+
+    let response = match path {
+        "/livez" => Response::from_string("ok\n"),
+        "/readyz" | "/healthz" => readyz(),
+        "/status" => {
+            let body = serde_json::to_string_pretty(&status()).unwrap();
+            let content_type = Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]);
+            Response::from_string(body).with_header(content_type.unwrap())
+        }
+        _ => Response::from_string("not found\n").with_status_code(404),
+    };
+
+    if let Err(e) = request.respond(response) {
+        debug!("error responding to status request: {}", e);
+    }
+}
+
+/// Respond with whether or not the KMS is ready, i.e. all signing providers
+/// passed their health checks.
+///
+/// Unlike `/livez`, which succeeds as long as the KMS is serving requests,
+/// this fails while e.g. an HSM is unreachable, so it shouldn't be used to
+/// decide whether to restart the KMS.
+fn readyz() -> Response<Cursor<Vec<u8>>> {
+    let status = status();
+
+    if status.ready {
+        return Response::from_string("ok\n");
+    }
+
+    let mut body = String::from("not ready\n");
+
+    for provider in status.providers.iter().filter(|p| !p.healthy) {
+        body.push_str(&format!(
+            "{}: {}\n",
+            provider.name,
+            provider.error.clone().unwrap_or_default()
+        ));
+    }
+
+    Response::from_string(body).with_status_code(503)
+}
@@ -1,23 +1,30 @@
 //! Application-local YubiHSM configuration and initialization
 
+pub mod attestation;
+pub mod audit_log;
+pub mod backup;
+pub mod session_pool;
+
+use self::session_pool::SessionPool;
+#[cfg(feature = "yubihsm-server")]
+use crate::config::provider::yubihsm::AuthConfig;
 use crate::{
     config::provider::yubihsm::YubihsmConfig,
     error::{Error, ErrorKind},
     prelude::*,
 };
-use once_cell::sync::Lazy;
+use once_cell::sync::{Lazy, OnceCell};
 #[cfg(all(feature = "yubihsm-server", not(feature = "yubihsm-mock")))]
 use std::thread;
 use std::{
+    fmt::Write,
     process,
     sync::{
         atomic::{self, AtomicBool},
         Mutex, MutexGuard,
     },
 };
-use yubihsm::{Client, Connector};
-#[cfg(feature = "yubihsm-server")]
-use zeroize::Zeroizing;
+use yubihsm::{Client, Connector, Credentials};
 #[cfg(not(feature = "yubihsm-mock"))]
 use {
     crate::config::provider::yubihsm::AdapterConfig,
@@ -33,6 +40,13 @@ static HSM_CONNECTOR: Lazy<Connector> = Lazy::new(init_connector);
 // TODO(tarcieri): refactor with a straightforward `once_cell::sync::OnceCell`
 static HSM_CLIENT: Lazy<Mutex<Client>> = Lazy::new(|| Mutex::new(init_client()));
 
+/// Credentials for the auth key in the global config (read once, so the
+/// password is only prompted for once)
+static CREDENTIALS: OnceCell<Credentials> = OnceCell::new();
+
+/// Pool of authenticated sessions used for signing
+static SESSION_POOL: Lazy<SessionPool> = Lazy::new(init_session_pool);
+
 /// Flag indicating we're inside of a `tmkms yubihsm` command
 // TODO(tarcieri): refactor with a straightforward `once_cell::sync::OnceCell`
 static CLI_COMMAND: AtomicBool = AtomicBool::new(false);
@@ -53,11 +67,23 @@ pub fn connector() -> &'static Connector {
     &HSM_CONNECTOR
 }
 
+/// Get the credentials for the auth key configured in the global config
+pub fn credentials() -> Result<Credentials, Error> {
+    CREDENTIALS
+        .get_or_try_init(|| config().auth.credentials())
+        .cloned()
+}
+
 /// Get an HSM client configured from global settings
 pub fn client() -> MutexGuard<'static, Client> {
     HSM_CLIENT.lock().unwrap()
 }
 
+/// Get the pool of sessions used for signing
+pub fn session_pool() -> &'static SessionPool {
+    &SESSION_POOL
+}
+
 /// Open a session with the YubiHSM2 using settings from the global config
 #[cfg(not(feature = "yubihsm-mock"))]
 fn init_connector() -> Connector {
@@ -141,55 +167,56 @@ fn init_connector() -> Connector {
 
 /// Get a `yubihsm::Client` configured from the global configuration
 fn init_client() -> Client {
-    let (credentials, reconnect) = client_config();
+    client_config()
+        .and_then(|(credentials, reconnect)| {
+            Ok(Client::open(connector().clone(), credentials, reconnect)?)
+        })
+        .unwrap_or_else(|e| {
+            status_err!("error connecting to YubiHSM2: {}", e);
+            process::exit(1);
+        })
+}
 
-    Client::open(connector().clone(), credentials, reconnect).unwrap_or_else(|e| {
-        status_err!("error connecting to YubiHSM2: {}", e);
-        process::exit(1);
-    })
+/// Open the pool of signing sessions using settings from the global config
+fn init_session_pool() -> SessionPool {
+    credentials()
+        .and_then(|credentials| {
+            SessionPool::open(connector(), &credentials, &config().session_pool)
+        })
+        .unwrap_or_else(|e| {
+            status_err!("error opening YubiHSM2 session pool: {}", e);
+            process::exit(1);
+        })
 }
 
 /// Get client configuration settings
 #[cfg(not(feature = "yubihsm-server"))]
-fn client_config() -> (yubihsm::Credentials, bool) {
-    (config().auth.credentials(), true)
+fn client_config() -> Result<(Credentials, bool), Error> {
+    Ok((credentials()?, true))
 }
 
 /// Get client configuration settings, accounting for `yubihsm-server` server
 /// overrides (i.e. local loopback for `tmkms yubihsm` commands)
 #[cfg(feature = "yubihsm-server")]
-fn client_config() -> (yubihsm::Credentials, bool) {
-    let cfg = config();
-
-    cfg.connector_server
-        .as_ref()
-        .and_then(|connector_server| {
-            connector_server.cli.as_ref().and_then(|cli| {
-                cli.auth_key.and_then(|auth_key_id| {
-                    if is_cli_command() {
-                        Some((prompt_for_auth_key_password(auth_key_id), false))
-                    } else {
-                        None
-                    }
-                })
-            })
-        })
-        .unwrap_or_else(|| (cfg.auth.credentials(), true))
-}
-
-/// Prompt for the password for the given auth key and generate `yubihsm::Credentials`
-#[cfg(feature = "yubihsm-server")]
-fn prompt_for_auth_key_password(auth_key_id: u16) -> yubihsm::Credentials {
-    let prompt = format!(
-        "Enter password for YubiHSM2 auth key 0x{:04x}: ",
-        auth_key_id
-    );
-
-    let password = Zeroizing::new(
-        rpassword::read_password_from_tty(Some(&prompt)).expect("error reading password"),
-    );
-
-    yubihsm::Credentials::from_password(auth_key_id, password.as_bytes())
+fn client_config() -> Result<(Credentials, bool), Error> {
+    let cli_auth_key = config()
+        .connector_server
+        .and_then(|connector_server| connector_server.cli)
+        .and_then(|cli| cli.auth_key)
+        .filter(|_| is_cli_command());
+
+    match cli_auth_key {
+        Some(auth_key_id) => {
+            // Prompt for the password for the CLI's auth key
+            let auth = AuthConfig::Prompt {
+                key: auth_key_id,
+                password_prompt: true,
+            };
+
+            Ok((auth.credentials()?, false))
+        }
+        None => Ok((credentials()?, true)),
+    }
 }
 
 /// Get the YubiHSM-related configuration
@@ -221,6 +248,23 @@ fn run_connnector_server(config: HttpConfig, connector: Connector) {
     });
 }
 
+/// Get the names of the given capabilities (in hex for ones which are
+/// unknown to the `yubihsm` crate)
+pub fn capability_names(capabilities: yubihsm::Capability) -> Vec<String> {
+    (0..64)
+        .map(|i| 1u64 << i)
+        .filter(|bit| capabilities.bits() & bit != 0)
+        .map(|bit| {
+            let mut name = String::new();
+
+            match yubihsm::Capability::from_bits(bit) {
+                Some(capability) if write!(name, "{}", capability).is_ok() => name,
+                _ => format!("0x{:x}", bit),
+            }
+        })
+        .collect()
+}
+
 impl From<yubihsm::client::Error> for Error {
     fn from(other: yubihsm::client::Error) -> Error {
         ErrorKind::YubihsmError.context(other).into()
@@ -0,0 +1,483 @@
+//! YubiHSM2 key attestation: offline verification of attestation certificate
+//! chains.
+//!
+//! The YubiHSM2 can sign an X.509 certificate for any asymmetric key it holds
+//! with its device attestation key, whose own certificate (stored on the
+//! device as opaque object 0) is issued by Yubico. Checking this chain
+//! against Yubico's root certificate proves the key was resident in a genuine
+//! YubiHSM2. Only the small subset of DER/X.509 needed for this is parsed.
+//!
+//! Validity periods aren't checked: the YubiHSM2 has no clock, and these
+//! certificates are used to prove where a key lives, not to authenticate a
+//! peer.
+
+use crate::{
+    error::{Error, ErrorKind::*},
+    prelude::*,
+};
+use ring::signature::{self, UnparsedPublicKey, VerificationAlgorithm};
+use std::{fs, path::Path};
+use subtle_encoding::base64;
+use yubihsm::object;
+
+/// Object ID of the opaque object holding the device attestation certificate
+pub const DEVICE_CERTIFICATE_ID: object::Id = 0;
+
+/// Maximum number of certificates in a chain (including the root)
+const MAX_CHAIN_LENGTH: usize = 8;
+
+/// DER tags
+const INTEGER: u8 = 0x02;
+const BIT_STRING: u8 = 0x03;
+const OID: u8 = 0x06;
+const SEQUENCE: u8 = 0x30;
+const EXPLICIT_VERSION: u8 = 0xa0;
+
+/// Object identifiers (DER-encoded contents)
+const OID_COMMON_NAME: &[u8] = &[0x55, 0x04, 0x03];
+const OID_RSA_ENCRYPTION: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x01];
+const OID_SHA256_WITH_RSA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0b];
+const OID_SHA384_WITH_RSA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0c];
+const OID_SHA512_WITH_RSA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0d];
+const OID_EC_PUBLIC_KEY: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01];
+const OID_ECDSA_WITH_SHA256: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x02];
+const OID_ECDSA_WITH_SHA384: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x03];
+const OID_P256: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07];
+const OID_P384: &[u8] = &[0x2b, 0x81, 0x04, 0x00, 0x22];
+const OID_ED25519: &[u8] = &[0x2b, 0x65, 0x70];
+
+/// Parsed X.509 certificate
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Certificate {
+    /// DER encoding of the whole certificate
+    der: Vec<u8>,
+
+    /// DER encoding of the signed part of the certificate
+    tbs_certificate: Vec<u8>,
+
+    /// Signature algorithm OID
+    signature_algorithm: Vec<u8>,
+
+    /// Signature over `tbs_certificate`
+    signature: Vec<u8>,
+
+    /// DER encoding of the issuer's name
+    issuer: Vec<u8>,
+
+    /// DER encoding of the subject's name
+    subject: Vec<u8>,
+
+    /// Public key algorithm OID
+    public_key_algorithm: Vec<u8>,
+
+    /// Public key algorithm parameters (i.e. the curve OID for EC keys)
+    public_key_params: Option<Vec<u8>>,
+
+    /// Subject's public key
+    public_key: Vec<u8>,
+}
+
+impl Certificate {
+    /// Parse a DER-encoded certificate
+    pub fn from_der(der: &[u8]) -> Result<Self, Error> {
+        let mut outer = Reader::new(der);
+        let certificate = outer.read(SEQUENCE)?;
+        outer.finish()?;
+
+        let mut certificate = Reader::new(certificate);
+        let tbs_certificate = certificate.read_raw(SEQUENCE)?;
+        let signature_algorithm = Reader::new(certificate.read(SEQUENCE)?).read(OID)?;
+        let signature = bit_string(certificate.read(BIT_STRING)?)?;
+        certificate.finish()?;
+
+        let mut tbs = Reader::new(Reader::new(tbs_certificate).read(SEQUENCE)?);
+
+        if tbs.peek() == Some(EXPLICIT_VERSION) {
+            tbs.read(EXPLICIT_VERSION)?;
+        }
+
+        tbs.read(INTEGER)?; // serial number
+        tbs.read(SEQUENCE)?; // signature algorithm (repeated)
+        let issuer = tbs.read_raw(SEQUENCE)?;
+        tbs.read(SEQUENCE)?; // validity
+        let subject = tbs.read_raw(SEQUENCE)?;
+
+        let mut public_key_info = Reader::new(tbs.read(SEQUENCE)?);
+        let mut algorithm = Reader::new(public_key_info.read(SEQUENCE)?);
+        let public_key_algorithm = algorithm.read(OID)?;
+
+        let public_key_params = if algorithm.peek() == Some(OID) {
+            Some(algorithm.read(OID)?.to_vec())
+        } else {
+            None
+        };
+
+        let public_key = bit_string(public_key_info.read(BIT_STRING)?)?;
+
+        Ok(Self {
+            der: der.to_vec(),
+            tbs_certificate: tbs_certificate.to_vec(),
+            signature_algorithm: signature_algorithm.to_vec(),
+            signature: signature.to_vec(),
+            issuer: issuer.to_vec(),
+            subject: subject.to_vec(),
+            public_key_algorithm: public_key_algorithm.to_vec(),
+            public_key_params,
+            public_key: public_key.to_vec(),
+        })
+    }
+
+    /// Parse one or more certificates, either PEM-encoded or a single
+    /// DER-encoded certificate
+    pub fn parse_all(bytes: &[u8]) -> Result<Vec<Self>, Error> {
+        let pem = match std::str::from_utf8(bytes) {
+            Ok(pem) if pem.contains("-----BEGIN CERTIFICATE-----") => pem,
+            _ => return Ok(vec![Self::from_der(bytes)?]),
+        };
+
+        let mut certificates = vec![];
+        let mut base64_lines: Option<String> = None;
+
+        for line in pem.lines().map(str::trim) {
+            if line == "-----BEGIN CERTIFICATE-----" {
+                base64_lines = Some(String::new());
+            } else if line == "-----END CERTIFICATE-----" {
+                let encoded = base64_lines
+                    .take()
+                    .ok_or_else(|| format_err!(ParseError, "malformed PEM certificate"))?;
+
+                let der = base64::decode(&encoded)
+                    .map_err(|_| format_err!(ParseError, "malformed PEM certificate"))?;
+
+                certificates.push(Self::from_der(&der)?);
+            } else if let Some(encoded) = base64_lines.as_mut() {
+                encoded.push_str(line);
+            }
+        }
+
+        if base64_lines.is_some() {
+            fail!(ParseError, "malformed PEM certificate: missing END line");
+        }
+
+        Ok(certificates)
+    }
+
+    /// Read one or more certificates (PEM or DER) from the given file
+    pub fn read_file(path: &Path) -> Result<Vec<Self>, Error> {
+        let bytes = fs::read(path)
+            .map_err(|e| format_err!(IoError, "couldn't read {}: {}", path.display(), e))?;
+
+        let certificates = Self::parse_all(&bytes)
+            .map_err(|e| format_err!(ParseError, "{}: {}", path.display(), e))?;
+
+        if certificates.is_empty() {
+            fail!(ParseError, "{}: no certificates found", path.display());
+        }
+
+        Ok(certificates)
+    }
+
+    /// DER encoding of this certificate
+    pub fn as_der(&self) -> &[u8] {
+        &self.der
+    }
+
+    /// PEM encoding of this certificate
+    pub fn to_pem(&self) -> String {
+        let encoded = base64::encode(&self.der);
+        let mut pem = String::from("-----BEGIN CERTIFICATE-----\n");
+
+        for line in encoded.chunks(64) {
+            pem.push_str(std::str::from_utf8(line).unwrap());
+            pem.push('\n');
+        }
+
+        pem.push_str("-----END CERTIFICATE-----\n");
+        pem
+    }
+
+    /// Common name of the certificate's subject (if it has one)
+    pub fn subject_name(&self) -> String {
+        common_name(&self.subject).unwrap_or_else(|| "(unnamed)".to_owned())
+    }
+
+    /// Subject's Ed25519 public key, if the certificate is for one
+    pub fn ed25519_public_key(&self) -> Option<&[u8]> {
+        if self.public_key_algorithm == OID_ED25519 {
+            Some(&self.public_key)
+        } else {
+            None
+        }
+    }
+
+    /// Is this certificate issued by the given certificate (i.e. does the
+    /// name match and does the issuer's key verify the signature)?
+    pub fn is_issued_by(&self, issuer: &Certificate) -> Result<(), Error> {
+        if self.issuer != issuer.subject {
+            fail!(
+                VerificationError,
+                "'{}' wasn't issued by '{}'",
+                self.subject_name(),
+                issuer.subject_name()
+            );
+        }
+
+        let algorithm = issuer.verification_algorithm(&self.signature_algorithm)?;
+
+        UnparsedPublicKey::new(algorithm, &issuer.public_key)
+            .verify(&self.tbs_certificate, &self.signature)
+            .map_err(|_| {
+                format_err!(
+                    VerificationError,
+                    "invalid signature on '{}' by '{}'",
+                    self.subject_name(),
+                    issuer.subject_name()
+                )
+                .into()
+            })
+    }
+
+    /// Algorithm for verifying signatures of the given type made with this
+    /// certificate's key
+    fn verification_algorithm(
+        &self,
+        signature_algorithm: &[u8],
+    ) -> Result<&'static dyn VerificationAlgorithm, Error> {
+        let key_algorithm = self.public_key_algorithm.as_slice();
+        let curve = self.public_key_params.as_deref();
+
+        Ok(match (key_algorithm, curve, signature_algorithm) {
+            (OID_RSA_ENCRYPTION, _, OID_SHA256_WITH_RSA) => &signature::RSA_PKCS1_2048_8192_SHA256,
+            (OID_RSA_ENCRYPTION, _, OID_SHA384_WITH_RSA) => &signature::RSA_PKCS1_2048_8192_SHA384,
+            (OID_RSA_ENCRYPTION, _, OID_SHA512_WITH_RSA) => &signature::RSA_PKCS1_2048_8192_SHA512,
+            (OID_EC_PUBLIC_KEY, Some(OID_P256), OID_ECDSA_WITH_SHA256) => {
+                &signature::ECDSA_P256_SHA256_ASN1
+            }
+            (OID_EC_PUBLIC_KEY, Some(OID_P256), OID_ECDSA_WITH_SHA384) => {
+                &signature::ECDSA_P256_SHA384_ASN1
+            }
+            (OID_EC_PUBLIC_KEY, Some(OID_P384), OID_ECDSA_WITH_SHA256) => {
+                &signature::ECDSA_P384_SHA256_ASN1
+            }
+            (OID_EC_PUBLIC_KEY, Some(OID_P384), OID_ECDSA_WITH_SHA384) => {
+                &signature::ECDSA_P384_SHA384_ASN1
+            }
+            (OID_ED25519, _, OID_ED25519) => &signature::ED25519,
+            _ => fail!(
+                VerificationError,
+                "unsupported signature algorithm for '{}'",
+                self.subject_name()
+            ),
+        })
+    }
+}
+
+/// Verify the given certificate chains to one of the trusted `roots`, via
+/// zero or more `intermediates` (in any order).
+///
+/// Returns the chain from `certificate` up to (and including) the root.
+pub fn verify_chain<'a>(
+    certificate: &'a Certificate,
+    intermediates: &'a [Certificate],
+    roots: &'a [Certificate],
+) -> Result<Vec<&'a Certificate>, Error> {
+    let mut chain = vec![certificate];
+
+    while chain.len() < MAX_CHAIN_LENGTH {
+        let current = *chain.last().unwrap();
+
+        if let Some(root) = roots.iter().find(|root| current.is_issued_by(root).is_ok()) {
+            chain.push(root);
+            return Ok(chain);
+        }
+
+        match intermediates
+            .iter()
+            .find(|cert| !chain.contains(cert) && current.is_issued_by(cert).is_ok())
+        {
+            Some(issuer) => chain.push(issuer),
+            None => fail!(
+                VerificationError,
+                "couldn't find a trusted issuer for '{}'",
+                current.subject_name()
+            ),
+        }
+    }
+
+    fail!(VerificationError, "certificate chain is too long")
+}
+
+/// Minimal DER reader
+struct Reader<'a> {
+    input: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    /// Create a reader for the given input
+    fn new(input: &'a [u8]) -> Self {
+        Self { input }
+    }
+
+    /// Tag of the next element (if any)
+    fn peek(&self) -> Option<u8> {
+        self.input.first().cloned()
+    }
+
+    /// Read an element with the given tag, returning its contents
+    fn read(&mut self, tag: u8) -> Result<&'a [u8], Error> {
+        let (header_len, contents_len) = self.read_header(tag)?;
+        let contents = &self.input[header_len..header_len + contents_len];
+        self.input = &self.input[header_len + contents_len..];
+        Ok(contents)
+    }
+
+    /// Read an element with the given tag, returning its complete encoding
+    fn read_raw(&mut self, tag: u8) -> Result<&'a [u8], Error> {
+        let (header_len, contents_len) = self.read_header(tag)?;
+        let element = &self.input[..header_len + contents_len];
+        self.input = &self.input[header_len + contents_len..];
+        Ok(element)
+    }
+
+    /// Ensure all input has been read
+    fn finish(&self) -> Result<(), Error> {
+        if self.input.is_empty() {
+            Ok(())
+        } else {
+            fail!(ParseError, "malformed certificate: trailing data")
+        }
+    }
+
+    /// Parse the header of the next element, returning the lengths of the
+    /// header and contents
+    fn read_header(&self, tag: u8) -> Result<(usize, usize), Error> {
+        if self.peek() != Some(tag) {
+            fail!(
+                ParseError,
+                "malformed certificate: expected tag 0x{:02x}",
+                tag
+            );
+        }
+
+        let (header_len, contents_len) = match self.input.get(1).cloned() {
+            Some(len) if len < 0x80 => (2, len as usize),
+            Some(len) if len > 0x80 && len <= 0x84 => {
+                let num_bytes = (len & 0x7f) as usize;
+                let bytes = self.input.get(2..2 + num_bytes).ok_or_else(|| {
+                    format_err!(ParseError, "malformed certificate: truncated length")
+                })?;
+
+                let contents_len = bytes
+                    .iter()
+                    .fold(0usize, |acc, &byte| (acc << 8) | byte as usize);
+
+                (2 + num_bytes, contents_len)
+            }
+            _ => fail!(ParseError, "malformed certificate: invalid length"),
+        };
+
+        if self.input.len() - header_len < contents_len {
+            fail!(ParseError, "malformed certificate: truncated element");
+        }
+
+        Ok((header_len, contents_len))
+    }
+}
+
+/// Contents of a BIT STRING without unused bits
+fn bit_string(contents: &[u8]) -> Result<&[u8], Error> {
+    match contents.split_first() {
+        Some((0, bits)) => Ok(bits),
+        _ => fail!(ParseError, "malformed certificate: invalid bit string"),
+    }
+}
+
+/// Find the common name (CN) in a DER-encoded X.509 name
+fn common_name(name: &[u8]) -> Option<String> {
+    let mut rdns = Reader::new(Reader::new(name).read(SEQUENCE).ok()?);
+
+    while rdns.peek().is_some() {
+        let mut attributes = Reader::new(rdns.read(0x31).ok()?);
+
+        while attributes.peek().is_some() {
+            let mut attribute = Reader::new(attributes.read(SEQUENCE).ok()?);
+
+            if attribute.read(OID).ok()? == OID_COMMON_NAME {
+                let value_tag = attribute.peek()?;
+                let value = attribute.read(value_tag).ok()?;
+                return Some(String::from_utf8_lossy(value).into_owned());
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test root CA (RSA-2048)
+    const ROOT_PEM: &str = include_str!("../../tests/support/yubihsm_attestation/root.pem");
+
+    /// Test root CA with the same name but a different (P-384) key
+    const OTHER_ROOT_PEM: &str =
+        include_str!("../../tests/support/yubihsm_attestation/other_root.pem");
+
+    /// Test device attestation certificate (P-256, issued by the root)
+    const DEVICE_PEM: &str = include_str!("../../tests/support/yubihsm_attestation/dev.pem");
+
+    /// Test attestation certificate for an Ed25519 key
+    const LEAF_PEM: &str = include_str!("../../tests/support/yubihsm_attestation/leaf.pem");
+
+    /// Ed25519 public key in `LEAF_PEM`
+    const LEAF_PUBLIC_KEY: [u8; 32] = [
+        0x73, 0xac, 0x2b, 0x44, 0xc2, 0x6c, 0x02, 0x13, 0x03, 0x39, 0xbb, 0x87, 0x12, 0xad, 0x56,
+        0x51, 0x4e, 0xd2, 0xa3, 0x21, 0x48, 0x84, 0x8f, 0x5f, 0xcd, 0x29, 0xfc, 0x7f, 0x9d, 0x61,
+        0xb4, 0xe5,
+    ];
+
+    fn parse(pem: &str) -> Certificate {
+        let mut certificates = Certificate::parse_all(pem.as_bytes()).unwrap();
+        assert_eq!(certificates.len(), 1);
+        certificates.remove(0)
+    }
+
+    #[test]
+    fn parses_certificates() {
+        let leaf = parse(LEAF_PEM);
+        assert_eq!(leaf.subject_name(), "YubiHSM Attestation id:0x0001");
+        assert_eq!(leaf.ed25519_public_key().unwrap(), &LEAF_PUBLIC_KEY[..]);
+        assert_eq!(parse(&leaf.to_pem()), leaf);
+        assert_eq!(Certificate::from_der(leaf.as_der()).unwrap(), leaf);
+
+        let bundle = [DEVICE_PEM, ROOT_PEM].concat();
+        assert_eq!(Certificate::parse_all(bundle.as_bytes()).unwrap().len(), 2);
+
+        assert!(Certificate::from_der(&leaf.as_der()[..100]).is_err());
+    }
+
+    #[test]
+    fn verifies_chain() {
+        let leaf = parse(LEAF_PEM);
+        let device = parse(DEVICE_PEM);
+        let root = parse(ROOT_PEM);
+        let other_root = parse(OTHER_ROOT_PEM);
+
+        let intermediates = [device.clone()];
+        let roots = [other_root.clone(), root.clone()];
+        let chain = verify_chain(&leaf, &intermediates, &roots).unwrap();
+        assert_eq!(chain, vec![&leaf, &device, &root]);
+
+        // Wrong root (same name, different key)
+        assert!(verify_chain(&leaf, &intermediates, &[other_root]).is_err());
+
+        // Missing device certificate
+        assert!(verify_chain(&leaf, &[], std::slice::from_ref(&root)).is_err());
+
+        // Tampered certificate
+        let mut tampered = leaf.clone();
+        tampered.tbs_certificate[20] ^= 1;
+        assert!(verify_chain(&tampered, &intermediates, &[root]).is_err());
+    }
+}
@@ -0,0 +1,378 @@
+//! YubiHSM2 audit log: retrieval, verification of the log's hash chain, and
+//! detection of operations the KMS didn't perform itself
+
+use crate::{config::provider::yubihsm::AuditLogConfig, error::Error, prelude::*};
+use sha2::{Digest, Sha256};
+use std::{
+    fmt::{self, Display},
+    thread,
+    time::Duration,
+};
+use yubihsm::{command, object, response, Client};
+
+/// Size of the (truncated) digest of each log entry
+pub const DIGEST_SIZE: usize = 16;
+
+/// Auth key ID the YubiHSM uses in entries which aren't tied to a session
+const NO_KEY: object::Id = 0xffff;
+
+/// Commands the KMS itself sends to the YubiHSM while running
+const KMS_COMMANDS: &[command::Code] = &[
+    command::Code::Echo,
+    command::Code::CreateSession,
+    command::Code::AuthenticateSession,
+    command::Code::SessionMessage,
+    command::Code::DeviceInfo,
+    command::Code::CloseSession,
+    command::Code::ListObjects,
+    command::Code::GetLogEntries,
+    command::Code::GetObjectInfo,
+    command::Code::GetPublicKey,
+    command::Code::SetLogIndex,
+    command::Code::SignEddsa,
+];
+
+/// Contents of the YubiHSM's audit log
+#[derive(Clone, Debug)]
+pub struct AuditLog {
+    /// Number of boot events which weren't logged (because the log was full)
+    pub unlogged_boot_events: u16,
+
+    /// Number of authentication events which weren't logged (because the log
+    /// was full)
+    pub unlogged_auth_events: u16,
+
+    /// Entries in the log
+    pub entries: Vec<Entry>,
+}
+
+impl AuditLog {
+    /// Read the audit log (i.e. all entries which haven't been consumed)
+    pub fn read(client: &Client) -> Result<Self, Error> {
+        let log = client.get_log_entries()?;
+
+        let entries = log
+            .entries
+            .iter()
+            .map(|entry| Entry {
+                item: entry.item,
+                cmd: entry.cmd,
+                length: entry.length,
+                session_key: entry.session_key,
+                target_key: entry.target_key,
+                second_key: entry.second_key,
+                result: entry.result,
+                tick: entry.tick,
+                digest: entry.digest.0,
+            })
+            .collect();
+
+        Ok(Self {
+            unlogged_boot_events: log.unlogged_boot_events,
+            unlogged_auth_events: log.unlogged_auth_events,
+            entries,
+        })
+    }
+}
+
+/// Audit log entry
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Entry {
+    /// Entry number
+    pub item: u16,
+
+    /// Command which was performed
+    pub cmd: command::Code,
+
+    /// Length of the command
+    pub length: u16,
+
+    /// Auth key ID of the session the command was sent in
+    pub session_key: object::Id,
+
+    /// ID of the object the command acted on
+    pub target_key: object::Id,
+
+    /// ID of a second object the command acted on
+    pub second_key: object::Id,
+
+    /// Result of the command
+    pub result: response::Code,
+
+    /// Tick count of the YubiHSM's internal clock
+    pub tick: u32,
+
+    /// Truncated SHA-256 digest of this entry and the previous entry's digest
+    pub digest: [u8; DIGEST_SIZE],
+}
+
+impl Entry {
+    /// Compute this entry's digest, chained from the previous entry's digest
+    pub fn compute_digest(&self, previous_digest: &[u8; DIGEST_SIZE]) -> [u8; DIGEST_SIZE] {
+        let mut hasher = Sha256::new();
+        hasher.input(self.item.to_be_bytes());
+        hasher.input([self.cmd.to_u8()]);
+        hasher.input(self.length.to_be_bytes());
+        hasher.input(self.session_key.to_be_bytes());
+        hasher.input(self.target_key.to_be_bytes());
+        hasher.input(self.second_key.to_be_bytes());
+        hasher.input([self.result.to_u8()]);
+        hasher.input(self.tick.to_be_bytes());
+        hasher.input(previous_digest);
+
+        let mut digest = [0u8; DIGEST_SIZE];
+        digest.copy_from_slice(&hasher.result()[..DIGEST_SIZE]);
+        digest
+    }
+
+    /// Is this entry the YubiHSM booting (or being reset)?
+    pub fn is_boot(&self) -> bool {
+        self.cmd == command::Code::Unknown && self.session_key == NO_KEY
+    }
+
+    /// ID of the auth key which performed this operation
+    pub fn auth_key(&self) -> object::Id {
+        // Sessions aren't established yet when they're being opened
+        if self.session_key == NO_KEY {
+            self.target_key
+        } else {
+            self.session_key
+        }
+    }
+
+    /// If this isn't an operation the KMS (authenticating with `auth_key`)
+    /// would perform, describe why
+    pub fn unexpected(&self, auth_key: object::Id) -> Option<String> {
+        if self.is_boot() {
+            return None;
+        }
+
+        if self.auth_key() != auth_key {
+            return Some(format!(
+                "operation by auth key 0x{:04x} (the KMS uses 0x{:04x})",
+                self.auth_key(),
+                auth_key
+            ));
+        }
+
+        if !KMS_COMMANDS.contains(&self.cmd) {
+            return Some(format!("unexpected {:?} operation", self.cmd));
+        }
+
+        if self.cmd == command::Code::AuthenticateSession && self.result.is_err() {
+            return Some("failed authentication".to_owned());
+        }
+
+        None
+    }
+}
+
+impl Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_boot() {
+            return write!(f, "#{} device boot (tick {})", self.item, self.tick);
+        }
+
+        write!(
+            f,
+            "#{} {:?} (auth key: 0x{:04x}, target: 0x{:04x}, second: 0x{:04x}) -> {:?} \
+             (tick {}, digest {})",
+            self.item,
+            self.cmd,
+            self.auth_key(),
+            self.target_key,
+            self.second_key,
+            self.result,
+            self.tick,
+            hex(&self.digest)
+        )
+    }
+}
+
+/// Verify the given entries form an unbroken hash chain (starting from
+/// `previous`, if given), returning all problems found.
+///
+/// Without a `previous` entry, the first entry is trusted as the start of the
+/// chain.
+pub fn verify(entries: &[Entry], previous: Option<&Entry>) -> Vec<String> {
+    let mut problems = vec![];
+    let mut previous = previous;
+
+    for entry in entries {
+        if let Some(prev) = previous {
+            let expected_item = prev.item.wrapping_add(1);
+
+            if entry.item != expected_item {
+                problems.push(format!(
+                    "entries missing between #{} and #{} (consumed or overwritten?)",
+                    prev.item, entry.item
+                ));
+            } else if entry.compute_digest(&prev.digest) != entry.digest {
+                problems.push(format!(
+                    "digest mismatch at #{} (log has been tampered with?)",
+                    entry.item
+                ));
+            }
+        }
+
+        previous = Some(entry);
+    }
+
+    problems
+}
+
+/// Spawn a thread which polls the audit log, forwarding new entries to the
+/// KMS's log and warning about operations the KMS didn't perform
+pub fn spawn_monitor(config: &AuditLogConfig, auth_key: object::Id) -> Result<(), Error> {
+    let interval = Duration::from_secs(config.interval_secs);
+
+    let mut monitor = Monitor {
+        auth_key,
+        consume: config.consume,
+        last_entry: None,
+    };
+
+    thread::Builder::new()
+        .name("yubihsm-audit-log".to_owned())
+        .spawn(move || loop {
+            if let Err(e) = monitor.poll() {
+                warn!("[yubihsm] couldn't read audit log: {}", e);
+            }
+
+            thread::sleep(interval);
+        })?;
+
+    Ok(())
+}
+
+/// Audit log monitor state
+struct Monitor {
+    /// Auth key ID the KMS uses
+    auth_key: object::Id,
+
+    /// Mark entries as consumed after forwarding them
+    consume: bool,
+
+    /// Last entry which was forwarded
+    last_entry: Option<Entry>,
+}
+
+impl Monitor {
+    /// Read the audit log and forward any new entries
+    fn poll(&mut self) -> Result<(), Error> {
+        let log = AuditLog::read(&super::client())?;
+
+        if log.unlogged_boot_events > 0 || log.unlogged_auth_events > 0 {
+            warn!(
+                "[yubihsm] audit log full: {} boot and {} auth events weren't logged",
+                log.unlogged_boot_events, log.unlogged_auth_events
+            );
+        }
+
+        // Skip entries we've already seen (unless they were consumed)
+        let new_entries = match &self.last_entry {
+            Some(last_entry) => match log.entries.iter().position(|e| e == last_entry) {
+                Some(pos) => &log.entries[pos + 1..],
+                None => &log.entries[..],
+            },
+            None => &log.entries[..],
+        };
+
+        for problem in verify(new_entries, self.last_entry.as_ref()) {
+            warn!("[yubihsm] audit log: {}", problem);
+        }
+
+        for entry in new_entries {
+            match entry.unexpected(self.auth_key) {
+                Some(reason) => warn!("[yubihsm] audit log: {}: {}", reason, entry),
+                None => debug!("[yubihsm] audit log: {}", entry),
+            }
+        }
+
+        if let Some(entry) = new_entries.last() {
+            if self.consume {
+                super::client().set_log_index(entry.item)?;
+            }
+
+            self.last_entry = Some(entry.clone());
+        }
+
+        Ok(())
+    }
+}
+
+/// Format bytes as lowercase hex
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Auth key used by the KMS in tests
+    const AUTH_KEY: object::Id = 2;
+
+    /// Build a hash chain of log entries for the given commands
+    fn example_entries(commands: &[(command::Code, object::Id)]) -> Vec<Entry> {
+        let mut entries: Vec<Entry> = vec![];
+
+        for (i, &(cmd, session_key)) in commands.iter().enumerate() {
+            let mut entry = Entry {
+                item: i as u16 + 1,
+                cmd,
+                length: 42,
+                session_key,
+                target_key: 1,
+                second_key: NO_KEY,
+                result: response::Code::Success(cmd),
+                tick: 1000 + i as u32,
+                digest: [0u8; DIGEST_SIZE],
+            };
+
+            let previous_digest = entries.last().map(|e| e.digest).unwrap_or_default();
+            entry.digest = entry.compute_digest(&previous_digest);
+            entries.push(entry);
+        }
+
+        entries
+    }
+
+    #[test]
+    fn verifies_hash_chain() {
+        let entries = example_entries(&[
+            (command::Code::SignEddsa, AUTH_KEY),
+            (command::Code::SignEddsa, AUTH_KEY),
+            (command::Code::GetLogEntries, AUTH_KEY),
+        ]);
+
+        assert!(verify(&entries, None).is_empty());
+        assert!(verify(&entries[1..], Some(&entries[0])).is_empty());
+
+        let mut tampered = entries.clone();
+        tampered[1].target_key = 3;
+        assert_eq!(verify(&tampered, None).len(), 1);
+
+        let gap = vec![entries[0].clone(), entries[2].clone()];
+        assert!(verify(&gap, None)[0].contains("missing"));
+    }
+
+    #[test]
+    fn detects_unexpected_operations() {
+        let entries = example_entries(&[
+            (command::Code::SignEddsa, AUTH_KEY),
+            (command::Code::ExportWrapped, AUTH_KEY),
+            (command::Code::GetPublicKey, 1),
+        ]);
+
+        assert!(entries[0].unexpected(AUTH_KEY).is_none());
+        assert!(entries[1]
+            .unexpected(AUTH_KEY)
+            .unwrap()
+            .contains("ExportWrapped"));
+        assert!(entries[2]
+            .unexpected(AUTH_KEY)
+            .unwrap()
+            .contains("auth key 0x0001"));
+    }
+}
@@ -0,0 +1,489 @@
+//! Backups of the YubiHSM2's contents: every object which is exportable
+//! under wrap, encrypted under a wrap key and collected into one archive
+
+use crate::{
+    error::{Error, ErrorKind},
+    prelude::*,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    os::unix::fs::OpenOptionsExt,
+    path::Path,
+};
+use subtle_encoding::{base64, hex};
+use yubihsm::{object, wrap, Capability, Client, Domain};
+
+/// Version of the archive format
+pub const ARCHIVE_VERSION: u32 = 1;
+
+/// Backup archive: a manifest describing each object alongside its
+/// ciphertext (encrypted under the wrap key)
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Archive {
+    /// Version of the archive format
+    pub version: u32,
+
+    /// Serial number of the device the backup was made from
+    pub serial_number: String,
+
+    /// Time the backup was made
+    pub created_at: DateTime<Utc>,
+
+    /// Wrap key the objects are encrypted under
+    pub wrap_key_id: object::Id,
+
+    /// Objects in the backup
+    pub objects: Vec<ArchivedObject>,
+
+    /// Objects which weren't backed up because they aren't exportable
+    pub skipped: Vec<SkippedObject>,
+}
+
+impl Archive {
+    /// Back up every exportable object in the HSM under the given wrap key
+    pub fn create(client: &Client, wrap_key_id: object::Id) -> Result<Self, Error> {
+        client
+            .get_object_info(wrap_key_id, object::Type::WrapKey)
+            .map_err(|e| {
+                format_err!(
+                    ErrorKind::YubihsmError,
+                    "couldn't find wrap key 0x{:04x}: {}",
+                    wrap_key_id,
+                    e
+                )
+            })?;
+
+        let serial_number = client.device_info()?.serial_number.to_string();
+        let mut objects = vec![];
+        let mut skipped = vec![];
+
+        for entry in client.list_objects(&[])? {
+            // The wrap key can't be exported under itself
+            if entry.object_type == object::Type::WrapKey && entry.object_id == wrap_key_id {
+                continue;
+            }
+
+            let info = client.get_object_info(entry.object_id, entry.object_type)?;
+
+            if !info
+                .capabilities
+                .contains(Capability::EXPORTABLE_UNDER_WRAP)
+            {
+                skipped.push(SkippedObject {
+                    object_id: info.object_id,
+                    object_type: info.object_type.to_string(),
+                    label: info.label.to_string(),
+                });
+                continue;
+            }
+
+            objects.push(ArchivedObject::export(client, wrap_key_id, &info)?);
+        }
+
+        Ok(Self {
+            version: ARCHIVE_VERSION,
+            serial_number,
+            created_at: Utc::now(),
+            wrap_key_id,
+            objects,
+            skipped,
+        })
+    }
+
+    /// Parse an archive from JSON
+    pub fn parse(json: &str) -> Result<Self, Error> {
+        let archive: Self = serde_json::from_str(json)
+            .map_err(|e| format_err!(ErrorKind::ParseError, "malformed backup archive: {}", e))?;
+
+        if archive.version != ARCHIVE_VERSION {
+            fail!(
+                ErrorKind::ParseError,
+                "unsupported backup archive version: {} (expected {})",
+                archive.version,
+                ARCHIVE_VERSION
+            );
+        }
+
+        Ok(archive)
+    }
+
+    /// Read an archive from the given file
+    pub fn read(path: &Path) -> Result<Self, Error> {
+        let json = fs::read_to_string(path).map_err(|e| {
+            format_err!(
+                ErrorKind::IoError,
+                "couldn't read {}: {}",
+                path.display(),
+                e
+            )
+        })?;
+
+        Self::parse(&json)
+    }
+
+    /// Write the archive to the given file (readable only by its owner),
+    /// which must not already exist
+    pub fn write(&self, path: &Path) -> Result<(), Error> {
+        let json = serde_json::to_string_pretty(self).unwrap();
+
+        OpenOptions::new()
+            .create_new(true)
+            .write(true)
+            .mode(0o600)
+            .open(path)
+            .and_then(|mut file| {
+                file.write_all(json.as_bytes())?;
+                file.sync_all()
+            })
+            .map_err(|e| {
+                format_err!(
+                    ErrorKind::IoError,
+                    "couldn't write {}: {}",
+                    path.display(),
+                    e
+                )
+                .into()
+            })
+    }
+
+    /// Restore the archived objects into the HSM, checking the public keys
+    /// of restored asymmetric keys match the ones in the manifest.
+    ///
+    /// Objects which are already present (e.g. auth keys created by
+    /// `tmkms yubihsm setup --restore`) are left as they are.
+    pub fn restore(&self, client: &Client) -> Result<Vec<Restored>, Error> {
+        let mut results = vec![];
+
+        for object in &self.objects {
+            let object_type = object.object_type()?;
+
+            let status = if client
+                .get_object_info(object.object_id, object_type)
+                .is_ok()
+            {
+                RestoreStatus::AlreadyPresent
+            } else {
+                object.import(client, self.wrap_key_id)?;
+                RestoreStatus::Imported
+            };
+
+            object.verify_public_key(client)?;
+
+            results.push(Restored {
+                object_id: object.object_id,
+                object_type,
+                label: object.label.clone(),
+                status,
+            });
+        }
+
+        Ok(results)
+    }
+}
+
+/// Object in a backup archive
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ArchivedObject {
+    /// ID of the object
+    pub object_id: object::Id,
+
+    /// Type of the object
+    pub object_type: String,
+
+    /// Label of the object
+    pub label: String,
+
+    /// Capabilities of the object
+    pub capabilities: Vec<String>,
+
+    /// Domains the object is accessible from
+    pub domains: Vec<usize>,
+
+    /// Public key (hex) if the object is an asymmetric key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub public_key: Option<String>,
+
+    /// Object encrypted under the wrap key (Base64)
+    pub wrapped: String,
+}
+
+impl ArchivedObject {
+    /// Export the given object under the given wrap key
+    fn export(
+        client: &Client,
+        wrap_key_id: object::Id,
+        info: &object::Info,
+    ) -> Result<Self, Error> {
+        let public_key = if info.object_type == object::Type::AsymmetricKey {
+            let public_key = client.get_public_key(info.object_id)?;
+            Some(String::from_utf8(hex::encode_upper(public_key.as_ref())).unwrap())
+        } else {
+            None
+        };
+
+        let wrapped = client
+            .export_wrapped(wrap_key_id, info.object_type, info.object_id)
+            .map_err(|e| {
+                format_err!(
+                    ErrorKind::YubihsmError,
+                    "couldn't export {} 0x{:04x} under wrap key 0x{:04x}: {}",
+                    info.object_type,
+                    info.object_id,
+                    wrap_key_id,
+                    e
+                )
+            })?;
+
+        Ok(Self {
+            object_id: info.object_id,
+            object_type: info.object_type.to_string(),
+            label: info.label.to_string(),
+            capabilities: super::capability_names(info.capabilities),
+            domains: (1..=16)
+                .filter(|&i| info.domains.contains(Domain::at(i).unwrap()))
+                .collect(),
+            public_key,
+            wrapped: String::from_utf8(base64::encode(wrapped.into_vec())).unwrap(),
+        })
+    }
+
+    /// Get the type of this object
+    pub fn object_type(&self) -> Result<object::Type, Error> {
+        self.object_type.parse().map_err(|_| {
+            format_err!(
+                ErrorKind::ParseError,
+                "invalid type for object 0x{:04x}: {}",
+                self.object_id,
+                self.object_type
+            )
+            .into()
+        })
+    }
+
+    /// Import this object into the HSM under the given wrap key
+    fn import(&self, client: &Client, wrap_key_id: object::Id) -> Result<(), Error> {
+        let message = base64::decode(&self.wrapped)
+            .ok()
+            .and_then(|bytes| wrap::Message::from_vec(bytes).ok())
+            .ok_or_else(|| {
+                format_err!(
+                    ErrorKind::ParseError,
+                    "malformed ciphertext for object 0x{:04x}",
+                    self.object_id
+                )
+            })?;
+
+        let handle = client.import_wrapped(wrap_key_id, message).map_err(|e| {
+            format_err!(
+                ErrorKind::YubihsmError,
+                "couldn't import {} 0x{:04x} under wrap key 0x{:04x}: {}",
+                self.object_type,
+                self.object_id,
+                wrap_key_id,
+                e
+            )
+        })?;
+
+        if handle.object_id != self.object_id || handle.object_type.to_string() != self.object_type
+        {
+            fail!(
+                ErrorKind::VerificationError,
+                "ciphertext for {} 0x{:04x} contained {} 0x{:04x}",
+                self.object_type,
+                self.object_id,
+                handle.object_type,
+                handle.object_id
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Check the public key in the HSM matches the one in the manifest
+    fn verify_public_key(&self, client: &Client) -> Result<(), Error> {
+        let expected = match &self.public_key {
+            Some(public_key) => public_key,
+            None => return Ok(()),
+        };
+
+        let public_key = client.get_public_key(self.object_id)?;
+        let actual = String::from_utf8(hex::encode_upper(public_key.as_ref())).unwrap();
+
+        if !actual.eq_ignore_ascii_case(expected) {
+            fail!(
+                ErrorKind::VerificationError,
+                "public key mismatch for key 0x{:04x}: expected {}, got {}",
+                self.object_id,
+                expected,
+                actual
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Object which wasn't included in a backup because it isn't exportable
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SkippedObject {
+    /// ID of the object
+    pub object_id: object::Id,
+
+    /// Type of the object
+    pub object_type: String,
+
+    /// Label of the object
+    pub label: String,
+}
+
+/// Result of restoring an object from a backup archive
+#[derive(Clone, Debug)]
+pub struct Restored {
+    /// ID of the object
+    pub object_id: object::Id,
+
+    /// Type of the object
+    pub object_type: object::Type,
+
+    /// Label of the object
+    pub label: String,
+
+    /// What happened to the object
+    pub status: RestoreStatus,
+}
+
+/// What happened to an object when restoring it
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum RestoreStatus {
+    /// Object was imported from the archive
+    Imported,
+
+    /// Object already existed in the HSM (and its public key, if any, matched)
+    AlreadyPresent,
+}
+
+#[cfg(all(test, feature = "yubihsm-mock"))]
+mod tests {
+    use super::*;
+    use yubihsm::{asymmetric, Connector, Credentials};
+
+    /// Wrap key used in tests
+    const WRAP_KEY_ID: object::Id = 1;
+
+    /// Asymmetric key used in tests
+    const KEY_ID: object::Id = 0x100;
+
+    /// Open a client for a fresh MockHSM with a wrap key
+    fn mock_client() -> Client {
+        let client = Client::open(Connector::mockhsm(), Credentials::default(), true).unwrap();
+
+        client
+            .put_wrap_key(
+                WRAP_KEY_ID,
+                "backup wrap key".into(),
+                Domain::all(),
+                Capability::EXPORT_WRAPPED | Capability::IMPORT_WRAPPED,
+                Capability::all(),
+                wrap::Algorithm::Aes256Ccm,
+                [0x42u8; 32].as_ref(),
+            )
+            .unwrap();
+
+        client
+    }
+
+    /// Generate an Ed25519 key with the given capabilities
+    fn generate_key(client: &Client, key_id: object::Id, capabilities: Capability) {
+        client
+            .generate_asymmetric_key(
+                key_id,
+                "test key".into(),
+                Domain::DOM1,
+                capabilities,
+                asymmetric::Algorithm::Ed25519,
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn backup_and_restore() {
+        let source = mock_client();
+        generate_key(
+            &source,
+            KEY_ID,
+            Capability::SIGN_EDDSA | Capability::EXPORTABLE_UNDER_WRAP,
+        );
+        generate_key(&source, KEY_ID + 1, Capability::SIGN_EDDSA);
+
+        let archive = Archive::parse(
+            &serde_json::to_string(&Archive::create(&source, WRAP_KEY_ID).unwrap()).unwrap(),
+        )
+        .unwrap();
+
+        let backed_up = archive
+            .objects
+            .iter()
+            .find(|object| object.object_id == KEY_ID)
+            .unwrap();
+
+        assert_eq!(backed_up.object_type, "asymmetric-key");
+        assert!(backed_up.public_key.is_some());
+        assert!(archive
+            .skipped
+            .iter()
+            .any(|object| object.object_id == KEY_ID + 1));
+
+        let destination = mock_client();
+        let results = archive.restore(&destination).unwrap();
+
+        let restored = results
+            .iter()
+            .find(|result| result.object_id == KEY_ID)
+            .unwrap();
+
+        assert_eq!(restored.status, RestoreStatus::Imported);
+        assert_eq!(
+            source.get_public_key(KEY_ID).unwrap(),
+            destination.get_public_key(KEY_ID).unwrap()
+        );
+
+        // The default auth key is present on both devices
+        assert!(results.iter().any(|result| {
+            result.object_type == object::Type::AuthenticationKey
+                && result.status == RestoreStatus::AlreadyPresent
+        }));
+    }
+
+    #[test]
+    fn restore_detects_public_key_mismatch() {
+        let source = mock_client();
+        generate_key(
+            &source,
+            KEY_ID,
+            Capability::SIGN_EDDSA | Capability::EXPORTABLE_UNDER_WRAP,
+        );
+
+        let archive = Archive::create(&source, WRAP_KEY_ID).unwrap();
+
+        // A different key with the same ID already exists on the destination
+        let destination = mock_client();
+        generate_key(&destination, KEY_ID, Capability::SIGN_EDDSA);
+
+        let err = archive.restore(&destination).unwrap_err();
+        assert!(err.to_string().contains("public key mismatch"));
+    }
+
+    #[test]
+    fn rejects_unsupported_version() {
+        let mut archive = Archive::create(&mock_client(), WRAP_KEY_ID).unwrap();
+        archive.version = ARCHIVE_VERSION + 1;
+
+        let err = Archive::parse(&serde_json::to_string(&archive).unwrap()).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("unsupported backup archive version"));
+    }
+}
@@ -0,0 +1,585 @@
+//! Pool of authenticated YubiHSM sessions used for signing.
+//!
+//! Requests for a session are served in the order they were made, and give up
+//! after a timeout, so a slow (or reconnecting) session can't stall signing
+//! for every chain. A background keepalive pings idle sessions so they don't
+//! time out, and reauthenticates old or broken ones ahead of the next request.
+
+use crate::{
+    config::provider::yubihsm::SessionPoolConfig,
+    error::{Error, ErrorKind},
+    prelude::*,
+};
+use serde::Serialize;
+use std::{
+    collections::VecDeque,
+    ops::Deref,
+    sync::{Condvar, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+use yubihsm::{Client, Connector, Credentials};
+
+/// Maximum number of sessions in the pool (the YubiHSM2 supports 16
+/// concurrent sessions: leave one for the KMS's other uses of the device)
+pub const MAX_SESSIONS: usize = 15;
+
+/// Sessions time out after being idle for this long
+pub const SESSION_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Pool of authenticated YubiHSM sessions
+pub struct SessionPool {
+    /// Connector used to open sessions
+    connector: Connector,
+
+    /// Credentials used to open sessions
+    credentials: Credentials,
+
+    /// Number of sessions in the pool
+    size: usize,
+
+    /// Idle sessions and queued requests
+    queue: Mutex<Queue>,
+
+    /// Signalled whenever a session is returned or the queue changes
+    changed: Condvar,
+
+    /// How long requests wait for a session
+    timeout: Duration,
+}
+
+/// Session in the pool
+struct Session {
+    /// Client with its own session
+    client: Client,
+
+    /// When the session was opened
+    opened_at: Instant,
+
+    /// When the session was last used
+    last_used: Instant,
+}
+
+/// Idle sessions, queued requests and counters
+#[derive(Default)]
+struct Queue {
+    /// Sessions which aren't in use
+    idle: Vec<Session>,
+
+    /// Tickets of queued requests, in the order they were made
+    waiting: VecDeque<u64>,
+
+    /// Ticket for the next request
+    next_ticket: u64,
+
+    /// Largest number of queued requests seen
+    max_queue_depth: usize,
+
+    /// Number of sessions handed out
+    acquired: u64,
+
+    /// Number of requests which timed out
+    timeouts: u64,
+
+    /// Longest time a request waited for a session
+    max_wait: Duration,
+
+    /// Number of keepalive pings sent
+    keepalives: u64,
+
+    /// Number of sessions reauthenticated by the keepalive
+    reauthentications: u64,
+
+    /// Error from the most recent keepalive (if it failed)
+    keepalive_error: Option<String>,
+}
+
+/// Session pool metrics
+#[derive(Clone, Debug, Serialize)]
+pub struct Metrics {
+    /// Number of sessions in the pool
+    pub sessions: usize,
+
+    /// Number of sessions which aren't in use
+    pub idle_sessions: usize,
+
+    /// Number of requests waiting for a session
+    pub queue_depth: usize,
+
+    /// Largest number of requests which have waited for a session
+    pub max_queue_depth: usize,
+
+    /// Number of sessions handed out
+    pub acquired: u64,
+
+    /// Number of requests which timed out waiting for a session
+    pub timeouts: u64,
+
+    /// Longest time a request waited for a session (in milliseconds)
+    pub max_wait_ms: u64,
+
+    /// Number of keepalive pings sent
+    pub keepalives: u64,
+
+    /// Number of sessions reauthenticated by the keepalive
+    pub reauthentications: u64,
+}
+
+impl SessionPool {
+    /// Open a pool of sessions with the given configuration
+    pub fn open(
+        connector: &Connector,
+        credentials: &Credentials,
+        config: &SessionPoolConfig,
+    ) -> Result<Self, Error> {
+        if config.size == 0 || config.size > MAX_SESSIONS {
+            fail!(
+                ErrorKind::ConfigError,
+                "YubiHSM session pool size must be between 1 and {} (got {})",
+                MAX_SESSIONS,
+                config.size
+            );
+        }
+
+        let now = Instant::now();
+        let mut idle = vec![];
+
+        for _ in 0..config.size {
+            idle.push(Session {
+                client: Client::open(connector.clone(), credentials.clone(), true)?,
+                opened_at: now,
+                last_used: now,
+            });
+        }
+
+        let queue = Queue {
+            idle,
+            ..Default::default()
+        };
+
+        Ok(Self {
+            connector: connector.clone(),
+            credentials: credentials.clone(),
+            size: config.size,
+            queue: Mutex::new(queue),
+            changed: Condvar::new(),
+            timeout: Duration::from_millis(config.timeout_ms),
+        })
+    }
+
+    /// Wait (in turn) for a session, failing if one doesn't become available
+    /// before the timeout
+    pub fn acquire(&self) -> Result<PooledClient<'_>, Error> {
+        let started = Instant::now();
+        let deadline = started + self.timeout;
+
+        let mut queue = self.queue.lock().unwrap();
+        let ticket = queue.next_ticket;
+        queue.next_ticket += 1;
+        queue.waiting.push_back(ticket);
+
+        if queue.waiting.len() > queue.max_queue_depth {
+            queue.max_queue_depth = queue.waiting.len();
+        }
+
+        loop {
+            if queue.waiting.front() == Some(&ticket) {
+                if let Some(session) = queue.idle.pop() {
+                    queue.waiting.pop_front();
+                    queue.acquired += 1;
+
+                    let waited = started.elapsed();
+
+                    if waited > queue.max_wait {
+                        queue.max_wait = waited;
+                    }
+
+                    // The next request in line may be able to take a session too
+                    self.changed.notify_all();
+
+                    return Ok(PooledClient {
+                        pool: self,
+                        session: Some(session),
+                    });
+                }
+            }
+
+            let now = Instant::now();
+
+            if now >= deadline {
+                queue.waiting.retain(|&t| t != ticket);
+                queue.timeouts += 1;
+                let queue_depth = queue.waiting.len();
+
+                drop(queue);
+                self.changed.notify_all();
+
+                fail!(
+                    ErrorKind::YubihsmError,
+                    "timed out after {} ms waiting for a YubiHSM session ({} other requests queued)",
+                    self.timeout.as_millis(),
+                    queue_depth
+                );
+            }
+
+            queue = self.changed.wait_timeout(queue, deadline - now).unwrap().0;
+        }
+    }
+
+    /// Ping idle sessions which haven't been used for `idle_time` so they
+    /// don't time out, and reauthenticate sessions which are older than
+    /// `max_age` (or whose ping failed).
+    ///
+    /// Sessions are only taken while no requests are waiting for one.
+    pub fn keepalive(&self, idle_time: Duration, max_age: Duration) -> Result<(), Error> {
+        let started = Instant::now();
+        let mut result = Ok(());
+
+        // Each session is checked at most once per keepalive
+        for _ in 0..self.size {
+            let mut session = match self.take_for_keepalive(started, idle_time, max_age) {
+                Some(session) => session,
+                None => break,
+            };
+
+            let session_result = if session.opened_at.elapsed() >= max_age {
+                self.reauthenticate(&mut session)
+            } else {
+                self.queue.lock().unwrap().keepalives += 1;
+
+                session.client.ping().map(|_| ()).or_else(|e| {
+                    warn!(
+                        "[yubihsm] session keepalive failed, reauthenticating: {}",
+                        e
+                    );
+                    self.reauthenticate(&mut session)
+                })
+            };
+
+            // Don't retry a failed session until the next keepalive
+            session.last_used = Instant::now();
+            self.release(session);
+
+            if let Err(e) = session_result {
+                result = Err(e);
+            }
+        }
+
+        self.queue.lock().unwrap().keepalive_error = result.as_ref().err().map(|e| e.to_string());
+
+        result
+    }
+
+    /// Check the most recent keepalive succeeded
+    pub fn health(&self) -> Result<(), Error> {
+        match &self.queue.lock().unwrap().keepalive_error {
+            Some(e) => fail!(ErrorKind::YubihsmError, "session keepalive failed: {}", e),
+            None => Ok(()),
+        }
+    }
+
+    /// Get the current metrics for this pool
+    pub fn metrics(&self) -> Metrics {
+        let queue = self.queue.lock().unwrap();
+
+        Metrics {
+            sessions: self.size,
+            idle_sessions: queue.idle.len(),
+            queue_depth: queue.waiting.len(),
+            max_queue_depth: queue.max_queue_depth,
+            acquired: queue.acquired,
+            timeouts: queue.timeouts,
+            max_wait_ms: queue.max_wait.as_millis() as u64,
+            keepalives: queue.keepalives,
+            reauthentications: queue.reauthentications,
+        }
+    }
+
+    /// Take an idle session which is due for a keepalive and hasn't been used
+    /// since the keepalive started (if there is one and no requests are
+    /// waiting)
+    fn take_for_keepalive(
+        &self,
+        started: Instant,
+        idle_time: Duration,
+        max_age: Duration,
+    ) -> Option<Session> {
+        let mut queue = self.queue.lock().unwrap();
+
+        if !queue.waiting.is_empty() {
+            return None;
+        }
+
+        let index = queue.idle.iter().position(|session| {
+            session.last_used < started
+                && (started - session.last_used >= idle_time
+                    || started - session.opened_at >= max_age)
+        })?;
+
+        Some(queue.idle.swap_remove(index))
+    }
+
+    /// Replace the given session with a newly authenticated one
+    fn reauthenticate(&self, session: &mut Session) -> Result<(), Error> {
+        // Close the old session first so we don't exceed the YubiHSM2's
+        // session limit. If opening a new one fails, the client will try
+        // again when it's next used.
+        session.client = Client::create(self.connector.clone(), self.credentials.clone())?;
+        session.opened_at = Instant::now();
+        self.queue.lock().unwrap().reauthentications += 1;
+
+        session.client.connect().map_err(|e| {
+            format_err!(
+                ErrorKind::YubihsmError,
+                "couldn't reauthenticate session: {}",
+                e
+            )
+            .into()
+        })
+    }
+
+    /// Return the given session to the pool
+    fn release(&self, session: Session) {
+        self.queue.lock().unwrap().idle.push(session);
+        self.changed.notify_all();
+    }
+}
+
+/// Run the keepalive for the given pool in a background thread
+pub fn spawn_keepalive(
+    pool: &'static SessionPool,
+    config: &SessionPoolConfig,
+) -> Result<(), Error> {
+    if config.keepalive_secs == 0 {
+        return Ok(());
+    }
+
+    let interval = Duration::from_secs(config.keepalive_secs);
+    let max_age = Duration::from_secs(config.max_session_age_secs);
+
+    if interval >= SESSION_TIMEOUT {
+        fail!(
+            ErrorKind::ConfigError,
+            "YubiHSM session keepalive must be more often than every {} seconds (got {})",
+            SESSION_TIMEOUT.as_secs(),
+            config.keepalive_secs
+        );
+    }
+
+    let idle_time = keepalive_idle_time(interval);
+
+    thread::Builder::new()
+        .name("yubihsm-keepalive".to_owned())
+        .spawn(move || {
+            // Ticks are scheduled at a fixed rate (rather than `interval`
+            // after the last keepalive finished) so `idle_time` holds
+            let mut next_tick = Instant::now() + interval;
+
+            loop {
+                thread::sleep(next_tick.saturating_duration_since(Instant::now()));
+                next_tick += interval;
+
+                if let Err(e) = pool.keepalive(idle_time, max_age) {
+                    error!("[yubihsm] session keepalive failed: {}", e);
+                }
+            }
+        })?;
+
+    Ok(())
+}
+
+/// How long a session can be idle before a keepalive running every
+/// `interval` pings it.
+///
+/// A session used just after a keepalive is `interval` short of being idle
+/// long enough at the next one, so it has to be pinged while it still has
+/// `interval` to spare before the HSM's session timeout.
+fn keepalive_idle_time(interval: Duration) -> Duration {
+    SESSION_TIMEOUT - interval
+}
+
+/// Session borrowed from a `SessionPool`, which is returned when dropped
+pub struct PooledClient<'a> {
+    /// Pool the session was borrowed from
+    pool: &'a SessionPool,
+
+    /// Borrowed session
+    session: Option<Session>,
+}
+
+impl<'a> Deref for PooledClient<'a> {
+    type Target = Client;
+
+    fn deref(&self) -> &Client {
+        &self.session.as_ref().unwrap().client
+    }
+}
+
+impl<'a> Drop for PooledClient<'a> {
+    fn drop(&mut self) {
+        let mut session = self.session.take().unwrap();
+        session.last_used = Instant::now();
+        self.pool.release(session);
+    }
+}
+
+#[cfg(all(test, feature = "yubihsm-mock"))]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    /// Open a pool of the given size backed by a MockHSM
+    fn mock_pool(size: usize, timeout_ms: u64) -> Arc<SessionPool> {
+        let config = SessionPoolConfig {
+            size,
+            timeout_ms,
+            ..Default::default()
+        };
+
+        let pool = SessionPool::open(&Connector::mockhsm(), &Credentials::default(), &config);
+        Arc::new(pool.unwrap())
+    }
+
+    /// Wait until the given number of requests are queued
+    fn wait_for_queue_depth(pool: &SessionPool, queue_depth: usize) {
+        while pool.metrics().queue_depth != queue_depth {
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_size() {
+        let config = SessionPoolConfig {
+            size: 0,
+            ..Default::default()
+        };
+
+        assert!(
+            SessionPool::open(&Connector::mockhsm(), &Credentials::default(), &config).is_err()
+        );
+    }
+
+    #[test]
+    fn sessions_are_independent() {
+        let pool = mock_pool(2, 1000);
+        let first = pool.acquire().unwrap();
+        let second = pool.acquire().unwrap();
+
+        first.device_info().unwrap();
+        second.device_info().unwrap();
+
+        let metrics = pool.metrics();
+        assert_eq!(metrics.sessions, 2);
+        assert_eq!(metrics.idle_sessions, 0);
+        assert_eq!(metrics.acquired, 2);
+
+        drop(first);
+        drop(second);
+        assert_eq!(pool.metrics().idle_sessions, 2);
+    }
+
+    #[test]
+    fn times_out_when_busy() {
+        let pool = mock_pool(1, 50);
+        let _session = pool.acquire().unwrap();
+
+        let err = pool.acquire().err().unwrap();
+        assert!(err.to_string().contains("timed out after 50 ms"));
+
+        let metrics = pool.metrics();
+        assert_eq!(metrics.timeouts, 1);
+        assert_eq!(metrics.queue_depth, 0);
+        assert_eq!(metrics.max_queue_depth, 1);
+    }
+
+    #[test]
+    fn serves_requests_in_order() {
+        let pool = mock_pool(1, 10_000);
+        let session = pool.acquire().unwrap();
+        let order = Arc::new(Mutex::new(vec![]));
+
+        let waiters = (0..3)
+            .map(|i| {
+                let waiter_pool = Arc::clone(&pool);
+                let order = Arc::clone(&order);
+                let waiter = thread::spawn(move || {
+                    let _session = waiter_pool.acquire().unwrap();
+                    order.lock().unwrap().push(i);
+                });
+
+                wait_for_queue_depth(&pool, i + 1);
+                waiter
+            })
+            .collect::<Vec<_>>();
+
+        drop(session);
+
+        for waiter in waiters {
+            waiter.join().unwrap();
+        }
+
+        assert_eq!(*order.lock().unwrap(), vec![0, 1, 2]);
+        assert_eq!(pool.metrics().max_queue_depth, 3);
+    }
+
+    #[test]
+    fn keepalive_pings_idle_sessions() {
+        let pool = mock_pool(2, 1000);
+        let session = pool.acquire().unwrap();
+
+        // Only the idle session is pinged
+        pool.keepalive(Duration::from_secs(0), Duration::from_secs(3600))
+            .unwrap();
+
+        let metrics = pool.metrics();
+        assert_eq!(metrics.keepalives, 1);
+        assert_eq!(metrics.reauthentications, 0);
+        assert!(pool.health().is_ok());
+
+        drop(session);
+
+        // Neither session has been idle long enough
+        pool.keepalive(Duration::from_secs(3600), Duration::from_secs(3600))
+            .unwrap();
+
+        assert_eq!(pool.metrics().keepalives, 1);
+    }
+
+    #[test]
+    fn keepalive_reauthenticates_old_sessions() {
+        let pool = mock_pool(2, 1000);
+
+        pool.keepalive(Duration::from_secs(3600), Duration::from_secs(0))
+            .unwrap();
+
+        let metrics = pool.metrics();
+        assert_eq!(metrics.reauthentications, 2);
+        assert_eq!(metrics.idle_sessions, 2);
+
+        pool.acquire().unwrap().device_info().unwrap();
+    }
+
+    #[test]
+    fn keepalive_pings_sessions_before_they_time_out() {
+        let just_after = Duration::from_millis(1);
+
+        for secs in 1..SESSION_TIMEOUT.as_secs() {
+            let interval = Duration::from_secs(secs);
+            let idle_time = keepalive_idle_time(interval);
+
+            // Session used just after a keepalive: it's idle for
+            // `n * interval - just_after` at the `n`th keepalive after that
+            let idle_when_pinged = (1..)
+                .map(|n| interval * n - just_after)
+                .find(|idle| *idle >= idle_time)
+                .unwrap();
+
+            assert!(
+                idle_when_pinged < SESSION_TIMEOUT,
+                "keepalive_secs = {}: session idle for {:?} before being pinged",
+                secs,
+                idle_when_pinged
+            );
+        }
+    }
+}
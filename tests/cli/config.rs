@@ -0,0 +1,226 @@
+//! Integration tests for the `config` subcommands
+
+use crate::cli;
+use std::{fs, str};
+use tempfile::TempDir;
+
+#[test]
+fn config_check_reports_all_problems() {
+    let tempdir = TempDir::new().unwrap();
+    let config_path = tempdir.path().join("tmkms.toml");
+
+    fs::write(
+        &config_path,
+        format!(
+            r#"
+            [[chain]]
+            id = "test_chain_id"
+            key_format = {{ type = "hex" }}
+            state_file = "{}"
+
+            [[validator]]
+            addr = "tcp://127.0.0.1:26658"
+            chain_id = "unknown_chain_id"
+            secret_key = "{}"
+
+            [[providers.softsign]]
+            chain_ids = ["other_chain_id"]
+            key_format = "base64"
+            path = "{}"
+            "#,
+            tempdir.path().join("missing/state.json").display(),
+            tempdir.path().join("identity.key").display(),
+            tempdir.path().join("consensus.key").display(),
+        ),
+    )
+    .unwrap();
+
+    let cmd_out = cli::run(&["config", "check", "-c", config_path.to_str().unwrap()]);
+    assert_eq!(cmd_out.status.code().unwrap(), 1);
+
+    let stdout = str::from_utf8(&cmd_out.stdout).unwrap();
+    assert!(stdout.contains("no peer ID"));
+
+    let stderr = str::from_utf8(&cmd_out.stderr).unwrap();
+    assert!(stderr.contains("unknown chain unknown_chain_id"));
+    assert!(stderr.contains("identity.key does not exist"));
+    assert!(stderr.contains("couldn't read key"));
+    assert!(stderr.contains("unknown chain other_chain_id"));
+    assert!(stderr.contains("chain test_chain_id: no signing keys"));
+    assert!(stderr.contains("missing isn't writable"));
+    assert!(stderr.contains("found 6 error(s) and 1 warning(s)"));
+}
+
+#[test]
+fn config_check_reports_malformed_json_key_with_other_problems() {
+    let tempdir = TempDir::new().unwrap();
+    let config_path = tempdir.path().join("tmkms.toml");
+    let key_path = tempdir.path().join("k.json");
+    fs::write(&key_path, "not json").unwrap();
+
+    fs::write(
+        &config_path,
+        format!(
+            r#"
+            [[chain]]
+            id = "test_chain_id"
+            key_format = {{ type = "hex" }}
+            state_file = "{}"
+
+            [[providers.softsign]]
+            chain_ids = ["test_chain_id"]
+            key_format = "json"
+            path = "{}"
+            "#,
+            tempdir.path().join("missing/state.json").display(),
+            key_path.display(),
+        ),
+    )
+    .unwrap();
+
+    let cmd_out = cli::run(&["config", "check", "-c", config_path.to_str().unwrap()]);
+    assert_eq!(cmd_out.status.code().unwrap(), 1);
+
+    let stderr = str::from_utf8(&cmd_out.stderr).unwrap();
+    assert!(stderr.contains("k.json"));
+    assert!(stderr.contains("missing isn't writable"));
+    assert!(stderr.contains("found 2 error(s)"));
+}
+
+#[test]
+fn config_check_rejects_multiple_softsign_providers() {
+    let tempdir = TempDir::new().unwrap();
+    let config_path = tempdir.path().join("tmkms.toml");
+
+    for key in &["a.key", "b.key"] {
+        cli::run_successfully(&[
+            "softsign",
+            "keygen",
+            tempdir.path().join(key).to_str().unwrap(),
+        ]);
+    }
+
+    fs::write(
+        &config_path,
+        format!(
+            r#"
+            [[chain]]
+            id = "chain-a"
+            key_format = {{ type = "hex" }}
+            state_file = "{dir}/chain-a.json"
+
+            [[chain]]
+            id = "chain-b"
+            key_format = {{ type = "hex" }}
+            state_file = "{dir}/chain-b.json"
+
+            [[providers.softsign]]
+            chain_ids = ["chain-a"]
+            key_format = "base64"
+            path = "{dir}/a.key"
+
+            [[providers.softsign]]
+            chain_ids = ["chain-b"]
+            key_format = "base64"
+            path = "{dir}/b.key"
+            "#,
+            dir = tempdir.path().display(),
+        ),
+    )
+    .unwrap();
+
+    let cmd_out = cli::run(&["config", "check", "-c", config_path.to_str().unwrap()]);
+    assert_eq!(cmd_out.status.code().unwrap(), 1);
+
+    let stderr = str::from_utf8(&cmd_out.stderr).unwrap();
+    assert!(stderr.contains("found 2 [[providers.softsign]] sections"));
+}
+
+#[test]
+fn config_check_accepts_valid_config() {
+    let tempdir = TempDir::new().unwrap();
+    let output_dir = tempdir.path().to_str().unwrap();
+    let config_path = tempdir.path().join("tmkms.toml");
+
+    cli::run_successfully(&["init", "--provider", "softsign", output_dir]);
+    cli::run_successfully(&[
+        "softsign",
+        "keygen",
+        tempdir
+            .path()
+            .join("secrets/consensus.key")
+            .to_str()
+            .unwrap(),
+    ]);
+
+    let cmd_out = cli::run_successfully(&["config", "check", "-c", config_path.to_str().unwrap()]);
+    assert!(str::from_utf8(&cmd_out.stdout)
+        .unwrap()
+        .contains("configuration is valid"));
+}
+
+/// Write a config file (with a softsign key password to be redacted)
+/// including `conf.d/*.toml`, with a chain in each of `chain_ids` defined in
+/// its own included file
+fn write_config_with_includes(tempdir: &TempDir, chain_ids: &[&str]) -> String {
+    let config_path = tempdir.path().join("tmkms.toml");
+    let conf_dir = tempdir.path().join("conf.d");
+    fs::create_dir(&conf_dir).unwrap();
+
+    fs::write(
+        &config_path,
+        r#"
+        include = ["conf.d/*.toml"]
+
+        [[providers.softsign]]
+        chain_ids = []
+        key_format = "encrypted"
+        path = "consensus.json"
+        password = "hunter2"
+        "#,
+    )
+    .unwrap();
+
+    for (i, chain_id) in chain_ids.iter().enumerate() {
+        fs::write(
+            conf_dir.join(format!("{}.toml", i)),
+            format!(
+                r#"
+                [[chain]]
+                id = "{}"
+                key_format = {{ type = "hex" }}
+                "#,
+                chain_id
+            ),
+        )
+        .unwrap();
+    }
+
+    config_path.to_str().unwrap().to_owned()
+}
+
+#[test]
+fn config_show_merges_included_files() {
+    let tempdir = TempDir::new().unwrap();
+    let config_path = write_config_with_includes(&tempdir, &["chain-a", "chain-b"]);
+
+    let cmd_out = cli::run_successfully(&["config", "show", "-c", &config_path]);
+    let stdout = str::from_utf8(&cmd_out.stdout).unwrap();
+
+    assert!(stdout.contains("chain-a"));
+    assert!(stdout.contains("chain-b"));
+    assert!(stdout.contains("<redacted>"));
+    assert!(!stdout.contains("hunter2"));
+}
+
+#[test]
+fn config_rejects_duplicates_in_included_files() {
+    let tempdir = TempDir::new().unwrap();
+    let config_path = write_config_with_includes(&tempdir, &["chain-a", "chain-a"]);
+
+    let cmd_out = cli::run(&["config", "check", "-c", &config_path]);
+    assert_eq!(cmd_out.status.code().unwrap(), 1);
+    assert!(str::from_utf8(&cmd_out.stderr)
+        .unwrap()
+        .contains("duplicate [[chain]] `chain-a`"));
+}
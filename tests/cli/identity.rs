@@ -0,0 +1,89 @@
+//! Integration tests for the `identity` subcommands
+
+use crate::cli;
+use std::{fs, str};
+use tempfile::TempDir;
+
+#[test]
+fn identity_generate_and_show() {
+    let tempdir = TempDir::new().unwrap();
+    let key_path = tempdir.path().join("secret_connection.key");
+    let key_path = key_path.to_str().unwrap();
+
+    let cmd_out = cli::run_successfully(&["identity", "generate", key_path]);
+    let generate_stdout = str::from_utf8(&cmd_out.stdout).unwrap().to_owned();
+    assert!(generate_stdout.contains("Generated"));
+
+    let cmd_out = cli::run_successfully(&["identity", "show", key_path]);
+    let node_id = str::from_utf8(&cmd_out.stdout).unwrap().trim().to_owned();
+    assert_eq!(node_id.len(), 40);
+    assert!(generate_stdout.contains(&node_id));
+}
+
+#[test]
+fn identity_generate_refuses_to_overwrite() {
+    let tempdir = TempDir::new().unwrap();
+    let key_path = tempdir.path().join("secret_connection.key");
+    let key_path = key_path.to_str().unwrap();
+
+    cli::run_successfully(&["identity", "generate", key_path]);
+    let original_key = fs::read(key_path).unwrap();
+
+    let status_code = cli::run(&["identity", "generate", key_path])
+        .status
+        .code()
+        .unwrap();
+
+    assert_eq!(status_code, 1);
+    assert_eq!(fs::read(key_path).unwrap(), original_key);
+}
+
+/// Run `tmkms start` with a single validator configured with `validator`
+/// (plus a `secret_key` which doesn't exist), checking it refuses to start
+fn assert_start_refuses_missing_identity(validator: &str) {
+    let tempdir = TempDir::new().unwrap();
+    let key_path = tempdir.path().join("secret_connection.key");
+    let config_path = tempdir.path().join("tmkms.toml");
+
+    fs::write(
+        &config_path,
+        format!(
+            r#"
+            [[chain]]
+            id = "test_chain_id"
+            key_format = {{ type = "hex" }}
+
+            [[validator]]
+            {}
+            chain_id = "test_chain_id"
+            reconnect = false
+            secret_key = "{}"
+
+            [providers]
+            "#,
+            validator,
+            key_path.display()
+        ),
+    )
+    .unwrap();
+
+    let cmd_out = cli::run(&["start", "-c", config_path.to_str().unwrap()]);
+    assert_eq!(cmd_out.status.code().unwrap(), 1);
+    assert!(str::from_utf8(&cmd_out.stderr)
+        .unwrap()
+        .contains("does not exist"));
+    assert!(!key_path.exists());
+}
+
+#[test]
+fn start_refuses_missing_identity() {
+    assert_start_refuses_missing_identity(r#"addr = "tcp://127.0.0.1:1""#);
+}
+
+#[test]
+fn start_refuses_missing_identity_for_unix_secret_connection() {
+    assert_start_refuses_missing_identity(
+        r#"addr = "unix:///nonexistent/tmkms-test.sock"
+            secret_connection = {}"#,
+    );
+}
@@ -0,0 +1,94 @@
+//! Integration tests for the `init` subcommand
+
+use crate::cli;
+use std::{fs, str};
+use tempfile::TempDir;
+use tmkms::{
+    chain::{self, Chain},
+    config::KmsConfig,
+    keyring,
+};
+
+#[test]
+fn init_generates_usable_config() {
+    let tempdir = TempDir::new().unwrap();
+    let output_dir = tempdir.path().join("kms");
+    let config_path = output_dir.join("tmkms.toml");
+
+    let cmd_out = cli::run_successfully(&[
+        "init",
+        "--network",
+        "cosmoshub",
+        "--network",
+        "irishub",
+        "--provider",
+        "softsign",
+        output_dir.to_str().unwrap(),
+    ]);
+    let init_stdout = str::from_utf8(&cmd_out.stdout).unwrap().to_owned();
+
+    let config = fs::read_to_string(&config_path).unwrap();
+    assert!(config.contains(r#"consensus_key_prefix = "cosmosvalconspub""#));
+    assert!(config.contains(r#"consensus_key_prefix = "icp""#));
+
+    cli::run_successfully(&[
+        "softsign",
+        "keygen",
+        output_dir.join("secrets/consensus.key").to_str().unwrap(),
+    ]);
+
+    let cmd_out = cli::run_successfully(&[
+        "identity",
+        "show",
+        output_dir
+            .join("secrets/kms-identity.key")
+            .to_str()
+            .unwrap(),
+    ]);
+    let node_id = str::from_utf8(&cmd_out.stdout).unwrap().trim().to_owned();
+    assert!(init_stdout.contains(&node_id));
+
+    let cmd_out = cli::run_successfully(&["state", "show", "-c", config_path.to_str().unwrap()]);
+    let state_stdout = str::from_utf8(&cmd_out.stdout).unwrap();
+    assert!(state_stdout.contains("cosmoshub-3"));
+    assert!(state_stdout.contains("irishub"));
+
+    let cmd_out = cli::run_successfully(&["config", "check", "-c", config_path.to_str().unwrap()]);
+    assert!(str::from_utf8(&cmd_out.stdout)
+        .unwrap()
+        .contains("configuration is valid"));
+
+    // Load the keyring the same way `tmkms start` does
+    let config = KmsConfig::load(&config_path).unwrap();
+    let mut registry = chain::Registry::default();
+
+    for chain_config in &config.chain {
+        registry
+            .register_chain(Chain::from_config(chain_config).unwrap())
+            .unwrap();
+    }
+
+    keyring::load_config(&mut registry, &config.providers).unwrap();
+
+    for chain in registry.chains() {
+        assert!(chain.keyring.default_pubkey().is_ok());
+    }
+}
+
+#[test]
+fn init_refuses_to_overwrite() {
+    let tempdir = TempDir::new().unwrap();
+    let output_dir = tempdir.path().to_str().unwrap();
+
+    cli::run_successfully(&["init", "--provider", "softsign", output_dir]);
+    let config_path = tempdir.path().join("tmkms.toml");
+    let original_config = fs::read(&config_path).unwrap();
+
+    let status_code = cli::run(&["init", "--provider", "softsign", output_dir])
+        .status
+        .code()
+        .unwrap();
+
+    assert_eq!(status_code, 1);
+    assert_eq!(fs::read(&config_path).unwrap(), original_config);
+}
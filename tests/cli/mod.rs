@@ -3,11 +3,19 @@
 use std::{
     ffi::OsStr,
     io::{self, Write},
-    process::{Command, Output},
+    process::{Command, Output, Stdio},
 };
 
 use super::KMS_EXE_PATH;
 
+#[cfg(feature = "softsign")]
+mod config;
+mod identity;
+#[cfg(feature = "softsign")]
+mod init;
+#[cfg(feature = "softsign")]
+mod softsign;
+mod state;
 #[cfg(feature = "yubihsm")]
 mod yubihsm;
 
@@ -20,6 +28,31 @@ where
     Command::new(KMS_EXE_PATH).args(args).output().unwrap()
 }
 
+/// Run the `tmkms` CLI command with the given arguments, writing `input` to
+/// its STDIN
+pub fn run_with_stdin<I, S>(args: I, input: &str) -> Output
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let mut child = Command::new(KMS_EXE_PATH)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+
+    child.wait_with_output().unwrap()
+}
+
 /// Run the `tmkms` CLI command with the expectation that it will exit successfully,
 /// panicking and printing stdout/stderr if it does not
 pub fn run_successfully<I, S>(args: I) -> Output
@@ -0,0 +1,225 @@
+//! Integration tests for the `softsign` subcommands
+
+use crate::cli;
+use std::{env, fs, str};
+use tempfile::TempDir;
+
+#[test]
+fn keygen_encrypts_keys() {
+    let tempdir = TempDir::new().unwrap();
+    let key_path = tempdir.path().join("consensus.json");
+    let config_path = tempdir.path().join("tmkms.toml");
+
+    env::set_var(
+        "TMKMS_TEST_SOFTSIGN_PASSWORD",
+        "correct horse battery staple",
+    );
+    env::set_var("TMKMS_TEST_SOFTSIGN_WRONG_PASSWORD", "hunter2");
+
+    cli::run_successfully(&[
+        "softsign",
+        "keygen",
+        "--encrypt",
+        "--password-env",
+        "TMKMS_TEST_SOFTSIGN_PASSWORD",
+        key_path.to_str().unwrap(),
+    ]);
+
+    let check_with_password = |password_var: &str| {
+        fs::write(
+            &config_path,
+            format!(
+                r#"
+                [[chain]]
+                id = "test_chain_id"
+                key_format = {{ type = "hex" }}
+                state_file = "{}"
+
+                [[providers.softsign]]
+                chain_ids = ["test_chain_id"]
+                key_format = "encrypted"
+                path = "{}"
+                password = "${{{}}}"
+                "#,
+                tempdir.path().join("state.json").display(),
+                key_path.display(),
+                password_var
+            ),
+        )
+        .unwrap();
+
+        cli::run(&["config", "check", "-c", config_path.to_str().unwrap()])
+    };
+
+    let cmd_out = check_with_password("TMKMS_TEST_SOFTSIGN_PASSWORD");
+    assert_eq!(cmd_out.status.code().unwrap(), 0);
+
+    let cmd_out = check_with_password("TMKMS_TEST_SOFTSIGN_WRONG_PASSWORD");
+    assert_eq!(cmd_out.status.code().unwrap(), 1);
+    assert!(str::from_utf8(&cmd_out.stderr)
+        .unwrap()
+        .contains("couldn't decrypt key (wrong password?)"));
+}
+
+#[test]
+fn recover_derives_test_vector() {
+    let tempdir = TempDir::new().unwrap();
+    let key_path = tempdir.path().join("consensus.key");
+
+    let cmd_out = cli::run_with_stdin(
+        &[
+            "softsign",
+            "recover",
+            "--derivation-path",
+            "/tmkms/softsign/cosmoshub-3",
+            key_path.to_str().unwrap(),
+        ],
+        "legal winner thank year wave sausage worth useful legal winner thank year \
+         wave sausage worth useful legal winner thank year wave sausage worth title\n",
+    );
+
+    assert_eq!(cmd_out.status.code().unwrap(), 0);
+    assert_eq!(
+        fs::read_to_string(&key_path).unwrap(),
+        "q94aayFsCuef8jw6J3HNXQfULLn8lRoic5MvJ6+xNDA="
+    );
+}
+
+#[test]
+fn keygen_mnemonic_can_be_recovered() {
+    let tempdir = TempDir::new().unwrap();
+    let key_path = tempdir.path().join("consensus.key");
+    let recovered_key_path = tempdir.path().join("recovered.key");
+
+    let cmd_out = cli::run_successfully(&[
+        "softsign",
+        "keygen",
+        "--mnemonic",
+        key_path.to_str().unwrap(),
+    ]);
+
+    // The phrase is printed as two indented groups of 12 words
+    let phrase = str::from_utf8(&cmd_out.stdout)
+        .unwrap()
+        .lines()
+        .filter(|line| line.starts_with("    "))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    assert_eq!(phrase.split_whitespace().count(), 24);
+    assert!(str::from_utf8(&cmd_out.stdout)
+        .unwrap()
+        .contains("Wrote mnemonic-derived Ed25519 private key"));
+
+    let cmd_out = cli::run_with_stdin(
+        &["softsign", "recover", recovered_key_path.to_str().unwrap()],
+        &format!("{}\n", phrase),
+    );
+
+    assert_eq!(cmd_out.status.code().unwrap(), 0);
+    assert_eq!(
+        fs::read(&key_path).unwrap(),
+        fs::read(&recovered_key_path).unwrap()
+    );
+}
+
+#[test]
+fn keygen_requires_encrypt_for_password_options() {
+    let tempdir = TempDir::new().unwrap();
+    let key_path = tempdir.path().join("consensus.key");
+
+    let cmd_out = cli::run(&[
+        "softsign",
+        "keygen",
+        "--password-env",
+        "TMKMS_TEST_SOFTSIGN_PASSWORD",
+        key_path.to_str().unwrap(),
+    ]);
+
+    assert_eq!(cmd_out.status.code().unwrap(), 1);
+    assert!(!key_path.exists());
+}
+
+#[test]
+fn export_writes_priv_validator_key_and_state() {
+    let tempdir = TempDir::new().unwrap();
+    let key_path = tempdir.path().join("consensus.key");
+    let config_path = tempdir.path().join("tmkms.toml");
+    let output_dir = tempdir.path().join("config");
+    let output_path = output_dir.join("priv_validator_key.json");
+    let state_path = tempdir.path().join("priv_validator_state.json");
+
+    fs::create_dir(&output_dir).unwrap();
+
+    fs::write(
+        &config_path,
+        format!(
+            r#"
+            [[chain]]
+            id = "test_chain_id"
+            key_format = {{ type = "hex" }}
+            state_file = "{}"
+            "#,
+            tempdir.path().join("state.json").display(),
+        ),
+    )
+    .unwrap();
+
+    cli::run_with_stdin(
+        &["softsign", "recover", key_path.to_str().unwrap()],
+        "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon \
+         abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon \
+         abandon abandon abandon art\n",
+    );
+
+    cli::run_successfully(&[
+        "state",
+        "set",
+        "-c",
+        config_path.to_str().unwrap(),
+        "--height",
+        "1234",
+        "test_chain_id",
+    ]);
+
+    cli::run_successfully(&[
+        "softsign",
+        "export",
+        "-c",
+        config_path.to_str().unwrap(),
+        "--state",
+        "test_chain_id",
+        "--state-file",
+        state_path.to_str().unwrap(),
+        key_path.to_str().unwrap(),
+        output_path.to_str().unwrap(),
+    ]);
+
+    let key_json: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&output_path).unwrap()).unwrap();
+
+    // Public key from the `derives_test_vectors` unit test
+    assert_eq!(
+        key_json["pub_key"]["value"],
+        "V4ETKnKOXVIvE8m9JVIDbuOr2AwumEQOo/VaN4NMkT0="
+    );
+
+    let state_json: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&state_path).unwrap()).unwrap();
+
+    assert_eq!(state_json["height"], "1234");
+
+    // The exported key can be imported again
+    let imported_key_path = tempdir.path().join("imported.key");
+    cli::run_successfully(&[
+        "softsign",
+        "import",
+        output_path.to_str().unwrap(),
+        imported_key_path.to_str().unwrap(),
+    ]);
+
+    assert_eq!(
+        fs::read(&key_path).unwrap(),
+        fs::read(&imported_key_path).unwrap()
+    );
+}
@@ -0,0 +1,197 @@
+//! Integration tests for the `state` subcommands
+
+use crate::cli;
+use std::{fs, path::Path, str};
+use tempfile::TempDir;
+use tmkms::chain::state::Lock;
+
+/// Write a config file with a single chain whose state is kept in `tempdir`
+fn write_config(tempdir: &TempDir) -> String {
+    let config_path = tempdir.path().join("tmkms.toml");
+    let state_path = tempdir.path().join("priv_validator_state.json");
+
+    fs::write(
+        &config_path,
+        format!(
+            r#"
+            [[chain]]
+            id = "test_chain_id"
+            key_format = {{ type = "hex" }}
+            state_file = "{}"
+
+            [providers]
+            "#,
+            state_path.display()
+        ),
+    )
+    .unwrap();
+
+    config_path.to_str().unwrap().to_owned()
+}
+
+/// Read the height/round/step from the given state file
+fn read_state(path: &Path) -> (String, String, i64) {
+    let state: serde_json::Value = serde_json::from_slice(&fs::read(path).unwrap()).unwrap();
+
+    (
+        state["height"].as_str().unwrap().to_owned(),
+        state["round"].as_str().unwrap().to_owned(),
+        state["step"].as_i64().unwrap(),
+    )
+}
+
+#[test]
+fn state_set_only_moves_forward() {
+    let tempdir = TempDir::new().unwrap();
+    let config_path = write_config(&tempdir);
+    let state_path = tempdir.path().join("priv_validator_state.json");
+
+    cli::run_successfully(&[
+        "state",
+        "set",
+        "-c",
+        &config_path,
+        "--height",
+        "100",
+        "--round",
+        "1",
+        "--step",
+        "2",
+        "test_chain_id",
+    ]);
+    assert_eq!(read_state(&state_path), ("100".into(), "1".into(), 2));
+
+    let cmd_out = cli::run(&[
+        "state",
+        "set",
+        "-c",
+        &config_path,
+        "--height",
+        "99",
+        "test_chain_id",
+    ]);
+    assert_eq!(cmd_out.status.code().unwrap(), 1);
+    assert_eq!(read_state(&state_path), ("100".into(), "1".into(), 2));
+
+    cli::run_successfully(&[
+        "state",
+        "set",
+        "-c",
+        &config_path,
+        "--height",
+        "99",
+        "--force",
+        "test_chain_id",
+    ]);
+    assert_eq!(read_state(&state_path), ("99".into(), "0".into(), 0));
+
+    let cmd_out = cli::run_successfully(&["state", "show", "-c", &config_path]);
+    assert!(str::from_utf8(&cmd_out.stdout).unwrap().contains("99/0/0"));
+
+    // A missing height is an error, rather than rewinding to 0
+    let cmd_out = cli::run(&[
+        "state",
+        "set",
+        "-c",
+        &config_path,
+        "--force",
+        "test_chain_id",
+    ]);
+    assert_eq!(cmd_out.status.code().unwrap(), 1);
+    assert!(str::from_utf8(&cmd_out.stderr).unwrap().contains("Usage"));
+    assert_eq!(read_state(&state_path), ("99".into(), "0".into(), 0));
+}
+
+#[test]
+fn state_reset_requires_force() {
+    let tempdir = TempDir::new().unwrap();
+    let config_path = write_config(&tempdir);
+    let state_path = tempdir.path().join("priv_validator_state.json");
+
+    cli::run_successfully(&[
+        "state",
+        "set",
+        "-c",
+        &config_path,
+        "--height",
+        "100",
+        "test_chain_id",
+    ]);
+
+    let cmd_out = cli::run(&["state", "reset", "-c", &config_path, "test_chain_id"]);
+    assert_eq!(cmd_out.status.code().unwrap(), 1);
+    assert_eq!(read_state(&state_path).0, "100");
+
+    cli::run_successfully(&[
+        "state",
+        "reset",
+        "-c",
+        &config_path,
+        "--force",
+        "test_chain_id",
+    ]);
+    assert_eq!(read_state(&state_path).0, "0");
+}
+
+#[test]
+fn state_import_and_export_tendermint() {
+    let tempdir = TempDir::new().unwrap();
+    let config_path = write_config(&tempdir);
+    let import_path = tempdir.path().join("tendermint_state.json");
+    let export_path = tempdir.path().join("exported_state.json");
+
+    fs::write(
+        &import_path,
+        r#"{"height": "1234", "round": "0", "step": 3, "signature": "ZXhhbXBsZQ=="}"#,
+    )
+    .unwrap();
+
+    cli::run_successfully(&[
+        "state",
+        "import",
+        "-c",
+        &config_path,
+        "--tendermint",
+        "test_chain_id",
+        import_path.to_str().unwrap(),
+    ]);
+
+    let state_path = tempdir.path().join("priv_validator_state.json");
+    assert_eq!(read_state(&state_path), ("1234".into(), "0".into(), 2));
+
+    cli::run_successfully(&[
+        "state",
+        "export",
+        "-c",
+        &config_path,
+        "--tendermint",
+        "-o",
+        export_path.to_str().unwrap(),
+        "test_chain_id",
+    ]);
+    assert_eq!(read_state(&export_path), ("1234".into(), "0".into(), 3));
+}
+
+#[test]
+fn state_refuses_to_change_locked_state() {
+    let tempdir = TempDir::new().unwrap();
+    let config_path = write_config(&tempdir);
+    let state_path = tempdir.path().join("priv_validator_state.json");
+
+    let _lock = Lock::acquire(&state_path).unwrap();
+
+    let cmd_out = cli::run(&[
+        "state",
+        "set",
+        "-c",
+        &config_path,
+        "--height",
+        "100",
+        "test_chain_id",
+    ]);
+    assert_eq!(cmd_out.status.code().unwrap(), 1);
+    assert!(str::from_utf8(&cmd_out.stderr)
+        .unwrap()
+        .contains("is the KMS running?"));
+    assert!(!state_path.exists());
+}
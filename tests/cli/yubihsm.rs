@@ -6,7 +6,11 @@ pub const KMS_CONFIG_PATH: &str = "tests/support/kms_yubihsm_mock.toml";
 #[allow(dead_code)]
 pub const PRIV_VALIDATOR_CONFIG_PATH: &str = "tests/support/priv_validator_mock.json";
 
+mod backup;
 // This test requires USB access to a YubiHSM2
 #[cfg(not(feature = "yubihsm-mock"))]
 mod detect;
 mod keys;
+mod log;
+mod restore;
+mod setup;
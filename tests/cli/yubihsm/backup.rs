@@ -0,0 +1,53 @@
+//! Integration tests for the `yubihsm backup` subcommand
+
+use crate::cli;
+use std::{fs, str};
+use tempfile::TempDir;
+
+#[test]
+fn backup_without_wrap_key_test() {
+    let dir = TempDir::new().unwrap();
+    let archive_path = dir.path().join("backup.json");
+
+    #[allow(unused_mut)]
+    let mut args = vec![
+        "yubihsm",
+        "backup",
+        "-w",
+        "42",
+        archive_path.to_str().unwrap(),
+    ];
+
+    #[cfg(feature = "yubihsm-mock")]
+    args.extend_from_slice(&["-c", super::KMS_CONFIG_PATH]);
+
+    let out = cli::run(args.as_slice());
+    assert_eq!(out.status.code().unwrap(), 1);
+
+    let stderr = str::from_utf8(&out.stderr).unwrap();
+    assert!(stderr.contains("couldn't find wrap key 0x002a"));
+    assert!(!archive_path.exists());
+}
+
+#[test]
+fn backup_refuses_to_overwrite_archive_test() {
+    let dir = TempDir::new().unwrap();
+    let archive_path = dir.path().join("backup.json");
+    fs::write(&archive_path, "previous backup").unwrap();
+
+    #[allow(unused_mut)]
+    let mut args = vec!["yubihsm", "backup", archive_path.to_str().unwrap()];
+
+    #[cfg(feature = "yubihsm-mock")]
+    args.extend_from_slice(&["-c", super::KMS_CONFIG_PATH]);
+
+    let out = cli::run(args.as_slice());
+    assert_eq!(out.status.code().unwrap(), 1);
+
+    let stderr = str::from_utf8(&out.stderr).unwrap();
+    assert!(stderr.contains("already exists"));
+    assert_eq!(
+        fs::read_to_string(&archive_path).unwrap(),
+        "previous backup"
+    );
+}
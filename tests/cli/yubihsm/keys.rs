@@ -1,7 +1,9 @@
 //! Integration tests for the `yubihsm keys` subcommand
 
+mod delete;
 mod generate;
 mod import;
+mod info;
 mod list;
 
 pub use super::{KMS_CONFIG_PATH, PRIV_VALIDATOR_CONFIG_PATH};
@@ -0,0 +1,47 @@
+//! Integration tests for the `yubihsm keys delete` subcommand
+
+use crate::cli;
+use std::{fs, str};
+use tempfile::TempDir;
+
+#[test]
+fn keys_delete_requires_backup_test() {
+    #[allow(unused_mut)]
+    let mut args = vec!["yubihsm", "keys", "delete", "1"];
+
+    #[cfg(feature = "yubihsm-mock")]
+    args.extend_from_slice(&["-c", super::KMS_CONFIG_PATH]);
+
+    let out = cli::run(args.as_slice());
+    assert_eq!(out.status.code().unwrap(), 1);
+
+    let stderr = str::from_utf8(&out.stderr).unwrap();
+    assert!(stderr.contains("without a backup"));
+}
+
+#[test]
+fn keys_delete_refuses_to_overwrite_backup_test() {
+    let tempdir = TempDir::new().unwrap();
+    let backup_path = tempdir.path().join("key-0x0001.enc");
+    fs::write(&backup_path, "existing backup").unwrap();
+
+    #[allow(unused_mut)]
+    let mut args = vec![
+        "yubihsm",
+        "keys",
+        "delete",
+        "1",
+        "--backup",
+        backup_path.to_str().unwrap(),
+    ];
+
+    #[cfg(feature = "yubihsm-mock")]
+    args.extend_from_slice(&["-c", super::KMS_CONFIG_PATH]);
+
+    let out = cli::run(args.as_slice());
+    assert_eq!(out.status.code().unwrap(), 1);
+
+    let stderr = str::from_utf8(&out.stderr).unwrap();
+    assert!(stderr.contains("already exists"));
+    assert_eq!(fs::read_to_string(&backup_path).unwrap(), "existing backup");
+}
@@ -0,0 +1,19 @@
+//! Integration tests for the `yubihsm keys info` subcommand
+
+use crate::cli;
+use std::str;
+
+#[test]
+fn keys_info_missing_key_test() {
+    #[allow(unused_mut)]
+    let mut args = vec!["yubihsm", "keys", "info", "1"];
+
+    #[cfg(feature = "yubihsm-mock")]
+    args.extend_from_slice(&["-c", super::KMS_CONFIG_PATH]);
+
+    let out = cli::run(args.as_slice());
+    assert_eq!(out.status.code().unwrap(), 1);
+
+    let stderr = str::from_utf8(&out.stderr).unwrap();
+    assert!(stderr.contains("couldn't get object info for key 0x0001"));
+}
@@ -19,3 +19,17 @@ fn keys_command_test() {
     let stderr = str::from_utf8(&out.stderr).unwrap().trim().to_owned();
     assert!(stderr.contains("no keys in this YubiHSM"));
 }
+
+#[test]
+fn keys_list_json_test() {
+    #[allow(unused_mut)]
+    let mut args = vec!["yubihsm", "keys", "list", "--json"];
+
+    #[cfg(feature = "yubihsm-mock")]
+    args.extend_from_slice(&["-c", super::KMS_CONFIG_PATH]);
+
+    let out = cli::run_successfully(args.as_slice());
+
+    let keys: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(keys, serde_json::json!([]));
+}
@@ -0,0 +1,18 @@
+//! Integration tests for the `yubihsm log` subcommand
+
+use crate::cli;
+use std::str;
+
+#[test]
+fn log_command_test() {
+    #[allow(unused_mut)]
+    let mut args = vec!["yubihsm", "log"];
+
+    #[cfg(feature = "yubihsm-mock")]
+    args.extend_from_slice(&["-c", super::KMS_CONFIG_PATH]);
+
+    let out = cli::run_successfully(args.as_slice());
+
+    let stdout = str::from_utf8(&out.stdout).unwrap();
+    assert!(stdout.contains("hash chain of 0 audit log entries"));
+}
@@ -0,0 +1,58 @@
+//! Integration tests for the `yubihsm restore` subcommand
+
+use crate::cli;
+use std::{fs, str};
+use tempfile::TempDir;
+
+/// Run `tmkms yubihsm restore` on an archive with the given contents
+fn restore(archive: &str) -> std::process::Output {
+    let dir = TempDir::new().unwrap();
+    let archive_path = dir.path().join("backup.json");
+    fs::write(&archive_path, archive).unwrap();
+
+    #[allow(unused_mut)]
+    let mut args = vec!["yubihsm", "restore", archive_path.to_str().unwrap()];
+
+    #[cfg(feature = "yubihsm-mock")]
+    args.extend_from_slice(&["-c", super::KMS_CONFIG_PATH]);
+
+    cli::run(args.as_slice())
+}
+
+#[test]
+fn restore_empty_archive_test() {
+    let out = restore(
+        r#"{
+            "version": 1,
+            "serial_number": "0123456789",
+            "created_at": "2020-01-01T00:00:00Z",
+            "wrap_key_id": 1,
+            "objects": [],
+            "skipped": []
+        }"#,
+    );
+
+    assert!(out.status.success());
+
+    let stdout = str::from_utf8(&out.stdout).unwrap();
+    assert!(stdout.contains("Restoring backup of YubiHSM2 0123456789"));
+}
+
+#[test]
+fn restore_unsupported_version_test() {
+    let out = restore(
+        r#"{
+            "version": 2,
+            "serial_number": "0123456789",
+            "created_at": "2020-01-01T00:00:00Z",
+            "wrap_key_id": 1,
+            "objects": [],
+            "skipped": []
+        }"#,
+    );
+
+    assert_eq!(out.status.code().unwrap(), 1);
+
+    let stderr = str::from_utf8(&out.stderr).unwrap();
+    assert!(stderr.contains("unsupported backup archive version: 2"));
+}
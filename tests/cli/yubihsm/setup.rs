@@ -0,0 +1,61 @@
+//! Integration tests for the `yubihsm setup` subcommand
+
+use crate::cli;
+use std::{fs, str};
+use tempfile::TempDir;
+
+#[test]
+fn setup_dry_run_test() {
+    let dir = TempDir::new().unwrap();
+    let spec_path = dir.path().join("spec.toml");
+
+    fs::write(
+        &spec_path,
+        r#"
+        [[role]]
+        name = "monitor"
+        key_id = 5
+        capabilities = ["get-log-entries"]
+        domains = [1, 2]
+        "#,
+    )
+    .unwrap();
+
+    #[allow(unused_mut)]
+    let mut args = vec![
+        "yubihsm",
+        "setup",
+        "--dry-run",
+        "--spec",
+        spec_path.to_str().unwrap(),
+    ];
+
+    #[cfg(feature = "yubihsm-mock")]
+    args.extend_from_slice(&["-c", super::KMS_CONFIG_PATH]);
+
+    let out = cli::run_successfully(args.as_slice());
+
+    let stdout = str::from_utf8(&out.stdout).unwrap();
+    assert!(stdout.contains("- authkey 0x0001 [admin]"));
+    assert!(stdout.contains(
+        "- authkey 0x0005 [monitor]: capabilities: get-log-entries; delegated: (none); domains: 1, 2"
+    ));
+    assert!(!stdout.contains("operator"));
+}
+
+#[test]
+fn setup_verify_test() {
+    #[allow(unused_mut)]
+    let mut args = vec!["yubihsm", "setup", "--verify"];
+
+    #[cfg(feature = "yubihsm-mock")]
+    args.extend_from_slice(&["-c", super::KMS_CONFIG_PATH]);
+
+    // The MockHSM is in its factory-default state
+    let out = cli::run(args.as_slice());
+    assert_eq!(out.status.code().unwrap(), 1);
+
+    let stderr = str::from_utf8(&out.stderr).unwrap();
+    assert!(stderr.contains("missing authentication key 0x0002 (operator)"));
+    assert!(stderr.contains("missing wrap key 0x0001 (primary)"));
+}
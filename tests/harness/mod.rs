@@ -0,0 +1,199 @@
+//! Mock validator harness.
+//!
+//! Runs a KMS session in-process (in a background thread) and accepts its
+//! connection the way a validator would: by acting as the server side of a
+//! SecretConnection. Tests can then send privval requests and check the
+//! responses without needing a prebuilt `tmkms` executable.
+
+use prost_amino::Message;
+use signatory::{
+    ed25519,
+    encoding::Encode,
+    public_key::PublicKeyed,
+    signature::{Signature, Verifier},
+};
+use signatory_dalek::{Ed25519Signer, Ed25519Verifier};
+use std::{
+    io::{Read, Write},
+    net::{TcpListener, TcpStream},
+    path::PathBuf,
+    thread,
+};
+use tempfile::TempDir;
+use tendermint::{amino_types::*, chain, net, TendermintKey};
+use tmkms::{
+    chain::{Chain, REGISTRY},
+    config::{chain::ChainConfig, SecretKeyConfig, ValidatorConfig},
+    connection::{secret_connection::SecretConnection, tcp},
+    error::Error,
+    keyring::{self, ed25519::Signer, SecretKeyEncoding, SigningProvider},
+};
+
+/// Validator side of a loopback connection to an in-process KMS session
+pub struct MockValidator {
+    /// Chain ID the KMS session is signing for
+    pub chain_id: chain::Id,
+
+    /// Consensus public key the KMS is signing with
+    pub consensus_key: ed25519::PublicKey,
+
+    /// SecretConnection to the KMS
+    connection: SecretConnection<TcpStream>,
+
+    /// KMS session running in the background
+    session: thread::JoinHandle<Result<(), Error>>,
+
+    /// Directory containing the KMS's identity key and chain state
+    _tempdir: TempDir,
+}
+
+impl MockValidator {
+    /// Register a chain with a freshly generated signing key, start a KMS
+    /// session for it, and accept the session's connection.
+    ///
+    /// Each call needs a unique `chain_id` as the chain registry is global.
+    pub fn start(chain_id: &str) -> Self {
+        Self::start_with_secret_key(chain_id, SecretKeyConfig::Path)
+    }
+
+    /// Start a KMS session whose identity key is configured by `secret_key`,
+    /// given the path to a Base64-encoded identity key file
+    pub fn start_with_secret_key(
+        chain_id: &str,
+        secret_key: impl FnOnce(PathBuf) -> SecretKeyConfig,
+    ) -> Self {
+        let tempdir = TempDir::new().unwrap();
+        let chain_id = chain_id.parse::<chain::Id>().unwrap();
+        let consensus_key = register_chain(chain_id, &tempdir);
+
+        // Identity keys of both ends of the SecretConnection
+        let kms_secret_key_path = tempdir.path().join("secret_connection.key");
+        let kms_secret_key = ed25519::Seed::generate();
+        kms_secret_key
+            .encode_to_file(&kms_secret_key_path, &SecretKeyEncoding::default())
+            .unwrap();
+
+        let validator_signer = Ed25519Signer::from(&ed25519::Seed::generate());
+        let validator_public_key = validator_signer.public_key().unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+
+        let config = ValidatorConfig {
+            addr: net::Address::Tcp {
+                peer_id: Some(peer_id(validator_public_key)),
+                host: "127.0.0.1".to_owned(),
+                port: listener.local_addr().unwrap().port(),
+            },
+            chain_id,
+            reconnect: false,
+            timeout: None,
+            secret_key: Some(secret_key(kms_secret_key_path)),
+            auto_generate_identity: false,
+            max_height: None,
+            peer_credentials: None,
+            secret_connection: None,
+        };
+
+        let session = thread::spawn(move || tmkms::client::run_client(config));
+
+        let connection = tcp::accept_secret_connection(
+            &listener,
+            &validator_public_key,
+            &validator_signer,
+            &Some(peer_id(
+                Ed25519Signer::from(&kms_secret_key).public_key().unwrap(),
+            )),
+            None,
+        )
+        .unwrap();
+
+        Self {
+            chain_id,
+            consensus_key,
+            connection,
+            session,
+            _tempdir: tempdir,
+        }
+    }
+
+    /// Send a request to the KMS and decode its response
+    pub fn request<Req, Resp>(&mut self, request: &Req) -> Resp
+    where
+        Req: Message,
+        Resp: Message + Default,
+    {
+        let mut buf = vec![];
+        request.encode(&mut buf).unwrap();
+        self.connection.write_all(&buf).unwrap();
+
+        let mut resp_buf = vec![0u8; tmkms::rpc::MAX_MSG_LEN];
+        let resp_len = self.connection.read(&mut resp_buf).unwrap();
+        Resp::decode(&resp_buf[..resp_len]).expect("error decoding response")
+    }
+
+    /// Ask the KMS to sign a vote
+    pub fn sign_vote(&mut self, vote: vote::Vote) -> vote::SignedVoteResponse {
+        self.request(&vote::SignVoteRequest { vote: Some(vote) })
+    }
+
+    /// Ask the KMS to sign a proposal
+    pub fn sign_proposal(
+        &mut self,
+        proposal: proposal::Proposal,
+    ) -> proposal::SignedProposalResponse {
+        self.request(&proposal::SignProposalRequest {
+            proposal: Some(proposal),
+        })
+    }
+
+    /// Verify a signature produced by the KMS over the given signable message
+    pub fn verify(&self, msg: &impl SignableMsg, signature: &[u8]) {
+        let mut sign_bytes = vec![];
+        msg.sign_bytes(self.chain_id, &mut sign_bytes).unwrap();
+
+        Ed25519Verifier::from(&self.consensus_key)
+            .verify(
+                &sign_bytes,
+                &ed25519::Signature::from_bytes(signature).unwrap(),
+            )
+            .expect("invalid signature from KMS");
+    }
+
+    /// Close the connection and wait for the KMS session to exit
+    pub fn shutdown(self) -> Result<(), Error> {
+        drop(self.connection);
+        self.session.join().unwrap()
+    }
+}
+
+/// Register a chain whose keyring contains a newly generated softsign key,
+/// returning the key's public key
+fn register_chain(chain_id: chain::Id, tempdir: &TempDir) -> ed25519::PublicKey {
+    let chain_config = ChainConfig {
+        id: chain_id,
+        key_format: keyring::Format::Hex,
+        state_file: Some(tempdir.path().join("priv_validator_state.json")),
+        state_hook: None,
+    };
+
+    let signer = Ed25519Signer::from(&ed25519::Seed::generate());
+    let public_key = signer.public_key().unwrap();
+
+    let mut chain = Chain::from_config(&chain_config).unwrap();
+    chain
+        .keyring
+        .add(Signer::new(
+            SigningProvider::SoftSign,
+            TendermintKey::ConsensusKey(public_key.into()),
+            Box::new(signer),
+        ))
+        .unwrap();
+
+    REGISTRY.register(chain).unwrap();
+
+    public_key
+}
+
+/// Compute the node ID for a SecretConnection identity key
+fn peer_id(public_key: ed25519::PublicKey) -> tendermint::node::Id {
+    tmkms::connection::secret_connection::PublicKey::from(public_key).peer_id()
+}
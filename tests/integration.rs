@@ -12,13 +12,12 @@ use signatory::{
 };
 use signatory_dalek::{Ed25519Signer, Ed25519Verifier};
 use std::{
-    fs,
     io::{self, Cursor, Read, Write},
     net::{TcpListener, TcpStream},
     os::unix::net::{UnixListener, UnixStream},
     process::{Child, Command},
 };
-use tempfile::NamedTempFile;
+use tempfile::{NamedTempFile, TempDir};
 use tendermint::amino_types::{self, *};
 use tmkms::{
     connection::{
@@ -85,6 +84,9 @@ struct KmsProcess {
 
     /// A socket to KMS process
     socket: KmsSocket,
+
+    /// Directory containing this KMS's chain state file
+    _state_dir: TempDir,
 }
 
 impl KmsProcess {
@@ -92,7 +94,8 @@ impl KmsProcess {
     pub fn create_tcp() -> Self {
         // Generate a random port and a config file
         let port: u16 = rand::thread_rng().gen_range(60000, 65535);
-        let config = KmsProcess::create_tcp_config(port);
+        let state_dir = TempDir::new().unwrap();
+        let config = KmsProcess::create_tcp_config(port, &state_dir);
 
         // Listen on a random port
         let listener = TcpListener::bind(format!("{}:{}", "127.0.0.1", port)).unwrap();
@@ -104,6 +107,7 @@ impl KmsProcess {
         Self {
             process: process,
             socket: KmsSocket::TCP(socket),
+            _state_dir: state_dir,
         }
     }
 
@@ -114,7 +118,8 @@ impl KmsProcess {
         let letter: char = rng.gen_range(b'a', b'z') as char;
         let number: u32 = rng.gen_range(0, 999999);
         let socket_path = format!("/tmp/tmkms-{}{:06}.sock", letter, number);
-        let config = KmsProcess::create_unix_config(&socket_path);
+        let state_dir = TempDir::new().unwrap();
+        let config = KmsProcess::create_unix_config(&socket_path, &state_dir);
 
         // Start listening for connections via the Unix socket
         let listener = UnixListener::bind(socket_path).unwrap();
@@ -127,11 +132,12 @@ impl KmsProcess {
         Self {
             process: process,
             socket: KmsSocket::UNIX(socket),
+            _state_dir: state_dir,
         }
     }
 
     /// Create a config file for a TCP KMS and return its path
-    fn create_tcp_config(port: u16) -> NamedTempFile {
+    fn create_tcp_config(port: u16, state_dir: &TempDir) -> NamedTempFile {
         let mut config_file = NamedTempFile::new().unwrap();
         let (pub_key, _) = test_key();
         let peer_id = secret_connection::PublicKey::from(pub_key).peer_id();
@@ -142,6 +148,7 @@ impl KmsProcess {
             [[chain]]
             id = "test_chain_id"
             key_format = {{ type = "bech32", account_key_prefix = "cosmospub", consensus_key_prefix = "cosmosvalconspub" }}
+            state_file = "{}"
 
             [[validator]]
             addr = "tcp://{}@127.0.0.1:{}"
@@ -155,7 +162,10 @@ impl KmsProcess {
             key_format = "base64"
             path = "{}"
         "#,
-            &peer_id.to_string(), port, SIGNING_KEY_PATH
+            state_dir.path().join("priv_validator_state.json").display(),
+            &peer_id.to_string(),
+            port,
+            SIGNING_KEY_PATH
         )
         .unwrap();
 
@@ -163,7 +173,7 @@ impl KmsProcess {
     }
 
     /// Create a config file for a UNIX KMS and return its path
-    fn create_unix_config(socket_path: &str) -> NamedTempFile {
+    fn create_unix_config(socket_path: &str, state_dir: &TempDir) -> NamedTempFile {
         let mut config_file = NamedTempFile::new().unwrap();
         writeln!(
             config_file,
@@ -171,6 +181,7 @@ impl KmsProcess {
             [[chain]]
             id = "test_chain_id"
             key_format = {{ type = "bech32", account_key_prefix = "cosmospub", consensus_key_prefix = "cosmosvalconspub" }}
+            state_file = "{}"
 
             [[validator]]
             addr = "unix://{}"
@@ -183,7 +194,9 @@ impl KmsProcess {
             key_format = "base64"
             path = "{}"
         "#,
-            socket_path, SIGNING_KEY_PATH
+            state_dir.path().join("priv_validator_state.json").display(),
+            socket_path,
+            SIGNING_KEY_PATH
         )
         .unwrap();
 
@@ -244,13 +257,6 @@ impl Drop for ProtocolTester {
     fn drop(&mut self) {
         self.tcp_device.process.kill().unwrap();
         self.unix_device.process.kill().unwrap();
-
-        match fs::remove_file("test_chain_id_priv_validator_state.json") {
-            Err(ref e) if e.kind() != io::ErrorKind::NotFound => {
-                panic!("{}", e);
-            }
-            _ => (),
-        }
     }
 }
 
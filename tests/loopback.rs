@@ -0,0 +1,212 @@
+//! End-to-end tests which run a KMS session in-process against a mock validator
+
+#![cfg(feature = "softsign")]
+
+mod harness;
+
+use self::harness::MockValidator;
+use serde_json::json;
+use std::{
+    io::{Read, Write},
+    net::{TcpListener, TcpStream},
+};
+use tendermint::amino_types::{self, *};
+use tmkms::{config::status::StatusConfig, error::ErrorKind};
+
+/// Create a prevote for the given height/round and block hash
+fn prevote(height: i64, round: i64, block_hash: &[u8]) -> vote::Vote {
+    vote::Vote {
+        vote_type: SignedMsgType::PreVote.to_u32(),
+        height,
+        round,
+        timestamp: Some(TimeMsg {
+            seconds: 1_518_332_962,
+            nanos: 765_000_000,
+        }),
+        block_id: Some(BlockId {
+            hash: block_hash.to_vec(),
+            parts_header: Some(PartsSetHeader {
+                total: 1,
+                hash: b"parts_hash0000000000000000000000".to_vec(),
+            }),
+        }),
+        validator_address: vec![
+            0xa3, 0xb2, 0xcc, 0xdd, 0x71, 0x86, 0xf1, 0x68, 0x5f, 0x21, 0xf2, 0x48, 0x2a, 0xf4,
+            0xfb, 0x34, 0x46, 0xa8, 0x4b, 0x35,
+        ],
+        validator_index: 1,
+        signature: vec![],
+    }
+}
+
+#[test]
+fn handshake_and_ping() {
+    let mut validator = MockValidator::start("loopback-ping");
+    let _: PingResponse = validator.request(&PingRequest {});
+    validator.shutdown().unwrap_err();
+}
+
+#[test]
+fn identity_key_from_provider() {
+    let mut validator = MockValidator::start_with_secret_key("loopback-identity", |path| {
+        serde_json::from_value(json!({
+            "provider": "softsign",
+            "key_format": "base64",
+            "path": path
+        }))
+        .unwrap()
+    });
+
+    let _: PingResponse = validator.request(&PingRequest {});
+}
+
+#[test]
+fn get_public_key() {
+    let mut validator = MockValidator::start("loopback-pubkey");
+    let response: PubKeyResponse = validator.request(&PubKeyRequest {});
+    assert_eq!(
+        response.pub_key_ed25519,
+        validator.consensus_key.as_bytes().to_vec()
+    );
+}
+
+#[test]
+fn sign_proposal() {
+    let mut validator = MockValidator::start("loopback-proposal");
+
+    let proposal = amino_types::proposal::Proposal {
+        msg_type: SignedMsgType::Proposal.to_u32(),
+        height: 12345,
+        round: 1,
+        timestamp: Some(TimeMsg {
+            seconds: 1_518_332_962,
+            nanos: 765_000_000,
+        }),
+        pol_round: -1,
+        block_id: None,
+        signature: vec![],
+    };
+
+    let response = validator.sign_proposal(proposal.clone());
+    assert!(response.err.is_none());
+
+    let signed = response.proposal.expect("no proposal in response");
+    let request = proposal::SignProposalRequest {
+        proposal: Some(proposal),
+    };
+    validator.verify(&request, &signed.signature);
+}
+
+#[test]
+fn sign_vote() {
+    let mut validator = MockValidator::start("loopback-vote");
+
+    let vote = prevote(12345, 2, b"some hash00000000000000000000000");
+    let response = validator.sign_vote(vote.clone());
+    assert!(response.err.is_none());
+
+    let signed = response.vote.expect("no vote in response");
+    let request = vote::SignVoteRequest { vote: Some(vote) };
+    validator.verify(&request, &signed.signature);
+}
+
+#[test]
+fn refuse_double_sign() {
+    let mut validator = MockValidator::start("loopback-double-sign");
+
+    let response = validator.sign_vote(prevote(1, 0, b"some hash00000000000000000000000"));
+    assert!(response.err.is_none());
+
+    // Same height/round/step, different block
+    let response = validator.sign_vote(prevote(1, 0, b"other hash0000000000000000000000"));
+    assert!(response.vote.is_none());
+    assert_eq!(
+        response.err.expect("double sign wasn't refused"),
+        RemoteError::double_sign(1)
+    );
+}
+
+/// Make an HTTP GET request, returning the status line and body
+fn http_get(port: u16, path: &str) -> (String, String) {
+    let mut socket = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    write!(socket, "GET {} HTTP/1.0\r\n\r\n", path).unwrap();
+
+    let mut response = String::new();
+    socket.read_to_string(&mut response).unwrap();
+
+    let status_line = response.lines().next().unwrap().to_owned();
+    let body_start = response.find("\r\n\r\n").unwrap() + 4;
+    let body = response[body_start..].to_owned();
+    (status_line, body)
+}
+
+#[test]
+fn status_endpoint() {
+    let mut validator = MockValidator::start("loopback-status");
+    let _: PingResponse = validator.request(&PingRequest {});
+
+    let port = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+
+    tmkms::status::spawn(&StatusConfig {
+        laddr: format!("tcp://127.0.0.1:{}", port).parse().unwrap(),
+    })
+    .unwrap();
+
+    let (status_line, body) = http_get(port, "/healthz");
+    assert!(status_line.contains("200"), "{}", status_line);
+    assert_eq!(body, "ok\n");
+
+    let (status_line, body) = http_get(port, "/status");
+    assert!(status_line.contains("200"), "{}", status_line);
+
+    let status: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(status["ready"], true);
+
+    let chain = status["chains"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|chain| chain["id"] == "loopback-status")
+        .expect("chain missing from status");
+
+    assert!(chain["last_signed"]["height"].is_string());
+
+    let session = status["sessions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|session| session["chain_id"] == "loopback-status")
+        .expect("session missing from status");
+
+    assert_eq!(session["connected"], true);
+    assert!(session["peer_id"].is_string());
+    assert!(session["last_request"].is_string());
+
+    let (status_line, body) = http_get(port, "/healthz?probe=1");
+    assert!(status_line.contains("200"), "{}", status_line);
+    assert_eq!(body, "ok\n");
+
+    let (status_line, _) = http_get(port, "/status?pretty");
+    assert!(status_line.contains("200"), "{}", status_line);
+
+    let (status_line, _) = http_get(port, "/nonexistent");
+    assert!(status_line.contains("404"), "{}", status_line);
+
+    // An unhealthy provider makes the KMS unready, but it's still alive
+    tmkms::status::register_provider("loopback-unhealthy", || Err(ErrorKind::SigningError.into()));
+
+    let (status_line, body) = http_get(port, "/readyz");
+    assert!(status_line.contains("503"), "{}", status_line);
+    assert!(body.contains("loopback-unhealthy"));
+
+    let (status_line, _) = http_get(port, "/readyz?verbose");
+    assert!(status_line.contains("503"), "{}", status_line);
+
+    let (status_line, body) = http_get(port, "/livez");
+    assert!(status_line.contains("200"), "{}", status_line);
+    assert_eq!(body, "ok\n");
+}